@@ -0,0 +1,1425 @@
+//! Fixed-width integer arithmetic with an explicit overflow policy.
+//!
+//! Every operation exists for each width from `int8_t` to `uint64_t`, and for
+//! the 128-bit [`Int128`] and [`Uint128`], as `<type>_<op>(..., policy, out)`,
+//! for example `i32_add`, `u8_pow` or `u128_mul`, and returns a
+//! [`MathStatus`]. `neg` and `abs` are only provided for the signed
+//! types. What happens when the exact result does not fit is chosen per call
+//! with an [`OverflowPolicy`]; division and remainder by zero always fail
+//! with `MathStatus::DivisionByZero`.
+//!
+//! The exports are spelled out one by one instead of being generated by a
+//! macro, because cbindgen does not expand macros when writing the header.
+
+use std::fmt::Display;
+
+use crate::MathStatus;
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+use crate::int128::{Int128, Uint128};
+
+/// How an integer operation treats a result outside the range of its type.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Return `MathStatus::Overflow` and leave `out` untouched.
+    Checked = 0,
+    /// Write the result reduced modulo 2^bits.
+    Wrapping = 1,
+    /// Write the representable value closest to the result.
+    Saturating = 2,
+    /// Write the wrapped result, and return `MathStatus::Overflow` if it
+    /// differs from the exact one.
+    Overflowing = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+        }
+    }
+}
+
+/// The primitive operations of one integer type, as `(wrapped, overflowed)`
+/// pairs and their saturating counterparts.
+pub(crate) trait Int: Copy + Default + PartialEq + Display {
+    const NAME: &'static str;
+
+    fn overflowing(op: Op, a: Self, b: Self) -> (Self, bool);
+    fn saturating(op: Op, a: Self, b: Self) -> Self;
+    fn overflowing_pow(self, exponent: u32) -> (Self, bool);
+    fn saturating_pow(self, exponent: u32) -> Self;
+}
+
+trait SignedInt: Int {
+    fn overflowing_neg(self) -> (Self, bool);
+    fn saturating_neg(self) -> Self;
+    fn overflowing_abs(self) -> (Self, bool);
+    fn saturating_abs(self) -> Self;
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {$(
+        impl Int for $t {
+            const NAME: &'static str = stringify!($t);
+
+            fn overflowing(op: Op, a: Self, b: Self) -> (Self, bool) {
+                match op {
+                    Op::Add => a.overflowing_add(b),
+                    Op::Sub => a.overflowing_sub(b),
+                    Op::Mul => a.overflowing_mul(b),
+                    Op::Div => a.overflowing_div(b),
+                    // MIN % -1 only overflows in the hardware; the exact
+                    // remainder is 0.
+                    Op::Rem => (a.wrapping_rem(b), false),
+                }
+            }
+
+            fn saturating(op: Op, a: Self, b: Self) -> Self {
+                match op {
+                    Op::Add => a.saturating_add(b),
+                    Op::Sub => a.saturating_sub(b),
+                    Op::Mul => a.saturating_mul(b),
+                    Op::Div => a.saturating_div(b),
+                    Op::Rem => a.wrapping_rem(b),
+                }
+            }
+
+            fn overflowing_pow(self, exponent: u32) -> (Self, bool) {
+                <$t>::overflowing_pow(self, exponent)
+            }
+
+            fn saturating_pow(self, exponent: u32) -> Self {
+                <$t>::saturating_pow(self, exponent)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_signed_int {
+    ($($t:ty),*) => {$(
+        impl SignedInt for $t {
+            fn overflowing_neg(self) -> (Self, bool) {
+                <$t>::overflowing_neg(self)
+            }
+
+            fn saturating_neg(self) -> Self {
+                <$t>::saturating_neg(self)
+            }
+
+            fn overflowing_abs(self) -> (Self, bool) {
+                <$t>::overflowing_abs(self)
+            }
+
+            fn saturating_abs(self) -> Self {
+                <$t>::saturating_abs(self)
+            }
+        }
+    )*};
+}
+
+impl_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+impl_signed_int!(i8, i16, i32, i64, i128);
+
+/// The value to write under a policy, and the error to report once it is
+/// written (only raised by `OverflowPolicy::Overflowing`).
+pub(crate) type Outcome<T> = MathResult<(T, Option<MathError>)>;
+
+pub(crate) fn resolve<T: Int>(
+    policy: OverflowPolicy,
+    (wrapped, overflowed): (T, bool),
+    saturated: impl FnOnce() -> T,
+    describe: impl FnOnce() -> String,
+) -> Outcome<T> {
+    if !overflowed {
+        return Ok((wrapped, None));
+    }
+    let error = || MathError::overflow(format!("{} overflows {}", describe(), T::NAME));
+    match policy {
+        OverflowPolicy::Checked => Err(error()),
+        OverflowPolicy::Wrapping => Ok((wrapped, None)),
+        OverflowPolicy::Saturating => Ok((saturated(), None)),
+        OverflowPolicy::Overflowing => Ok((wrapped, Some(error()))),
+    }
+}
+
+/// Compute an [`Outcome`] inside the panic guard, write its value as its C
+/// representation, then report its deferred error, if any.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `C`.
+unsafe fn emit<T: Int, C: From<T>>(
+    out: *mut C,
+    outcome: impl FnOnce() -> Outcome<T>,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let (value, flag) = outcome()?;
+        unsafe { ffi::write_out(out, "out", C::from(value)) }?;
+        flag.map_or(Ok(()), Err)
+    })
+}
+
+fn binary<T: Int>(op: Op, a: T, b: T, policy: OverflowPolicy) -> Outcome<T> {
+    if matches!(op, Op::Div | Op::Rem) && b == T::default() {
+        return Err(MathError::new(
+            MathStatus::DivisionByZero,
+            format!("{a} {} 0 in {}", op.symbol(), T::NAME),
+        ));
+    }
+    resolve(
+        policy,
+        T::overflowing(op, a, b),
+        || T::saturating(op, a, b),
+        || format!("{a} {} {b}", op.symbol()),
+    )
+}
+
+fn pow<T: Int>(base: T, exponent: u32, policy: OverflowPolicy) -> Outcome<T> {
+    resolve(
+        policy,
+        base.overflowing_pow(exponent),
+        || base.saturating_pow(exponent),
+        || format!("{base}^{exponent}"),
+    )
+}
+
+fn neg<T: SignedInt>(a: T, policy: OverflowPolicy) -> Outcome<T> {
+    resolve(
+        policy,
+        a.overflowing_neg(),
+        || a.saturating_neg(),
+        || format!("-({a})"),
+    )
+}
+
+fn abs<T: SignedInt>(a: T, policy: OverflowPolicy) -> Outcome<T> {
+    resolve(
+        policy,
+        a.overflowing_abs(),
+        || a.saturating_abs(),
+        || format!("|{a}|"),
+    )
+}
+
+/// Compute `a + b` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_add(a: i8, b: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_sub(a: i8, b: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_mul(a: i8, b: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b`, truncated towards zero, for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_div(a: i8, b: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b`, with the sign of `a`, for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_rem(a: i8, b: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_pow(
+    base: i8,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut i8,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `-a` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_neg(a: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || neg(a, policy)) }
+}
+
+/// Compute `|a|` for `int8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i8_abs(a: i8, policy: OverflowPolicy, out: *mut i8) -> MathStatus {
+    unsafe { emit(out, || abs(a, policy)) }
+}
+
+/// Compute `a + b` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_add(
+    a: i16,
+    b: i16,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_sub(
+    a: i16,
+    b: i16,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_mul(
+    a: i16,
+    b: i16,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b`, truncated towards zero, for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_div(
+    a: i16,
+    b: i16,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b`, with the sign of `a`, for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_rem(
+    a: i16,
+    b: i16,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_pow(
+    base: i16,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut i16,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `-a` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_neg(a: i16, policy: OverflowPolicy, out: *mut i16) -> MathStatus {
+    unsafe { emit(out, || neg(a, policy)) }
+}
+
+/// Compute `|a|` for `int16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i16_abs(a: i16, policy: OverflowPolicy, out: *mut i16) -> MathStatus {
+    unsafe { emit(out, || abs(a, policy)) }
+}
+
+/// Compute `a + b` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_add(
+    a: i32,
+    b: i32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_sub(
+    a: i32,
+    b: i32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_mul(
+    a: i32,
+    b: i32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b`, truncated towards zero, for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_div(
+    a: i32,
+    b: i32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b`, with the sign of `a`, for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_rem(
+    a: i32,
+    b: i32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_pow(
+    base: i32,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `-a` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_neg(a: i32, policy: OverflowPolicy, out: *mut i32) -> MathStatus {
+    unsafe { emit(out, || neg(a, policy)) }
+}
+
+/// Compute `|a|` for `int32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i32_abs(a: i32, policy: OverflowPolicy, out: *mut i32) -> MathStatus {
+    unsafe { emit(out, || abs(a, policy)) }
+}
+
+/// Compute `a + b` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_add(
+    a: i64,
+    b: i64,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_sub(
+    a: i64,
+    b: i64,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_mul(
+    a: i64,
+    b: i64,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b`, truncated towards zero, for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_div(
+    a: i64,
+    b: i64,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b`, with the sign of `a`, for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_rem(
+    a: i64,
+    b: i64,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_pow(
+    base: i64,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `-a` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_neg(a: i64, policy: OverflowPolicy, out: *mut i64) -> MathStatus {
+    unsafe { emit(out, || neg(a, policy)) }
+}
+
+/// Compute `|a|` for `int64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i64_abs(a: i64, policy: OverflowPolicy, out: *mut i64) -> MathStatus {
+    unsafe { emit(out, || abs(a, policy)) }
+}
+
+/// Compute `a + b` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_add(a: u8, b: u8, policy: OverflowPolicy, out: *mut u8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_sub(a: u8, b: u8, policy: OverflowPolicy, out: *mut u8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_mul(a: u8, b: u8, policy: OverflowPolicy, out: *mut u8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_div(a: u8, b: u8, policy: OverflowPolicy, out: *mut u8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_rem(a: u8, b: u8, policy: OverflowPolicy, out: *mut u8) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `uint8_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u8_pow(
+    base: u8,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut u8,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `a + b` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_add(
+    a: u16,
+    b: u16,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_sub(
+    a: u16,
+    b: u16,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_mul(
+    a: u16,
+    b: u16,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_div(
+    a: u16,
+    b: u16,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_rem(
+    a: u16,
+    b: u16,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `uint16_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint16_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u16_pow(
+    base: u16,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut u16,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `a + b` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_add(
+    a: u32,
+    b: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_sub(
+    a: u32,
+    b: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_mul(
+    a: u32,
+    b: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_div(
+    a: u32,
+    b: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_rem(
+    a: u32,
+    b: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `uint32_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u32_pow(
+    base: u32,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut u32,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `a + b` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_add(
+    a: u64,
+    b: u64,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Add, a, b, policy)) }
+}
+
+/// Compute `a - b` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_sub(
+    a: u64,
+    b: u64,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Sub, a, b, policy)) }
+}
+
+/// Compute `a * b` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_mul(
+    a: u64,
+    b: u64,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Mul, a, b, policy)) }
+}
+
+/// Compute `a / b` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_div(
+    a: u64,
+    b: u64,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Div, a, b, policy)) }
+}
+
+/// Compute `a % b` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_rem(
+    a: u64,
+    b: u64,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || binary(Op::Rem, a, b, policy)) }
+}
+
+/// Compute `base` raised to `exponent` for `uint64_t` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u64_pow(
+    base: u64,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut u64,
+) -> MathStatus {
+    unsafe { emit(out, || pow(base, exponent, policy)) }
+}
+
+/// Compute `a + b` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_add(
+    a: Int128,
+    b: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Add, i128::from(a), i128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a - b` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_sub(
+    a: Int128,
+    b: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Sub, i128::from(a), i128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a * b` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_mul(
+    a: Int128,
+    b: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Mul, i128::from(a), i128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a / b`, truncated towards zero, for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_div(
+    a: Int128,
+    b: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Div, i128::from(a), i128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a % b`, with the sign of `a`, for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_rem(
+    a: Int128,
+    b: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Rem, i128::from(a), i128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `base` raised to `exponent` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_pow(
+    base: Int128,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe { emit(out, || pow(i128::from(base), exponent, policy)) }
+}
+
+/// Compute `-a` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_neg(
+    a: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe { emit(out, || neg(i128::from(a), policy)) }
+}
+
+/// Compute `|a|` for `Int128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Int128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn i128_abs(
+    a: Int128,
+    policy: OverflowPolicy,
+    out: *mut Int128,
+) -> MathStatus {
+    unsafe { emit(out, || abs(i128::from(a), policy)) }
+}
+
+/// Compute `a + b` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_add(
+    a: Uint128,
+    b: Uint128,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Add, u128::from(a), u128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a - b` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_sub(
+    a: Uint128,
+    b: Uint128,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Sub, u128::from(a), u128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a * b` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_mul(
+    a: Uint128,
+    b: Uint128,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Mul, u128::from(a), u128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a / b` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_div(
+    a: Uint128,
+    b: Uint128,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Div, u128::from(a), u128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `a % b` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_rem(
+    a: Uint128,
+    b: Uint128,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe {
+        emit(out, || {
+            binary(Op::Rem, u128::from(a), u128::from(b), policy)
+        })
+    }
+}
+
+/// Compute `base` raised to `exponent` for `Uint128` under `policy`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn u128_pow(
+    base: Uint128,
+    exponent: u32,
+    policy: OverflowPolicy,
+    out: *mut Uint128,
+) -> MathStatus {
+    unsafe { emit(out, || pow(u128::from(base), exponent, policy)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    use OverflowPolicy::{Checked, Overflowing, Saturating, Wrapping};
+
+    fn run<T: Copy + Default>(f: impl FnOnce(*mut T) -> MathStatus) -> (MathStatus, T) {
+        let mut out = T::default();
+        let status = f(&mut out);
+        (status, out)
+    }
+
+    #[test]
+    fn test_in_range_results_ignore_the_policy() {
+        for policy in [Checked, Wrapping, Saturating, Overflowing] {
+            assert_eq!(
+                run(|out| unsafe { i32_add(2, 3, policy, out) }),
+                (MathStatus::Ok, 5)
+            );
+            assert_eq!(
+                run(|out| unsafe { u8_sub(9, 4, policy, out) }),
+                (MathStatus::Ok, 5)
+            );
+            assert_eq!(
+                run(|out| unsafe { i64_div(-7, 2, policy, out) }),
+                (MathStatus::Ok, -3)
+            );
+            assert_eq!(
+                run(|out| unsafe { i16_rem(-7, 2, policy, out) }),
+                (MathStatus::Ok, -1)
+            );
+            assert_eq!(
+                run(|out| unsafe { u64_pow(3, 40, policy, out) }),
+                (MathStatus::Ok, 3u64.pow(40))
+            );
+            assert_eq!(
+                run(|out| unsafe { i8_abs(-127, policy, out) }),
+                (MathStatus::Ok, 127)
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        assert_eq!(
+            run(|out| unsafe { i32_add(i32::MAX, 1, Checked, out) }),
+            (MathStatus::Overflow, 0)
+        );
+        let text = crate::ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "overflow: 2147483647 + 1 overflows i32");
+        assert_eq!(
+            run(|out| unsafe { i32_add(i32::MAX, 1, Wrapping, out) }),
+            (MathStatus::Ok, i32::MIN)
+        );
+        assert_eq!(error::math_last_error_code(), MathStatus::Ok);
+        assert_eq!(
+            run(|out| unsafe { i32_add(i32::MAX, 1, Saturating, out) }),
+            (MathStatus::Ok, i32::MAX)
+        );
+        assert_eq!(
+            run(|out| unsafe { i32_add(i32::MAX, 1, Overflowing, out) }),
+            (MathStatus::Overflow, i32::MIN)
+        );
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+
+        assert_eq!(
+            run(|out| unsafe { u8_sub(1, 2, Wrapping, out) }),
+            (MathStatus::Ok, 255)
+        );
+        assert_eq!(
+            run(|out| unsafe { u8_sub(1, 2, Saturating, out) }),
+            (MathStatus::Ok, 0)
+        );
+        assert_eq!(
+            run(|out| unsafe { u16_mul(300, 300, Wrapping, out) }),
+            (MathStatus::Ok, 24_464)
+        );
+        assert_eq!(
+            run(|out| unsafe { i16_mul(-300, 300, Saturating, out) }),
+            (MathStatus::Ok, i16::MIN)
+        );
+        assert_eq!(
+            run(|out| unsafe { u32_pow(2, 32, Saturating, out) }),
+            (MathStatus::Ok, u32::MAX)
+        );
+        assert_eq!(
+            run(|out| unsafe { i64_pow(-2, 63, Checked, out) }),
+            (MathStatus::Ok, i64::MIN)
+        );
+        assert_eq!(
+            run(|out| unsafe { i64_pow(-2, 64, Overflowing, out) }),
+            (MathStatus::Overflow, 0)
+        );
+        assert_eq!(
+            run(|out| unsafe { i8_neg(i8::MIN, Saturating, out) }),
+            (MathStatus::Ok, i8::MAX)
+        );
+        assert_eq!(
+            run(|out| unsafe { i8_abs(i8::MIN, Wrapping, out) }),
+            (MathStatus::Ok, i8::MIN)
+        );
+        assert_eq!(
+            run(|out| unsafe { i64_abs(i64::MIN, Checked, out) }),
+            (MathStatus::Overflow, 0)
+        );
+    }
+
+    #[test]
+    fn test_division_edge_cases() {
+        for policy in [Checked, Wrapping, Saturating, Overflowing] {
+            assert_eq!(
+                run(|out| unsafe { u32_div(1, 0, policy, out) }),
+                (MathStatus::DivisionByZero, 0)
+            );
+            assert_eq!(
+                run(|out| unsafe { i8_rem(1, 0, policy, out) }),
+                (MathStatus::DivisionByZero, 0)
+            );
+        }
+        assert_eq!(
+            run(|out| unsafe { i32_div(i32::MIN, -1, Checked, out) }),
+            (MathStatus::Overflow, 0)
+        );
+        assert_eq!(
+            run(|out| unsafe { i32_div(i32::MIN, -1, Wrapping, out) }),
+            (MathStatus::Ok, i32::MIN)
+        );
+        assert_eq!(
+            run(|out| unsafe { i32_div(i32::MIN, -1, Saturating, out) }),
+            (MathStatus::Ok, i32::MAX)
+        );
+        assert_eq!(
+            run(|out| unsafe { i32_rem(i32::MIN, -1, Saturating, out) }),
+            (MathStatus::Ok, 0)
+        );
+        assert_eq!(
+            run(|out| unsafe { i32_rem(i32::MIN, -1, Overflowing, out) }),
+            (MathStatus::Ok, 0)
+        );
+        // `Checked` writes the exact remainder.
+        let mut out = 7;
+        let status = unsafe { i32_rem(i32::MIN, -1, Checked, &mut out) };
+        assert_eq!((status, out), (MathStatus::Ok, 0));
+        assert_eq!(
+            run(|out| unsafe { i64_rem(i64::MIN, -1, Checked, out) }),
+            (MathStatus::Ok, 0)
+        );
+    }
+
+    #[test]
+    fn test_null_out_is_rejected() {
+        let status = unsafe { i32_add(1, 2, Checked, std::ptr::null_mut()) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_128_bit_operations() {
+        let max = Uint128::from(u128::MAX);
+        let one = Uint128::from(1u128);
+        assert_eq!(
+            run(|out| unsafe { u128_add(max, one, Wrapping, out) }),
+            (MathStatus::Ok, Uint128::default())
+        );
+        assert_eq!(
+            run(|out| unsafe { u128_add(max, one, Checked, out) }),
+            (MathStatus::Overflow, Uint128::default())
+        );
+        let text = crate::ffi::take_string(error::math_last_error_message());
+        assert_eq!(
+            text,
+            "overflow: 340282366920938463463374607431768211455 + 1 overflows u128"
+        );
+        let lo = Uint128::from(u64::MAX as u128);
+        assert_eq!(
+            run(|out| unsafe { u128_mul(lo, lo, Checked, out) }),
+            (
+                MathStatus::Ok,
+                Uint128 {
+                    hi: u64::MAX - 1,
+                    lo: 1
+                }
+            )
+        );
+        assert_eq!(
+            run(|out| unsafe { u128_pow(Uint128::from(3u128), 81, Saturating, out) }),
+            (MathStatus::Ok, max)
+        );
+
+        let min = Int128::from(i128::MIN);
+        let minus_one = Int128::from(-1i128);
+        assert_eq!(
+            run(|out| unsafe { i128_div(min, minus_one, Saturating, out) }),
+            (MathStatus::Ok, Int128::from(i128::MAX))
+        );
+        assert_eq!(
+            run(|out| unsafe { i128_neg(min, Overflowing, out) }),
+            (MathStatus::Overflow, min)
+        );
+        assert_eq!(
+            run(|out| unsafe { i128_sub(minus_one, Int128::from(1i128 << 100), Checked, out) }),
+            (MathStatus::Ok, Int128::from(-1 - (1i128 << 100)))
+        );
+        assert_eq!(
+            run(|out| unsafe { i128_rem(min, Int128::default(), Wrapping, out) }),
+            (MathStatus::DivisionByZero, Int128::default())
+        );
+    }
+}
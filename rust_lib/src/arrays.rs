@@ -0,0 +1,962 @@
+//! Element-wise arithmetic over caller-owned arrays.
+//!
+//! Each export takes its input arrays, an output array and a common length,
+//! and computes `out[i] = a[i] op b[i]` (or `a[i] * b[i] + c[i]` for `fma`)
+//! for `int32_t`, `int64_t`, `float` and `double`. The loops are plain
+//! element-wise maps that the compiler vectorises. `out` may be the same
+//! array as one of the inputs, but must not partially overlap any of them.
+//!
+//! Integer `add`, `sub`, `mul`, `div` and `fma` take an [`OverflowPolicy`],
+//! applied to every element as in `arith`: with `Checked` nothing is
+//! written unless every element fits, and with `Overflowing` all elements
+//! are written and `MathStatus::Overflow` reports that at least one wrapped.
+//! Integer division by zero fails before anything is written. Floating-point
+//! operations follow IEEE 754; `min` and `max` return the other operand when
+//! one of them is NaN.
+
+use std::fmt::Display;
+
+use crate::error::{MathError, MathResult};
+use crate::{MathStatus, OverflowPolicy, ffi};
+
+/// A caller-owned input array, read one element at a time so that it may
+/// alias the output.
+#[derive(Clone, Copy)]
+struct Input<T>(*const T);
+
+impl<T: Copy> Input<T> {
+    /// # Safety
+    ///
+    /// `ptr` must be null or valid for reading `len` elements.
+    unsafe fn new(ptr: *const T, len: usize, name: &str) -> MathResult<Self> {
+        if len > 0 && ptr.is_null() {
+            return Err(MathError::invalid_argument(format!("`{name}` is null")));
+        }
+        Ok(Input(ptr))
+    }
+
+    /// # Safety
+    ///
+    /// `i` must be below the length the input was created with.
+    unsafe fn get(self, i: usize) -> T {
+        unsafe { self.0.add(i).read() }
+    }
+}
+
+/// Write `f(i)` to `out[i]` for every `i < len`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing `len` elements, and `f` may only
+/// read inputs valid for `len` elements.
+unsafe fn fill<T>(out: *mut T, len: usize, f: impl Fn(usize) -> T) -> MathResult<()> {
+    if len > 0 && out.is_null() {
+        return Err(MathError::invalid_argument("`out` is null"));
+    }
+    for i in 0..len {
+        unsafe { out.add(i).write(f(i)) };
+    }
+    Ok(())
+}
+
+/// Write an integer operation under `policy`: `overflowing(i)` gives the
+/// wrapped value of element `i` and whether it overflowed, `saturating(i)`
+/// its clamped value, and `describe(i)` names it in error messages.
+///
+/// # Safety
+///
+/// As for [`fill`].
+unsafe fn fill_with_policy<T: Copy>(
+    out: *mut T,
+    len: usize,
+    policy: OverflowPolicy,
+    overflowing: impl Fn(usize) -> (T, bool),
+    saturating: impl Fn(usize) -> T,
+    describe: impl Fn(usize) -> String,
+) -> MathResult<()> {
+    let overflow = |i: usize| MathError::overflow(format!("element {i}: {}", describe(i)));
+    match policy {
+        OverflowPolicy::Checked => {
+            let any = (0..len).fold(false, |any, i| any | overflowing(i).1);
+            if any {
+                let first = (0..len)
+                    .find(|&i| overflowing(i).1)
+                    .expect("an element overflowed");
+                return Err(overflow(first));
+            }
+            unsafe { fill(out, len, |i| overflowing(i).0) }
+        }
+        OverflowPolicy::Wrapping => unsafe { fill(out, len, |i| overflowing(i).0) },
+        OverflowPolicy::Saturating => unsafe { fill(out, len, saturating) },
+        OverflowPolicy::Overflowing => {
+            // Find the first wrapped element before `out` is written, since
+            // `out` may alias an input.
+            let first = (0..len).find(|&i| overflowing(i).1).map(overflow);
+            unsafe { fill(out, len, |i| overflowing(i).0) }?;
+            first.map_or(Ok(()), Err)
+        }
+    }
+}
+
+/// `out[i] = a[i] op b[i]` for an integer type under `policy`; division
+/// (`divides`) first checks that no divisor is zero.
+///
+/// # Safety
+///
+/// `a` and `b` must be null or valid for reading `len` elements, and `out`
+/// null or valid for writing `len` elements.
+unsafe fn int_binary<T: Copy + Display + Default + PartialEq>(
+    (a, b, out, len): (*const T, *const T, *mut T, usize),
+    policy: OverflowPolicy,
+    (symbol, divides): (&str, bool),
+    overflowing: impl Fn(T, T) -> (T, bool),
+    saturating: impl Fn(T, T) -> T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { Input::new(a, len, "a") }?;
+        let b = unsafe { Input::new(b, len, "b") }?;
+        let zero = if divides {
+            (0..len).find(|&i| unsafe { b.get(i) } == T::default())
+        } else {
+            None
+        };
+        if let Some(i) = zero {
+            return Err(MathError::new(
+                MathStatus::DivisionByZero,
+                format!("element {i}: division by zero"),
+            ));
+        }
+        unsafe {
+            fill_with_policy(
+                out,
+                len,
+                policy,
+                |i| overflowing(a.get(i), b.get(i)),
+                |i| saturating(a.get(i), b.get(i)),
+                |i| format!("{} {symbol} {} overflows", a.get(i), b.get(i)),
+            )
+        }
+    })
+}
+
+/// Integer `out[i] = a[i] * b[i] + c[i]`, rounded once: `exact` gives the
+/// wrapped result and whether the exact one overflowed, `saturating` the
+/// clamped exact result.
+///
+/// # Safety
+///
+/// As for [`int_binary`], with `c` like `a`.
+unsafe fn int_fma<T: Copy + Display>(
+    (a, b, c, out, len): (*const T, *const T, *const T, *mut T, usize),
+    policy: OverflowPolicy,
+    exact: impl Fn(T, T, T) -> (T, bool),
+    saturating: impl Fn(T, T, T) -> T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { Input::new(a, len, "a") }?;
+        let b = unsafe { Input::new(b, len, "b") }?;
+        let c = unsafe { Input::new(c, len, "c") }?;
+        unsafe {
+            fill_with_policy(
+                out,
+                len,
+                policy,
+                |i| exact(a.get(i), b.get(i), c.get(i)),
+                |i| saturating(a.get(i), b.get(i), c.get(i)),
+                |i| format!("{} * {} + {} overflows", a.get(i), b.get(i), c.get(i)),
+            )
+        }
+    })
+}
+
+/// `out[i] = f(a[i], b[i])` for an operation that cannot fail.
+///
+/// # Safety
+///
+/// As for [`int_binary`].
+unsafe fn map2<T: Copy>(
+    (a, b, out, len): (*const T, *const T, *mut T, usize),
+    f: impl Fn(T, T) -> T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { Input::new(a, len, "a") }?;
+        let b = unsafe { Input::new(b, len, "b") }?;
+        unsafe { fill(out, len, |i| f(a.get(i), b.get(i))) }
+    })
+}
+
+/// `out[i] = f(a[i], b[i], c[i])` for an operation that cannot fail.
+///
+/// # Safety
+///
+/// As for [`int_fma`].
+unsafe fn map3<T: Copy>(
+    (a, b, c, out, len): (*const T, *const T, *const T, *mut T, usize),
+    f: impl Fn(T, T, T) -> T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { Input::new(a, len, "a") }?;
+        let b = unsafe { Input::new(b, len, "b") }?;
+        let c = unsafe { Input::new(c, len, "c") }?;
+        unsafe { fill(out, len, |i| f(a.get(i), b.get(i), c.get(i))) }
+    })
+}
+
+fn overflowing_fma_i32(a: i32, b: i32, c: i32) -> (i32, bool) {
+    let exact = a as i64 * b as i64 + c as i64;
+    (exact as i32, exact != exact as i32 as i64)
+}
+
+fn saturating_fma_i32(a: i32, b: i32, c: i32) -> i32 {
+    (a as i64 * b as i64 + c as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+fn overflowing_fma_i64(a: i64, b: i64, c: i64) -> (i64, bool) {
+    let exact = a as i128 * b as i128 + c as i128;
+    (exact as i64, exact != exact as i64 as i128)
+}
+
+fn saturating_fma_i64(a: i64, b: i64, c: i64) -> i64 {
+    (a as i128 * b as i128 + c as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Compute `out[i] = a[i] + b[i]` for `int32_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_add_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("+", false),
+            i32::overflowing_add,
+            i32::saturating_add,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] - b[i]` for `int32_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sub_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("-", false),
+            i32::overflowing_sub,
+            i32::saturating_sub,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] * b[i]` for `int32_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_mul_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("*", false),
+            i32::overflowing_mul,
+            i32::saturating_mul,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] / b[i]`, truncated towards zero, for `int32_t`
+/// arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_div_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("/", true),
+            i32::overflowing_div,
+            i32::saturating_div,
+        )
+    }
+}
+
+/// Compute `out[i] = min(a[i], b[i])` for `int32_t` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_min_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), Ord::min) }
+}
+
+/// Compute `out[i] = max(a[i], b[i])` for `int32_t` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_max_i32(
+    a: *const i32,
+    b: *const i32,
+    out: *mut i32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), Ord::max) }
+}
+
+/// Compute `out[i] = a[i] * b[i] + c[i]` for `int32_t` arrays under `policy`,
+/// judging overflow on the exact result.
+///
+/// # Safety
+///
+/// `a`, `b` and `c` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_fma_i32(
+    a: *const i32,
+    b: *const i32,
+    c: *const i32,
+    out: *mut i32,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_fma(
+            (a, b, c, out, len),
+            policy,
+            overflowing_fma_i32,
+            saturating_fma_i32,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] + b[i]` for `int64_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_add_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("+", false),
+            i64::overflowing_add,
+            i64::saturating_add,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] - b[i]` for `int64_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sub_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("-", false),
+            i64::overflowing_sub,
+            i64::saturating_sub,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] * b[i]` for `int64_t` arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_mul_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("*", false),
+            i64::overflowing_mul,
+            i64::saturating_mul,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] / b[i]`, truncated towards zero, for `int64_t`
+/// arrays under `policy`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_div_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_binary(
+            (a, b, out, len),
+            policy,
+            ("/", true),
+            i64::overflowing_div,
+            i64::saturating_div,
+        )
+    }
+}
+
+/// Compute `out[i] = min(a[i], b[i])` for `int64_t` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_min_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), Ord::min) }
+}
+
+/// Compute `out[i] = max(a[i], b[i])` for `int64_t` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_max_i64(
+    a: *const i64,
+    b: *const i64,
+    out: *mut i64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), Ord::max) }
+}
+
+/// Compute `out[i] = a[i] * b[i] + c[i]` for `int64_t` arrays under `policy`,
+/// judging overflow on the exact result.
+///
+/// # Safety
+///
+/// `a`, `b` and `c` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_fma_i64(
+    a: *const i64,
+    b: *const i64,
+    c: *const i64,
+    out: *mut i64,
+    len: usize,
+    policy: OverflowPolicy,
+) -> MathStatus {
+    unsafe {
+        int_fma(
+            (a, b, c, out, len),
+            policy,
+            overflowing_fma_i64,
+            saturating_fma_i64,
+        )
+    }
+}
+
+/// Compute `out[i] = a[i] + b[i]` for `float` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_add_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x + y) }
+}
+
+/// Compute `out[i] = a[i] - b[i]` for `float` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sub_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x - y) }
+}
+
+/// Compute `out[i] = a[i] * b[i]` for `float` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_mul_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x * y) }
+}
+
+/// Compute `out[i] = a[i] / b[i]` for `float` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_div_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x / y) }
+}
+
+/// Compute `out[i] = min(a[i], b[i])` for `float` arrays, ignoring a NaN
+/// operand.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_min_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), f32::min) }
+}
+
+/// Compute `out[i] = max(a[i], b[i])` for `float` arrays, ignoring a NaN
+/// operand.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_max_f32(
+    a: *const f32,
+    b: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), f32::max) }
+}
+
+/// Compute `out[i] = a[i] * b[i] + c[i]` for `float` arrays with a single
+/// rounding.
+///
+/// # Safety
+///
+/// `a`, `b` and `c` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_fma_f32(
+    a: *const f32,
+    b: *const f32,
+    c: *const f32,
+    out: *mut f32,
+    len: usize,
+) -> MathStatus {
+    unsafe { map3((a, b, c, out, len), f32::mul_add) }
+}
+
+/// Compute `out[i] = a[i] + b[i]` for `double` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_add_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x + y) }
+}
+
+/// Compute `out[i] = a[i] - b[i]` for `double` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sub_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x - y) }
+}
+
+/// Compute `out[i] = a[i] * b[i]` for `double` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_mul_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x * y) }
+}
+
+/// Compute `out[i] = a[i] / b[i]` for `double` arrays.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_div_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), |x, y| x / y) }
+}
+
+/// Compute `out[i] = min(a[i], b[i])` for `double` arrays, ignoring a NaN
+/// operand.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_min_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), f64::min) }
+}
+
+/// Compute `out[i] = max(a[i], b[i])` for `double` arrays, ignoring a NaN
+/// operand.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_max_f64(
+    a: *const f64,
+    b: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map2((a, b, out, len), f64::max) }
+}
+
+/// Compute `out[i] = a[i] * b[i] + c[i]` for `double` arrays with a single
+/// rounding.
+///
+/// # Safety
+///
+/// `a`, `b` and `c` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null (with `len == 0`) or valid for writing
+/// `len` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_fma_f64(
+    a: *const f64,
+    b: *const f64,
+    c: *const f64,
+    out: *mut f64,
+    len: usize,
+) -> MathStatus {
+    unsafe { map3((a, b, c, out, len), f64::mul_add) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    use OverflowPolicy::{Checked, Overflowing, Saturating, Wrapping};
+
+    #[test]
+    fn test_integer_arrays() {
+        let a = [1i32, -7, i32::MAX, 40];
+        let b = [2i32, 2, 1, -3];
+        let mut out = [0i32; 4];
+        let run =
+            |f: unsafe extern "C" fn(
+                *const i32,
+                *const i32,
+                *mut i32,
+                usize,
+                OverflowPolicy,
+            ) -> MathStatus,
+             out: &mut [i32; 4],
+             policy| unsafe { f(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), 4, policy) };
+
+        assert_eq!(run(array_add_i32, &mut out, Wrapping), MathStatus::Ok);
+        assert_eq!(out, [3, -5, i32::MIN, 37]);
+        assert_eq!(run(array_add_i32, &mut out, Saturating), MathStatus::Ok);
+        assert_eq!(out, [3, -5, i32::MAX, 37]);
+
+        out = [9; 4];
+        assert_eq!(run(array_add_i32, &mut out, Checked), MathStatus::Overflow);
+        assert_eq!(out, [9; 4]);
+        let text = crate::ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "overflow: element 2: 2147483647 + 1 overflows");
+        assert_eq!(
+            run(array_add_i32, &mut out, Overflowing),
+            MathStatus::Overflow
+        );
+        assert_eq!(out, [3, -5, i32::MIN, 37]);
+
+        assert_eq!(run(array_sub_i32, &mut out, Checked), MathStatus::Ok);
+        assert_eq!(out, [-1, -9, i32::MAX - 1, 43]);
+        assert_eq!(run(array_mul_i32, &mut out, Saturating), MathStatus::Ok);
+        assert_eq!(out, [2, -14, i32::MAX, -120]);
+        assert_eq!(run(array_div_i32, &mut out, Checked), MathStatus::Ok);
+        assert_eq!(out, [0, -3, i32::MAX, -13]);
+
+        let zero = [1i32, 0, 1, 1];
+        out = [9; 4];
+        let status =
+            unsafe { array_div_i32(a.as_ptr(), zero.as_ptr(), out.as_mut_ptr(), 4, Wrapping) };
+        assert_eq!(status, MathStatus::DivisionByZero);
+        assert_eq!(out, [9; 4]);
+
+        let (x, y) = ([i64::MIN, 5], [-1i64, 7]);
+        let mut wide = [0i64; 2];
+        let status =
+            unsafe { array_div_i64(x.as_ptr(), y.as_ptr(), wide.as_mut_ptr(), 2, Saturating) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(wide, [i64::MAX, 0]);
+        let status = unsafe { array_min_i64(x.as_ptr(), y.as_ptr(), wide.as_mut_ptr(), 2) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(wide, [i64::MIN, 5]);
+        let status = unsafe { array_max_i32(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), 4) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, [2, 2, i32::MAX, 40]);
+    }
+
+    #[test]
+    fn test_integer_fma_uses_the_exact_result() {
+        // i32::MAX * 2 overflows on its own, but adding -i32::MAX brings the
+        // exact result back into range.
+        let (a, b, c) = ([i32::MAX, i32::MAX], [2, 2], [-i32::MAX, 0]);
+        let mut out = [0i32; 2];
+        let status = unsafe {
+            array_fma_i32(
+                a.as_ptr(),
+                b.as_ptr(),
+                c.as_ptr(),
+                out.as_mut_ptr(),
+                1,
+                Checked,
+            )
+        };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out[0], i32::MAX);
+        let status = unsafe {
+            array_fma_i32(
+                a.as_ptr(),
+                b.as_ptr(),
+                c.as_ptr(),
+                out.as_mut_ptr(),
+                2,
+                Saturating,
+            )
+        };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, [i32::MAX, i32::MAX]);
+
+        let (x, y, z) = ([1i64 << 40], [1i64 << 30], [-1i64]);
+        let mut wide = [0i64];
+        let status = unsafe {
+            array_fma_i64(
+                x.as_ptr(),
+                y.as_ptr(),
+                z.as_ptr(),
+                wide.as_mut_ptr(),
+                1,
+                Wrapping,
+            )
+        };
+        assert_eq!(status, MathStatus::Ok);
+        // 2^70 - 1 wraps to -1.
+        assert_eq!(wide, [-1]);
+    }
+
+    #[test]
+    fn test_float_arrays() {
+        let a = [1.5f64, -2.0, f64::NAN, 0.1];
+        let b = [0.5f64, 4.0, 3.0, 0.2];
+        let mut out = [0.0f64; 4];
+        let status = unsafe { array_add_f64(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), 4) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out[..2], [2.0, 2.0]);
+        assert!(out[2].is_nan());
+        let status = unsafe { array_min_f64(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), 4) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, [0.5, -2.0, 3.0, 0.1]);
+        let status =
+            unsafe { array_fma_f64(a.as_ptr(), b.as_ptr(), b.as_ptr(), out.as_mut_ptr(), 4) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out[3], 0.1f64.mul_add(0.2, 0.2));
+
+        let (x, y) = ([1.0f32, 9.0], [4.0f32, 3.0]);
+        let mut single = [0.0f32; 2];
+        let status = unsafe { array_div_f32(x.as_ptr(), y.as_ptr(), single.as_mut_ptr(), 2) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(single, [0.25, 3.0]);
+    }
+
+    #[test]
+    fn test_in_place_and_null_buffers() {
+        let mut a = [1i64, 2, 3];
+        let b = [10i64, 20, 30];
+        let status = unsafe { array_add_i64(a.as_ptr(), b.as_ptr(), a.as_mut_ptr(), 3, Checked) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(a, [11, 22, 33]);
+
+        let mut out = [0.0f32; 2];
+        let status = unsafe { array_sub_f32(std::ptr::null(), out.as_ptr(), out.as_mut_ptr(), 2) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        let status = unsafe {
+            array_mul_i32(
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+                Checked,
+            )
+        };
+        assert_eq!(status, MathStatus::Ok);
+    }
+}
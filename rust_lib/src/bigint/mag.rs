@@ -0,0 +1,324 @@
+//! Algorithms on unsigned magnitudes stored as little-endian `u64` limbs.
+//!
+//! Inputs may contain trailing zero limbs; outputs never do.
+
+use std::cmp::Ordering;
+
+pub(crate) fn trim(a: &mut Vec<u64>) {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+}
+
+pub(crate) fn trimmed(a: &[u64]) -> &[u64] {
+    let len = a.iter().rposition(|&limb| limb != 0).map_or(0, |i| i + 1);
+    &a[..len]
+}
+
+pub(crate) fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    let (a, b) = (trimmed(a), trimmed(b));
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+/// `acc[offset..] += b`, growing `acc` as needed.
+pub(crate) fn add_assign_at(acc: &mut Vec<u64>, b: &[u64], offset: usize) {
+    if acc.len() < offset + b.len() {
+        acc.resize(offset + b.len(), 0);
+    }
+    let mut carry = false;
+    for (i, &limb) in b.iter().enumerate() {
+        let (sum, c1) = acc[offset + i].overflowing_add(limb);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        acc[offset + i] = sum;
+        carry = c1 || c2;
+    }
+    let mut i = offset + b.len();
+    while carry {
+        if i == acc.len() {
+            acc.push(1);
+            break;
+        }
+        let (sum, c) = acc[i].overflowing_add(1);
+        acc[i] = sum;
+        carry = c;
+        i += 1;
+    }
+}
+
+pub(crate) fn add(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = long.to_vec();
+    add_assign_at(&mut out, short, 0);
+    trim(&mut out);
+    out
+}
+
+/// `a -= b`; requires `a >= b`.
+pub(crate) fn sub_assign(a: &mut Vec<u64>, b: &[u64]) {
+    let b = trimmed(b);
+    debug_assert!(
+        cmp(a, b) != Ordering::Less,
+        "magnitude subtraction underflow"
+    );
+    let mut borrow = false;
+    for (i, limb) in a.iter_mut().enumerate() {
+        if i >= b.len() && !borrow {
+            break;
+        }
+        let rhs = b.get(i).copied().unwrap_or(0);
+        let (diff, b1) = limb.overflowing_sub(rhs);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        *limb = diff;
+        borrow = b1 || b2;
+    }
+    trim(a);
+}
+
+/// `a - b`; requires `a >= b`.
+pub(crate) fn sub(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut out = a.to_vec();
+    sub_assign(&mut out, b);
+    out
+}
+
+/// `a *= factor`, returning the carry out of the top limb.
+pub(crate) fn mul_small_assign(a: &mut [u64], factor: u64) -> u64 {
+    let mut carry = 0u64;
+    for limb in a.iter_mut() {
+        let wide = *limb as u128 * factor as u128 + carry as u128;
+        *limb = wide as u64;
+        carry = (wide >> 64) as u64;
+    }
+    carry
+}
+
+/// `a /= divisor`, returning the remainder.
+pub(crate) fn divrem_small_assign(a: &mut Vec<u64>, divisor: u64) -> u64 {
+    assert_ne!(divisor, 0, "division of a magnitude by zero");
+    let mut rem = 0u64;
+    for limb in a.iter_mut().rev() {
+        let wide = ((rem as u128) << 64) | *limb as u128;
+        *limb = (wide / divisor as u128) as u64;
+        rem = (wide % divisor as u128) as u64;
+    }
+    trim(a);
+    rem
+}
+
+pub(crate) fn shl(a: &[u64], bits: usize) -> Vec<u64> {
+    let a = trimmed(a);
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let (limbs, bits) = (bits / 64, bits % 64);
+    let mut out = vec![0u64; limbs];
+    out.reserve(a.len() + 1);
+    if bits == 0 {
+        out.extend_from_slice(a);
+    } else {
+        let mut carry = 0u64;
+        for &limb in a {
+            out.push((limb << bits) | carry);
+            carry = limb >> (64 - bits);
+        }
+        out.push(carry);
+    }
+    trim(&mut out);
+    out
+}
+
+/// `a >> bits`, truncating towards zero.
+pub(crate) fn shr(a: &[u64], bits: usize) -> Vec<u64> {
+    let (limbs, bits) = (bits / 64, bits % 64);
+    if limbs >= a.len() {
+        return Vec::new();
+    }
+    let a = &a[limbs..];
+    let mut out: Vec<u64> = if bits == 0 {
+        a.to_vec()
+    } else {
+        (0..a.len())
+            .map(|i| (a[i] >> bits) | a.get(i + 1).map_or(0, |&hi| hi << (64 - bits)))
+            .collect()
+    };
+    trim(&mut out);
+    out
+}
+
+/// Whether any of the low `bits` bits of `a` is set.
+pub(crate) fn has_low_bits(a: &[u64], bits: usize) -> bool {
+    let (limbs, bits) = (bits / 64, bits % 64);
+    a.iter().take(limbs).any(|&limb| limb != 0)
+        || (bits != 0 && a.get(limbs).is_some_and(|&limb| limb << (64 - bits) != 0))
+}
+
+pub(crate) fn bit_len(a: &[u64]) -> usize {
+    let a = trimmed(a);
+    match a.last() {
+        Some(top) => a.len() * 64 - top.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Quotient and remainder of `u / v` (Knuth, TAOCP vol. 2, algorithm D).
+///
+/// Panics if `v` is zero.
+pub(crate) fn divrem(u: &[u64], v: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    let (u, v) = (trimmed(u), trimmed(v));
+    assert!(!v.is_empty(), "division of a magnitude by zero");
+    if cmp(u, v) == Ordering::Less {
+        return (Vec::new(), u.to_vec());
+    }
+    if v.len() == 1 {
+        let mut q = u.to_vec();
+        let r = divrem_small_assign(&mut q, v[0]);
+        let mut r = vec![r];
+        trim(&mut r);
+        return (q, r);
+    }
+
+    // Normalise so the top bit of the divisor is set; this keeps every
+    // quotient estimate at most two too large.
+    let shift = v[v.len() - 1].leading_zeros() as usize;
+    let v = shl(v, shift);
+    let mut u = shl(u, shift);
+    u.resize(u.len().max(v.len()) + 1, 0);
+    let n = v.len();
+    let m = u.len() - n - 1;
+    let (v_top, v_next) = (v[n - 1] as u128, v[n - 2] as u128);
+    let base = 1u128 << 64;
+
+    let mut q = vec![0u64; m + 1];
+    for j in (0..=m).rev() {
+        let numerator = ((u[j + n] as u128) << 64) | u[j + n - 1] as u128;
+        let mut qhat = numerator / v_top;
+        let mut rhat = numerator % v_top;
+        while qhat >= base || qhat * v_next > ((rhat << 64) | u[j + n - 2] as u128) {
+            qhat -= 1;
+            rhat += v_top;
+            if rhat >= base {
+                break;
+            }
+        }
+
+        // u[j..=j+n] -= qhat * v
+        let mut carry = 0u128;
+        let mut borrow = false;
+        for i in 0..n {
+            let product = qhat * v[i] as u128 + carry;
+            carry = product >> 64;
+            let (diff, b1) = u[i + j].overflowing_sub(product as u64);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            u[i + j] = diff;
+            borrow = b1 || b2;
+        }
+        let (diff, b1) = u[j + n].overflowing_sub(carry as u64);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        u[j + n] = diff;
+
+        if b1 || b2 {
+            // The estimate was one too large: add the divisor back.
+            qhat -= 1;
+            let mut carry = false;
+            for i in 0..n {
+                let (sum, c1) = u[i + j].overflowing_add(v[i]);
+                let (sum, c2) = sum.overflowing_add(carry as u64);
+                u[i + j] = sum;
+                carry = c1 || c2;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u64);
+        }
+        q[j] = qhat as u64;
+    }
+
+    trim(&mut q);
+    u.truncate(n);
+    (q, shr(&u, shift))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Small deterministic generator for randomised tests.
+    pub(crate) struct XorShift(pub(crate) u64);
+
+    impl XorShift {
+        pub(crate) fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        pub(crate) fn limbs(&mut self, len: usize) -> Vec<u64> {
+            let mut out: Vec<u64> = (0..len).map(|_| self.next_u64()).collect();
+            // Exercise the carry paths with runs of all-ones limbs.
+            if len > 2 && self.next_u64().is_multiple_of(4) {
+                out[len / 2] = u64::MAX;
+                out[len / 2 - 1] = u64::MAX;
+            }
+            trim(&mut out);
+            out
+        }
+    }
+
+    #[test]
+    fn test_add_sub_round_trip() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        for len in [0, 1, 2, 5, 17] {
+            let a = rng.limbs(len);
+            let b = rng.limbs(len / 2 + 1);
+            let sum = add(&a, &b);
+            assert_eq!(sub(&sum, &b), a);
+            assert_eq!(sub(&sum, &a), b);
+        }
+        assert_eq!(add(&[u64::MAX, u64::MAX], &[1]), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn test_shifts() {
+        let a = vec![0x8000_0000_0000_0001, 3];
+        assert_eq!(shr(&shl(&a, 131), 131), a);
+        assert_eq!(shl(&a, 1), vec![2, 7]);
+        assert_eq!(shr(&a, 1), vec![0xC000_0000_0000_0000, 1]);
+        assert!(has_low_bits(&a, 1));
+        assert!(!has_low_bits(&[0, 4], 66));
+        assert!(has_low_bits(&[0, 4], 67));
+        assert_eq!(bit_len(&a), 66);
+    }
+
+    #[test]
+    fn test_divrem_matches_multiplication() {
+        let mut rng = XorShift(0xDEAD_BEEF_CAFE_F00D);
+        for (ulen, vlen) in [(2, 2), (4, 2), (9, 3), (30, 11), (40, 39)] {
+            for _ in 0..20 {
+                let u = rng.limbs(ulen);
+                let mut v = rng.limbs(vlen);
+                if v.is_empty() {
+                    v.push(1);
+                }
+                let (q, r) = divrem(&u, &v);
+                assert_eq!(cmp(&r, &v), Ordering::Less);
+                let mut back = super::super::mul::mul(&q, &v);
+                add_assign_at(&mut back, &r, 0);
+                trim(&mut back);
+                assert_eq!(back, u);
+            }
+        }
+    }
+
+    #[test]
+    fn test_divrem_add_back_case() {
+        // Classic input that needs the rare "add back" correction step.
+        let u = [0, 0, 0x8000_0000_0000_0000, 0x7FFF_FFFF_FFFF_FFFF];
+        let v = [1, 0, 0x8000_0000_0000_0000];
+        let (q, r) = divrem(&u, &v);
+        let mut back = super::super::mul::mul(&q, &v);
+        add_assign_at(&mut back, &r, 0);
+        trim(&mut back);
+        assert_eq!(back, u.to_vec());
+    }
+}
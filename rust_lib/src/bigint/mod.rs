@@ -0,0 +1,1018 @@
+//! Arbitrary-precision integers exposed to C through opaque handles.
+//!
+//! C code only ever sees `BigInt *`: values are created by the constructors,
+//! the arithmetic functions and `factorial_big`, inspected with the
+//! conversion functions and released with `bigint_free`. Every arithmetic
+//! function returns a new handle and leaves its operands untouched.
+
+pub(crate) mod mag;
+pub(crate) mod mul;
+
+use std::cmp::Ordering;
+use std::ffi::{CStr, c_char};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::MathStatus;
+use crate::error::{self, MathError, MathResult};
+use crate::ffi;
+
+/// Upper bound on the size of results the library agrees to build, so that
+/// runaway `pow`/`shl` requests fail cleanly instead of exhausting memory.
+pub(crate) const MAX_BITS: u64 = 1 << 32;
+
+/// Signed arbitrary-precision integer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Little-endian 64-bit limbs without trailing zeros; zero has no limbs.
+    mag: Vec<u64>,
+}
+
+impl BigInt {
+    pub(crate) fn zero() -> Self {
+        BigInt::default()
+    }
+
+    pub(crate) fn one() -> Self {
+        BigInt::from(1u64)
+    }
+
+    /// Build a value from a sign and little-endian magnitude limbs.
+    pub(crate) fn from_limbs(negative: bool, mut mag: Vec<u64>) -> Self {
+        mag::trim(&mut mag);
+        let negative = negative && !mag.is_empty();
+        BigInt { negative, mag }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub(crate) fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// -1, 0 or 1 according to the sign of the value.
+    pub(crate) fn signum(&self) -> i32 {
+        match (self.is_zero(), self.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        }
+    }
+
+    /// Little-endian magnitude limbs.
+    pub(crate) fn limbs(&self) -> &[u64] {
+        &self.mag
+    }
+
+    pub(crate) fn to_u64(&self) -> Option<u64> {
+        match (self.negative, self.mag.as_slice()) {
+            (false, []) => Some(0),
+            (false, [limb]) => Some(*limb),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn abs(&self) -> BigInt {
+        BigInt::from_limbs(false, self.mag.clone())
+    }
+
+    pub(crate) fn bit_len(&self) -> u64 {
+        mag::bit_len(&self.mag) as u64
+    }
+
+    /// Multiply in place by a single limb.
+    pub(crate) fn mul_small_assign(&mut self, factor: u64) {
+        if factor == 0 {
+            *self = BigInt::zero();
+            return;
+        }
+        let carry = mag::mul_small_assign(&mut self.mag, factor);
+        if carry != 0 {
+            self.mag.push(carry);
+        }
+    }
+
+    /// Quotient truncated towards zero and remainder of the magnitude.
+    pub(crate) fn divrem_small(&self, divisor: u64) -> (BigInt, u64) {
+        let mut quotient = self.mag.clone();
+        let rem = mag::divrem_small_assign(&mut quotient, divisor);
+        (BigInt::from_limbs(self.negative, quotient), rem)
+    }
+
+    /// Divide by a small value known to divide `self` exactly.
+    fn div_exact_small(&self, divisor: u64) -> BigInt {
+        let (quotient, rem) = self.divrem_small(divisor);
+        debug_assert_eq!(rem, 0);
+        quotient
+    }
+
+    /// Quotient truncated towards zero and remainder with the sign of the
+    /// dividend, matching C's `/` and `%`.
+    pub(crate) fn divrem(&self, divisor: &BigInt) -> MathResult<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return Err(MathError::new(
+                MathStatus::DivisionByZero,
+                "divisor is zero",
+            ));
+        }
+        let (q, r) = mag::divrem(&self.mag, &divisor.mag);
+        Ok((
+            BigInt::from_limbs(self.negative != divisor.negative, q),
+            BigInt::from_limbs(self.negative, r),
+        ))
+    }
+
+    pub(crate) fn pow(&self, exponent: u32) -> MathResult<BigInt> {
+        if self.bit_len().saturating_sub(1) * exponent as u64 > MAX_BITS {
+            return Err(MathError::overflow(format!(
+                "result of raising a {}-bit value to the power {exponent} is too large",
+                self.bit_len()
+            )));
+        }
+        let mut result = BigInt::one();
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Greatest common divisor; always non-negative, and `gcd(0, 0) == 0`.
+    pub(crate) fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.mag.clone(), other.mag.clone());
+        while !b.is_empty() {
+            let (_, r) = mag::divrem(&a, &b);
+            a = std::mem::replace(&mut b, r);
+        }
+        BigInt::from_limbs(false, a)
+    }
+
+    pub(crate) fn shl(&self, bits: u64) -> MathResult<BigInt> {
+        if !self.is_zero() && bits > MAX_BITS.saturating_sub(self.bit_len()) {
+            return Err(MathError::overflow(format!(
+                "shifting a {}-bit value left by {bits} bits is too large",
+                self.bit_len()
+            )));
+        }
+        Ok(self.shl_bits(bits as usize))
+    }
+
+    /// `self * 2^bits` without the size check of [`BigInt::shl`], for
+    /// internal shifts whose size is bounded by construction.
+    pub(crate) fn shl_bits(&self, bits: usize) -> BigInt {
+        BigInt::from_limbs(self.negative, mag::shl(&self.mag, bits))
+    }
+
+    /// Arithmetic right shift: rounds towards negative infinity, like `>>` on
+    /// two's complement integers.
+    pub(crate) fn shr(&self, bits: u64) -> BigInt {
+        let bits = usize::try_from(bits).unwrap_or(usize::MAX);
+        let shifted = BigInt::from_limbs(self.negative, mag::shr(&self.mag, bits));
+        if self.negative && mag::has_low_bits(&self.mag, bits) {
+            &shifted - &BigInt::one()
+        } else {
+            shifted
+        }
+    }
+
+    /// Largest integer whose square does not exceed a non-negative value.
+    pub(crate) fn sqrt_floor(&self) -> BigInt {
+        debug_assert!(!self.negative, "square root of a negative value");
+        if self.is_zero() {
+            return BigInt::zero();
+        }
+        // Newton's iteration decreases monotonically from any start above
+        // the root and stops at its floor.
+        let mut x = BigInt::one().shl_bits(self.bit_len().div_ceil(2) as usize);
+        loop {
+            let (q, _) = mag::divrem(&self.mag, &x.mag);
+            let next = (&x + &BigInt::from_limbs(false, q)).shr(1);
+            if next >= x {
+                return x;
+            }
+            x = next;
+        }
+    }
+
+    /// Parse an optionally signed string of digits in `radix` (2 to 36,
+    /// letters in either case).
+    pub(crate) fn from_str_radix(s: &str, radix: u32) -> MathResult<BigInt> {
+        check_radix(radix)?;
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(MathError::invalid_argument("no digits to parse"));
+        }
+        let (_, chunk_digits) = chunk_base(radix);
+        let mut mag = Vec::new();
+        for chunk in digits.as_bytes().chunks(chunk_digits) {
+            let mut value = 0u64;
+            for &c in chunk {
+                let digit = (c as char).to_digit(radix).ok_or_else(|| {
+                    MathError::invalid_argument(format!(
+                        "invalid digit {:?} for radix {radix}",
+                        c as char
+                    ))
+                })?;
+                value = value * radix as u64 + digit as u64;
+            }
+            let carry = mag::mul_small_assign(&mut mag, (radix as u64).pow(chunk.len() as u32));
+            if carry != 0 {
+                mag.push(carry);
+            }
+            mag::add_assign_at(&mut mag, &[value], 0);
+        }
+        Ok(BigInt::from_limbs(negative, mag))
+    }
+
+    /// Format in `radix` (2 to 36) with lowercase letters.
+    pub(crate) fn to_string_radix(&self, radix: u32) -> MathResult<String> {
+        check_radix(radix)?;
+        let (chunk, chunk_digits) = chunk_base(radix);
+        let mut mag = self.mag.clone();
+        let mut chunks = Vec::new();
+        while !mag.is_empty() {
+            chunks.push(mag::divrem_small_assign(&mut mag, chunk));
+        }
+        let mut out = String::new();
+        if self.negative {
+            out.push('-');
+        }
+        push_digits(&mut out, chunks.pop().unwrap_or(0), radix, 1);
+        for &value in chunks.iter().rev() {
+            push_digits(&mut out, value, radix, chunk_digits);
+        }
+        Ok(out)
+    }
+
+    /// Magnitude as big-endian bytes without leading zeros; zero is empty.
+    pub(crate) fn to_bytes_be(&self) -> Vec<u8> {
+        let bytes: Vec<u8> = self
+            .mag
+            .iter()
+            .rev()
+            .flat_map(|limb| limb.to_be_bytes())
+            .collect();
+        let leading = bytes.iter().take_while(|&&b| b == 0).count();
+        bytes[leading..].to_vec()
+    }
+
+    fn byte_len(&self) -> usize {
+        (self.bit_len() as usize).div_ceil(8)
+    }
+}
+
+/// Product of `factors`; see [`product_of`].
+pub(crate) fn product(factors: &[u64], threads: usize) -> BigInt {
+    product_of(factors.len(), &|i| factors[i], threads)
+}
+
+/// Product of `term(0) * term(1) * ... * term(len - 1)`, multiplied as a
+/// balanced tree so that both operands of every big multiplication have
+/// similar sizes. With `threads > 1` the two halves of large subtrees are
+/// computed on separate threads.
+pub(crate) fn product_of(
+    len: usize,
+    term: &(dyn Fn(usize) -> u64 + Sync),
+    threads: usize,
+) -> BigInt {
+    product_range(0, len, term, threads)
+}
+
+fn product_range(
+    lo: usize,
+    hi: usize,
+    term: &(dyn Fn(usize) -> u64 + Sync),
+    threads: usize,
+) -> BigInt {
+    const LEAF: usize = 16;
+    const PARALLEL_MIN: usize = 1024;
+    if hi - lo <= LEAF {
+        let mut acc = BigInt::one();
+        let mut word = 1u64;
+        for factor in (lo..hi).map(term) {
+            match word.checked_mul(factor) {
+                Some(w) => word = w,
+                None => {
+                    acc.mul_small_assign(word);
+                    word = factor;
+                }
+            }
+        }
+        acc.mul_small_assign(word);
+        return acc;
+    }
+    let mid = lo + (hi - lo) / 2;
+    if threads > 1 && hi - lo >= PARALLEL_MIN {
+        let (left, right) = std::thread::scope(|scope| {
+            let right = scope.spawn(|| product_range(mid, hi, term, threads - threads / 2));
+            (product_range(lo, mid, term, threads / 2), right.join())
+        });
+        let right = right.unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+        &left * &right
+    } else {
+        &product_range(lo, mid, term, threads) * &product_range(mid, hi, term, threads)
+    }
+}
+
+/// Product of `base^exponent` over `powers`.
+///
+/// Bases are grouped by the bits of their exponents, so the result is
+/// `prod_i (prod_{bit i of e set} base)^(2^i)`: each inner product is a
+/// balanced [`product`] and the outer one is built by repeated squaring.
+pub(crate) fn power_product(powers: &[(u64, u64)], threads: usize) -> MathResult<BigInt> {
+    if powers
+        .iter()
+        .any(|&(base, exponent)| base == 0 && exponent > 0)
+    {
+        return Ok(BigInt::zero());
+    }
+    let bits: f64 = powers
+        .iter()
+        .map(|&(base, exponent)| exponent as f64 * (base as f64).log2())
+        .sum();
+    ensure_fits(bits)?;
+
+    let mut groups: Vec<Vec<u64>> = Vec::new();
+    for &(base, exponent) in powers {
+        let len = (u64::BITS - exponent.leading_zeros()) as usize;
+        if groups.len() < len {
+            groups.resize(len, Vec::new());
+        }
+        for (bit, group) in groups.iter_mut().enumerate() {
+            if exponent >> bit & 1 == 1 {
+                group.push(base);
+            }
+        }
+    }
+    let mut acc = BigInt::one();
+    for group in groups.iter().rev() {
+        acc = &acc * &acc;
+        acc = &acc * &product(group, threads);
+    }
+    Ok(acc)
+}
+
+/// Refuse to build a result of (approximately) `bits` bits if it would
+/// exceed [`MAX_BITS`].
+pub(crate) fn ensure_fits(bits: f64) -> MathResult<()> {
+    if bits > MAX_BITS as f64 {
+        Err(MathError::overflow(format!(
+            "result of about {bits:.3e} bits is too large to represent"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_radix(radix: u32) -> MathResult<()> {
+    if (2..=36).contains(&radix) {
+        Ok(())
+    } else {
+        Err(MathError::invalid_argument(format!(
+            "radix {radix} is outside 2..=36"
+        )))
+    }
+}
+
+/// Largest power of `radix` that fits in a limb, and its number of digits.
+fn chunk_base(radix: u32) -> (u64, usize) {
+    let radix = radix as u64;
+    let (mut power, mut digits) = (radix, 1);
+    while let Some(next) = power.checked_mul(radix) {
+        power = next;
+        digits += 1;
+    }
+    (power, digits)
+}
+
+/// Append `value` in `radix`, left-padded with zeros to at least `width`.
+fn push_digits(out: &mut String, mut value: u64, radix: u32, width: usize) {
+    let mut digits = Vec::new();
+    while value > 0 || digits.len() < width {
+        let digit = (value % radix as u64) as u32;
+        digits.push(char::from_digit(digit, radix).expect("digit is below radix"));
+        value /= radix as u64;
+    }
+    out.extend(digits.iter().rev());
+}
+
+impl From<u64> for BigInt {
+    fn from(value: u64) -> Self {
+        BigInt::from_limbs(false, vec![value])
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        BigInt::from_limbs(value < 0, vec![value.unsigned_abs()])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => mag::cmp(&self.mag, &other.mag),
+            (true, true) => mag::cmp(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `a + b` where the operands are given as sign and magnitude.
+fn add_signed(a_neg: bool, a: &[u64], b_neg: bool, b: &[u64]) -> BigInt {
+    if a_neg == b_neg {
+        return BigInt::from_limbs(a_neg, mag::add(a, b));
+    }
+    match mag::cmp(a, b) {
+        Ordering::Greater => BigInt::from_limbs(a_neg, mag::sub(a, b)),
+        Ordering::Less => BigInt::from_limbs(b_neg, mag::sub(b, a)),
+        Ordering::Equal => BigInt::zero(),
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        add_signed(self.negative, &self.mag, rhs.negative, &rhs.mag)
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        add_signed(self.negative, &self.mag, !rhs.negative, &rhs.mag)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_limbs(self.negative != rhs.negative, mul::mul(&self.mag, &rhs.mag))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_limbs(!self.negative, self.mag.clone())
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.abs().to_string_radix(10).map_err(|_| fmt::Error)?;
+        f.pad_integral(!self.negative, "", &digits)
+    }
+}
+
+/// Run a handle-producing operation, returning null on failure.
+fn handle_result(f: impl FnOnce() -> MathResult<BigInt>) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || f().map(ffi::into_handle))
+}
+
+/// Borrow the operands of a binary operation.
+///
+/// # Safety
+///
+/// Both pointers must be null or live handles.
+unsafe fn operands<'a>(a: *const BigInt, b: *const BigInt) -> MathResult<(&'a BigInt, &'a BigInt)> {
+    Ok(unsafe { (ffi::handle_ref(a, "a")?, ffi::handle_ref(b, "b")?) })
+}
+
+/// Create a big integer from an unsigned 64-bit value.
+///
+/// The handle must be released with `bigint_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn bigint_from_u64(value: u64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        Ok(ffi::into_handle(BigInt::from(value)))
+    })
+}
+
+/// Create a big integer from a signed 64-bit value.
+///
+/// The handle must be released with `bigint_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn bigint_from_i64(value: i64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        Ok(ffi::into_handle(BigInt::from(value)))
+    })
+}
+
+/// Copy a big integer into a new handle, or return null if `value` is null.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_clone(value: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { ffi::handle_ref(value, "value") }.cloned())
+}
+
+/// Release a big integer handle. Passing null is a no-op.
+///
+/// The last error is left untouched, so temporaries can be released before
+/// it is read.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library, and must
+/// not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_free(value: *mut BigInt) {
+    ffi::catch((), || unsafe { ffi::free_handle(value) })
+}
+
+/// -1, 0 or 1 according to the sign of `value`; 0 if `value` is null.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_sign(value: *const BigInt) -> i32 {
+    ffi::guard(0, || {
+        Ok(unsafe { ffi::handle_ref(value, "value") }?.signum())
+    })
+}
+
+/// Format `value` in decimal, or return null on failure.
+///
+/// The string must be released with `math_free_string`.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_to_string(value: *const BigInt) -> *mut c_char {
+    ffi::guard(std::ptr::null_mut(), || {
+        let value = unsafe { ffi::handle_ref(value, "value") }?;
+        Ok(error::into_c_string(value.to_string()))
+    })
+}
+
+/// Write the magnitude of `value` to `buf` as big-endian bytes without
+/// leading zeros (zero has no bytes); the sign is available from
+/// `bigint_sign`.
+///
+/// The required length is always stored in `out_len` (if non-null). When
+/// `capacity` is smaller than that, nothing is copied and
+/// `MathStatus::BufferTooSmall` is returned, so callers may pass a null
+/// buffer first to query the size.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle, `buf` must be null or valid for
+/// writing `capacity` bytes, and `out_len` must be null or valid for writing
+/// a single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_to_bytes(
+    value: *const BigInt,
+    buf: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = unsafe { ffi::handle_ref(value, "value") }?;
+        let len = value.byte_len();
+        if !out_len.is_null() {
+            unsafe { out_len.write(len) };
+        }
+        if capacity < len {
+            return Err(error::MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{len} bytes required, {capacity} available"),
+            ));
+        }
+        let buf = unsafe { ffi::slice_mut(buf, len, "buf") }?;
+        buf.copy_from_slice(&value.to_bytes_be());
+        Ok(())
+    })
+}
+
+/// Parse a big integer from a NUL-terminated string of digits in `radix`
+/// (2 to 36), with an optional leading `+` or `-`. Returns null on failure.
+///
+/// The handle must be released with `bigint_free`.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_from_string(s: *const c_char, radix: u32) -> *mut BigInt {
+    handle_result(|| {
+        if s.is_null() {
+            return Err(MathError::invalid_argument("`s` is null"));
+        }
+        let s = unsafe { CStr::from_ptr(s) }
+            .to_str()
+            .map_err(|_| MathError::invalid_argument("string is not valid UTF-8"))?;
+        BigInt::from_str_radix(s, radix)
+    })
+}
+
+/// Format `value` in `radix` (2 to 36) with lowercase letters, or return null
+/// on failure.
+///
+/// The string must be released with `math_free_string`.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_to_string_radix(value: *const BigInt, radix: u32) -> *mut c_char {
+    ffi::guard(std::ptr::null_mut(), || {
+        let value = unsafe { ffi::handle_ref(value, "value") }?;
+        Ok(error::into_c_string(value.to_string_radix(radix)?))
+    })
+}
+
+/// Compare two big integers: -1 if `a < b`, 0 if equal, 1 if `a > b`.
+///
+/// Returns 0 and records `MathStatus::InvalidArgument` if either is null.
+///
+/// # Safety
+///
+/// Both arguments must be null or live handles returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_compare(a: *const BigInt, b: *const BigInt) -> i32 {
+    ffi::guard(0, || {
+        let (a, b) = unsafe { operands(a, b) }?;
+        Ok(a.cmp(b) as i32)
+    })
+}
+
+/// `a + b` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// Both arguments must be null or live handles returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_add(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { operands(a, b) }.map(|(a, b)| a + b))
+}
+
+/// `a - b` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// Both arguments must be null or live handles returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_sub(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { operands(a, b) }.map(|(a, b)| a - b))
+}
+
+/// `a * b` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// Both arguments must be null or live handles returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_mul(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { operands(a, b) }.map(|(a, b)| a * b))
+}
+
+/// `-value` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_neg(value: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { ffi::handle_ref(value, "value") }.map(|value| -value))
+}
+
+/// Divide `a` by `b` with C semantics: the quotient is truncated towards zero
+/// and the remainder takes the sign of `a`.
+///
+/// New handles are stored in `out_quotient` and `out_remainder`; either may
+/// be null if that result is not needed. Returns
+/// `MathStatus::DivisionByZero` if `b` is zero.
+///
+/// # Safety
+///
+/// `a` and `b` must be null or live handles; each out-pointer must be null or
+/// valid for writing a single pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_divmod(
+    a: *const BigInt,
+    b: *const BigInt,
+    out_quotient: *mut *mut BigInt,
+    out_remainder: *mut *mut BigInt,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let (a, b) = unsafe { operands(a, b) }?;
+        let (quotient, remainder) = a.divrem(b)?;
+        if !out_quotient.is_null() {
+            unsafe { out_quotient.write(ffi::into_handle(quotient)) };
+        }
+        if !out_remainder.is_null() {
+            unsafe { out_remainder.write(ffi::into_handle(remainder)) };
+        }
+        Ok(())
+    })
+}
+
+/// `base` raised to `exponent` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// `base` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_pow(base: *const BigInt, exponent: u32) -> *mut BigInt {
+    handle_result(|| unsafe { ffi::handle_ref(base, "base") }?.pow(exponent))
+}
+
+/// Non-negative greatest common divisor of `a` and `b` as a new handle, or
+/// null on failure.
+///
+/// # Safety
+///
+/// Both arguments must be null or live handles returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_gcd(a: *const BigInt, b: *const BigInt) -> *mut BigInt {
+    handle_result(|| unsafe { operands(a, b) }.map(|(a, b)| a.gcd(b)))
+}
+
+/// `value * 2^bits` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_shl(value: *const BigInt, bits: u64) -> *mut BigInt {
+    handle_result(|| unsafe { ffi::handle_ref(value, "value") }?.shl(bits))
+}
+
+/// `floor(value / 2^bits)` as a new handle, or null on failure.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle returned by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_shr(value: *const BigInt, bits: u64) -> *mut BigInt {
+    handle_result(|| unsafe { ffi::handle_ref(value, "value") }.map(|value| value.shr(bits)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::take_string as c_string;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(BigInt::zero().to_string(), "0");
+        assert_eq!(BigInt::from(-42i64).to_string(), "-42");
+        assert_eq!(BigInt::from(u64::MAX).to_string(), "18446744073709551615");
+
+        let mut big = BigInt::from(u64::MAX);
+        big.mul_small_assign(u64::MAX);
+        assert_eq!(big.to_string(), "340282366920938463426481119284349108225");
+    }
+
+    #[test]
+    fn test_ordering() {
+        let values = [-(1i64 << 40), -3, 0, 5, 1 << 50];
+        for pair in values.windows(2) {
+            assert!(BigInt::from(pair[0]) < BigInt::from(pair[1]));
+        }
+        let big = &BigInt::from(u64::MAX) * &BigInt::from(2u64);
+        assert!(big > BigInt::from(u64::MAX));
+        assert!(&big * &BigInt::from(-1i64) < BigInt::from(i64::MIN));
+    }
+
+    #[test]
+    fn test_handles_and_bytes() {
+        let handle = bigint_from_i64(-258);
+        assert_eq!(unsafe { bigint_sign(handle) }, -1);
+        assert_eq!(c_string(unsafe { bigint_to_string(handle) }), "-258");
+
+        let mut len = 0usize;
+        let status = unsafe { bigint_to_bytes(handle, std::ptr::null_mut(), 0, &mut len) };
+        assert_eq!(status, MathStatus::BufferTooSmall);
+        assert_eq!(len, 2);
+
+        let mut buf = [0u8; 4];
+        let status = unsafe { bigint_to_bytes(handle, buf.as_mut_ptr(), buf.len(), &mut len) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(&buf[..len], &[1, 2]);
+
+        let copy = unsafe { bigint_clone(handle) };
+        unsafe { bigint_free(handle) };
+        assert_eq!(c_string(unsafe { bigint_to_string(copy) }), "-258");
+        unsafe { bigint_free(copy) };
+
+        let temporary = bigint_from_i64(1);
+        assert!(unsafe { bigint_to_string(std::ptr::null()) }.is_null());
+        assert_eq!(
+            crate::error::math_last_error_code(),
+            MathStatus::InvalidArgument
+        );
+        // Releasing a temporary keeps the error of the failed call.
+        unsafe { bigint_free(temporary) };
+        assert_eq!(
+            crate::error::math_last_error_code(),
+            MathStatus::InvalidArgument
+        );
+    }
+
+    fn parse(s: &str) -> BigInt {
+        BigInt::from_str_radix(s, 10).unwrap()
+    }
+
+    #[test]
+    fn test_product() {
+        let factors: Vec<u64> = (1..=3000).map(|k| k * 0x1_0000_0001).collect();
+        let mut expected = BigInt::one();
+        for &f in &factors {
+            expected.mul_small_assign(f);
+        }
+        assert_eq!(product(&factors, 1), expected);
+        assert_eq!(product(&factors, 3), expected);
+        assert_eq!(product(&[], 1), BigInt::one());
+    }
+
+    #[test]
+    fn test_power_product() {
+        let value = power_product(&[(3, 5), (10, 2), (7, 0), (2, 13)], 1).unwrap();
+        assert_eq!(value, BigInt::from(243u64 * 100 * 8192));
+        assert_eq!(power_product(&[], 1).unwrap(), BigInt::one());
+        assert_eq!(power_product(&[(0, 3), (5, 1)], 1).unwrap(), BigInt::zero());
+        assert!(power_product(&[(3, u64::MAX)], 1).is_err());
+    }
+
+    #[test]
+    fn test_radix_round_trip() {
+        let value = parse("-123456789012345678901234567890123456789");
+        for radix in [2, 3, 7, 10, 16, 36] {
+            let text = value.to_string_radix(radix).unwrap();
+            assert_eq!(BigInt::from_str_radix(&text, radix).unwrap(), value);
+            let upper = text.to_uppercase();
+            assert_eq!(BigInt::from_str_radix(&upper, radix).unwrap(), value);
+        }
+        assert_eq!(
+            BigInt::from_str_radix("+ff", 16).unwrap().to_string(),
+            "255"
+        );
+        assert_eq!(BigInt::from(255u64).to_string_radix(2).unwrap(), "11111111");
+        assert_eq!(BigInt::zero().to_string_radix(36).unwrap(), "0");
+        assert_eq!(parse("-0"), BigInt::zero());
+
+        assert!(BigInt::from_str_radix("12a", 10).is_err());
+        assert!(BigInt::from_str_radix("-", 10).is_err());
+        assert!(BigInt::from_str_radix("", 10).is_err());
+        assert!(BigInt::from_str_radix("10", 37).is_err());
+        assert!(BigInt::zero().to_string_radix(1).is_err());
+    }
+
+    #[test]
+    fn test_divrem_follows_c_semantics() {
+        for (a, b) in [(7i64, 2i64), (-7, 2), (7, -2), (-7, -2), (6, 3), (1, 5)] {
+            let (q, r) = BigInt::from(a).divrem(&BigInt::from(b)).unwrap();
+            assert_eq!(
+                (q, r),
+                (BigInt::from(a / b), BigInt::from(a % b)),
+                "{a} / {b}"
+            );
+        }
+        let err = BigInt::one().divrem(&BigInt::zero()).unwrap_err();
+        assert_eq!(err.status(), MathStatus::DivisionByZero);
+    }
+
+    #[test]
+    fn test_pow_gcd_and_shifts() {
+        assert_eq!(BigInt::from(-3i64).pow(5).unwrap(), BigInt::from(-243i64));
+        assert_eq!(BigInt::from(10u64).pow(0).unwrap(), BigInt::one());
+        assert_eq!(
+            BigInt::from(2u64).pow(100).unwrap().to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert!(BigInt::from(4u64).pow(u32::MAX).is_err());
+        assert_eq!(BigInt::zero().pow(u32::MAX).unwrap(), BigInt::zero());
+
+        let a = &BigInt::from(2u64).pow(200).unwrap() * &BigInt::from(15u64);
+        let b = &BigInt::from(2u64).pow(130).unwrap() * &BigInt::from(-21i64);
+        assert_eq!(
+            a.gcd(&b),
+            &BigInt::from(2u64).pow(130).unwrap() * &BigInt::from(3u64)
+        );
+        assert_eq!(BigInt::zero().gcd(&b), b.abs());
+
+        let x = BigInt::from(-5i64);
+        assert_eq!(x.shl(70).unwrap().shr(70), x);
+        assert_eq!(x.shr(1), BigInt::from(-3i64));
+        assert_eq!(x.shr(200), BigInt::from(-1i64));
+        assert_eq!(BigInt::from(5u64).shr(1), BigInt::from(2u64));
+        assert!(BigInt::one().shl(MAX_BITS).is_err());
+
+        // `bit_len + bits` would wrap around u64.
+        let one = ffi::into_handle(BigInt::one());
+        assert!(unsafe { bigint_shl(one, u64::MAX) }.is_null());
+        assert_eq!(crate::error::math_last_error_code(), MathStatus::Overflow);
+        unsafe { bigint_free(one) };
+    }
+
+    #[test]
+    fn test_sqrt_floor() {
+        for n in 0..=1000u64 {
+            let root = BigInt::from(n).sqrt_floor().to_u64().unwrap();
+            assert!(root * root <= n && (root + 1) * (root + 1) > n, "{n}");
+        }
+        let x = BigInt::from(3u64).pow(301).unwrap();
+        let square = &x * &x;
+        assert_eq!(square.sqrt_floor(), x);
+        assert_eq!((&square - &BigInt::one()).sqrt_floor(), &x - &BigInt::one());
+    }
+
+    #[test]
+    fn test_large_products_divide_back() {
+        let a = BigInt::from(3u64).pow(60_000).unwrap();
+        let b = BigInt::from(7u64).pow(45_000).unwrap();
+        let product = &a * &b;
+        let (q, r) = product.divrem(&b).unwrap();
+        assert_eq!(q, a);
+        assert!(r.is_zero());
+        let (q, r) = (&product + &BigInt::from(12345u64)).divrem(&a).unwrap();
+        assert_eq!(q, b);
+        assert_eq!(r, BigInt::from(12345u64));
+    }
+
+    #[test]
+    fn test_arithmetic_exports() {
+        let a = unsafe { bigint_from_string(c"-ZZ".as_ptr(), 36) };
+        let b = bigint_from_i64(1000);
+        assert_eq!(c_string(unsafe { bigint_to_string(a) }), "-1295");
+
+        let sum = unsafe { bigint_add(a, b) };
+        let diff = unsafe { bigint_sub(a, b) };
+        let product = unsafe { bigint_mul(a, b) };
+        let negated = unsafe { bigint_neg(a) };
+        assert_eq!(c_string(unsafe { bigint_to_string(sum) }), "-295");
+        assert_eq!(c_string(unsafe { bigint_to_string(diff) }), "-2295");
+        assert_eq!(c_string(unsafe { bigint_to_string(product) }), "-1295000");
+        assert_eq!(
+            c_string(unsafe { bigint_to_string_radix(negated, 16) }),
+            "50f"
+        );
+        assert_eq!(unsafe { bigint_compare(a, b) }, -1);
+        assert_eq!(unsafe { bigint_compare(b, a) }, 1);
+
+        let mut q = std::ptr::null_mut();
+        let mut r = std::ptr::null_mut();
+        assert_eq!(
+            unsafe { bigint_divmod(a, b, &mut q, &mut r) },
+            MathStatus::Ok
+        );
+        assert_eq!(c_string(unsafe { bigint_to_string(q) }), "-1");
+        assert_eq!(c_string(unsafe { bigint_to_string(r) }), "-295");
+
+        let zero = bigint_from_u64(0);
+        let status = unsafe { bigint_divmod(a, zero, std::ptr::null_mut(), std::ptr::null_mut()) };
+        assert_eq!(status, MathStatus::DivisionByZero);
+
+        let power = unsafe { bigint_pow(b, 3) };
+        let shifted = unsafe { bigint_shl(b, 3) };
+        let halved = unsafe { bigint_shr(a, 1) };
+        let gcd = unsafe { bigint_gcd(a, b) };
+        assert_eq!(c_string(unsafe { bigint_to_string(power) }), "1000000000");
+        assert_eq!(c_string(unsafe { bigint_to_string(shifted) }), "8000");
+        assert_eq!(c_string(unsafe { bigint_to_string(halved) }), "-648");
+        assert_eq!(c_string(unsafe { bigint_to_string(gcd) }), "5");
+
+        assert!(unsafe { bigint_from_string(c"12x".as_ptr(), 10) }.is_null());
+        assert_eq!(
+            crate::error::math_last_error_code(),
+            MathStatus::InvalidArgument
+        );
+
+        for handle in [
+            a, b, sum, diff, product, negated, q, r, zero, power, shifted, halved, gcd,
+        ] {
+            unsafe { bigint_free(handle) };
+        }
+    }
+}
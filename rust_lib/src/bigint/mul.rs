@@ -0,0 +1,156 @@
+//! Magnitude multiplication: schoolbook for small operands, Karatsuba for
+//! medium ones and Toom-3 for large ones.
+
+use super::BigInt;
+use super::mag;
+
+/// Below this many limbs (of the shorter operand) schoolbook wins.
+pub(crate) const KARATSUBA_THRESHOLD: usize = 32;
+/// From this many limbs (of the shorter operand) Toom-3 replaces Karatsuba.
+pub(crate) const TOOM3_THRESHOLD: usize = 192;
+
+/// Product of two magnitudes.
+pub(crate) fn mul(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (a, b) = (mag::trimmed(a), mag::trimmed(b));
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.is_empty() {
+        Vec::new()
+    } else if b.len() < KARATSUBA_THRESHOLD {
+        schoolbook(a, b)
+    } else if 2 * b.len() <= a.len() {
+        unbalanced(a, b)
+    } else if b.len() < TOOM3_THRESHOLD {
+        karatsuba(a, b)
+    } else {
+        toom3(a, b)
+    }
+}
+
+pub(crate) fn schoolbook(a: &[u64], b: &[u64]) -> Vec<u64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u64; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let wide = x as u128 * y as u128 + out[i + j] as u128 + carry as u128;
+            out[i + j] = wide as u64;
+            carry = (wide >> 64) as u64;
+        }
+        out[i + b.len()] = carry;
+    }
+    mag::trim(&mut out);
+    out
+}
+
+/// `a` is at least twice as long as `b`: multiply `b` by `b`-sized slices
+/// of `a` so that every recursive product is balanced.
+fn unbalanced(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    for (i, chunk) in a.chunks(b.len()).enumerate() {
+        mag::add_assign_at(&mut out, &mul(chunk, b), i * b.len());
+    }
+    mag::trim(&mut out);
+    out
+}
+
+/// Requires `a.len() >= b.len() > a.len() / 2`.
+fn karatsuba(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let m = a.len() / 2;
+    let (a0, a1) = a.split_at(m);
+    let (b0, b1) = b.split_at(m);
+
+    let z0 = mul(a0, b0);
+    let z2 = mul(a1, b1);
+    let mut z1 = mul(&mag::add(a0, a1), &mag::add(b0, b1));
+    mag::sub_assign(&mut z1, &z0);
+    mag::sub_assign(&mut z1, &z2);
+
+    let mut out = z0;
+    mag::add_assign_at(&mut out, &z1, m);
+    mag::add_assign_at(&mut out, &z2, 2 * m);
+    mag::trim(&mut out);
+    out
+}
+
+/// Toom-Cook 3-way split, evaluating at 0, 1, -1, -2 and infinity with
+/// Bodrato's interpolation sequence. Requires `a.len() >= b.len() > a.len() / 2`.
+fn toom3(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let k = a.len().div_ceil(3);
+    let split = |x: &[u64]| -> [BigInt; 3] {
+        std::array::from_fn(|i| {
+            let lo = (i * k).min(x.len());
+            let hi = ((i + 1) * k).min(x.len());
+            BigInt::from_limbs(false, x[lo..hi].to_vec())
+        })
+    };
+    let evaluate = |[x0, x1, x2]: &[BigInt; 3]| -> [BigInt; 5] {
+        let even = x0 + x2;
+        let at_one = &even + x1;
+        let at_minus_one = &even - x1;
+        let mut at_minus_two = &at_minus_one + x2;
+        at_minus_two.mul_small_assign(2);
+        let at_minus_two = &at_minus_two - x0;
+        [x0.clone(), at_one, at_minus_one, at_minus_two, x2.clone()]
+    };
+    let pa = evaluate(&split(a));
+    let pb = evaluate(&split(b));
+    let [r0, r1, rm1, rm2, rinf]: [BigInt; 5] = std::array::from_fn(|i| &pa[i] * &pb[i]);
+
+    let r3 = (&rm2 - &r1).div_exact_small(3);
+    let r1 = (&r1 - &rm1).div_exact_small(2);
+    let r2 = &rm1 - &r0;
+    let mut rinf2 = rinf.clone();
+    rinf2.mul_small_assign(2);
+    let r3 = &(&r2 - &r3).div_exact_small(2) + &rinf2;
+    let r2 = &(&r2 + &r1) - &rinf;
+    let r1 = &r1 - &r3;
+
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    for (i, coefficient) in [r0, r1, r2, r3, rinf].iter().enumerate() {
+        debug_assert!(!coefficient.is_negative());
+        mag::add_assign_at(&mut out, coefficient.limbs(), i * k);
+    }
+    mag::trim(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::mag::tests::XorShift;
+
+    #[test]
+    fn test_fast_paths_match_schoolbook() {
+        let mut rng = XorShift(0x0123_4567_89AB_CDEF);
+        let sizes = [
+            (KARATSUBA_THRESHOLD, KARATSUBA_THRESHOLD),
+            (KARATSUBA_THRESHOLD * 3 + 1, KARATSUBA_THRESHOLD * 2),
+            (TOOM3_THRESHOLD + 7, TOOM3_THRESHOLD),
+            (TOOM3_THRESHOLD * 2, TOOM3_THRESHOLD + 5),
+            (TOOM3_THRESHOLD * 5, TOOM3_THRESHOLD + 1),
+        ];
+        for (alen, blen) in sizes {
+            let a = rng.limbs(alen);
+            let b = rng.limbs(blen);
+            assert_eq!(mul(&a, &b), schoolbook(&a, &b), "{alen}x{blen}");
+            assert_eq!(mul(&b, &a), schoolbook(&a, &b), "{blen}x{alen}");
+        }
+    }
+
+    #[test]
+    fn test_all_ones_operands() {
+        // (B^n - 1)^2 = B^2n - 2 B^n + 1 stresses every carry path.
+        let n = TOOM3_THRESHOLD * 2 + 3;
+        let a = vec![u64::MAX; n];
+        let mut expected = vec![0u64; 2 * n];
+        expected[0] = 1;
+        expected[n] = u64::MAX - 1;
+        for limb in &mut expected[n + 1..] {
+            *limb = u64::MAX;
+        }
+        assert_eq!(mul(&a, &a), expected);
+        assert_eq!(karatsuba(&a, &a), expected);
+    }
+}
@@ -0,0 +1,612 @@
+//! Counting functions: binomial and multinomial coefficients, Catalan,
+//! Stirling and Bell numbers, and derangements.
+//!
+//! Each comes as a `*_checked` export writing a `u64` (reporting
+//! `MathStatus::Overflow` instead of wrapping) and a `*_big` export returning
+//! a `BigInt` handle. The checked versions never form an intermediate larger
+//! than the final result, so they only fail when the answer itself does not
+//! fit.
+
+use crate::MathStatus;
+use crate::bigint::{self, BigInt};
+use crate::error::{MathError, MathResult};
+use crate::factorial::{big_factorial, legendre_exponent};
+use crate::{ffi, primes};
+
+/// Largest `n` for which big coefficients are assembled from the primes up
+/// to `n`; the sieve needs n / 16 bytes.
+const SIEVE_MAX: u64 = 1 << 30;
+/// The sieve only pays off once the smaller part is at least
+/// `n / SIEVE_SHARE`; below that, the falling factorial of that part is
+/// cheaper than visiting every prime up to `n`.
+const SIEVE_SHARE: u64 = 32;
+
+/// Whether to build a coefficient of `n`! over factorials whose parts other
+/// than the largest sum to `small` from the prime factorisation.
+fn use_sieve(n: u64, small: u64) -> bool {
+    n <= SIEVE_MAX && small >= n / SIEVE_SHARE
+}
+
+/// n! / (parts[0]! parts[1]! ...) from its prime factorisation, for parts
+/// summing to at most `n <= SIEVE_MAX` (or otherwise dividing n!).
+fn factorial_ratio(n: u64, parts: &[u64]) -> MathResult<BigInt> {
+    let powers: Vec<(u64, u64)> = primes::primes_up_to(n)
+        .into_iter()
+        .map(|p| {
+            let divided: u64 = parts.iter().map(|&k| legendre_exponent(k, p)).sum();
+            (p, legendre_exponent(n, p) - divided)
+        })
+        .filter(|&(_, exponent)| exponent > 0)
+        .collect();
+    bigint::power_product(&powers, 1)
+}
+
+fn checked_binomial(n: u64, k: u64) -> MathResult<u64> {
+    if k > n {
+        return Ok(0);
+    }
+    let k = k.min(n - k);
+    // After step i the accumulator is binomial(n - k + i, i), which never
+    // exceeds the result, and each division is exact.
+    let mut acc = 1u64;
+    for i in 1..=k {
+        let next = acc as u128 * (n - k + i) as u128 / i as u128;
+        acc = u64::try_from(next)
+            .map_err(|_| MathError::overflow(format!("binomial({n}, {k}) does not fit in u64")))?;
+    }
+    Ok(acc)
+}
+
+fn big_binomial(n: u64, k: u64) -> MathResult<BigInt> {
+    if k > n {
+        return Ok(BigInt::zero());
+    }
+    let k = k.min(n - k);
+    if k == 0 {
+        return Ok(BigInt::one());
+    }
+    // binomial(n, k) <= (en / k)^k
+    bigint::ensure_fits(k as f64 * ((n as f64 / k as f64).log2() + std::f64::consts::LOG2_E))?;
+    if use_sieve(n, k) {
+        return factorial_ratio(n, &[k, n - k]);
+    }
+    // Too many primes to sieve for so few factors (and beyond `SIEVE_MAX`, k
+    // is small enough for the result to fit): divide the falling factorial
+    // by k!.
+    let falling = bigint::product_of(k as usize, &|i| n - i as u64, 1);
+    Ok(falling.divrem(&big_factorial(k, 1)?)?.0)
+}
+
+fn multinomial_total(ks: &[u64]) -> MathResult<u64> {
+    ks.iter()
+        .try_fold(0u64, |acc, &k| acc.checked_add(k))
+        .ok_or_else(|| MathError::overflow("multinomial total does not fit in u64"))
+}
+
+/// (k_1 + ... + k_m)! / (k_1! ... k_m!) as a product of binomials
+/// binomial(k_1 + ... + k_i, k_i).
+fn checked_multinomial(ks: &[u64]) -> MathResult<u64> {
+    multinomial_total(ks)?;
+    let mut total = 0u64;
+    ks.iter().try_fold(1u64, |acc, &k| {
+        total += k;
+        acc.checked_mul(checked_binomial(total, k)?)
+            .ok_or_else(|| MathError::overflow("multinomial coefficient does not fit in u64"))
+    })
+}
+
+fn big_multinomial(ks: &[u64]) -> MathResult<BigInt> {
+    let total = multinomial_total(ks)?;
+    let largest = ks.iter().copied().max().unwrap_or(0);
+    if use_sieve(total, total - largest) {
+        return factorial_ratio(total, ks);
+    }
+    let mut acc = BigInt::one();
+    let mut prefix = 0u64;
+    for &k in ks {
+        prefix += k;
+        acc = &acc * &big_binomial(prefix, k)?;
+        bigint::ensure_fits(acc.bit_len() as f64)?;
+    }
+    Ok(acc)
+}
+
+/// C(n) = binomial(2n, n) / (n + 1), via C(i + 1) = C(i) * 2(2i + 1) / (i + 2).
+fn checked_catalan(n: u32) -> MathResult<u64> {
+    (0..n as u64).try_fold(1u64, |acc, i| {
+        let next = acc as u128 * (2 * (2 * i + 1)) as u128 / (i + 2) as u128;
+        u64::try_from(next)
+            .map_err(|_| MathError::overflow(format!("Catalan number C({n}) does not fit in u64")))
+    })
+}
+
+fn big_catalan(n: u32) -> MathResult<BigInt> {
+    let n = n as u64;
+    bigint::ensure_fits(2.0 * n as f64)?;
+    factorial_ratio(2 * n, &[n, n + 1])
+}
+
+/// Stirling-type triangle entry T(n, k) for recurrences of the form
+/// T(m, j) = w(m, j) T(m - 1, j) + T(m - 1, j - 1), with T(0, 0) = 1.
+///
+/// Only the band of entries T(j + t, j) with j <= k and t <= n - k feeds into
+/// T(n, k); for the Stirling numbers every one of them is at most T(n, k).
+/// `step(left, w, below)` computes `w * left + below`.
+fn stirling_band<T: Clone>(
+    n: u32,
+    k: u32,
+    zero: T,
+    one: T,
+    weight: impl Fn(u64, u64) -> u64,
+    step: impl Fn(&T, u64, &T) -> MathResult<T>,
+) -> MathResult<T> {
+    if k > n {
+        return Ok(zero);
+    }
+    let width = (n - k) as usize + 1;
+    // row[t] = T(j + t, j), starting from j = 0.
+    let mut row = vec![zero.clone(); width];
+    row[0] = one;
+    for j in 1..=k as u64 {
+        // T(j, j) = 1 for both kinds; the weighted term vanishes at t = 0.
+        let mut left = row[0].clone();
+        for t in 1..width {
+            let next = step(&left, weight(j + t as u64, j), &row[t])?;
+            row[t - 1] = left;
+            left = next;
+        }
+        row[width - 1] = left;
+    }
+    Ok(row.pop().expect("band has at least one column"))
+}
+
+fn checked_step(what: &'static str) -> impl Fn(&u64, u64, &u64) -> MathResult<u64> {
+    move |left, w, below| {
+        left.checked_mul(w)
+            .and_then(|x| x.checked_add(*below))
+            .ok_or_else(|| MathError::overflow(format!("{what} does not fit in u64")))
+    }
+}
+
+fn big_step(left: &BigInt, w: u64, below: &BigInt) -> MathResult<BigInt> {
+    let mut scaled = left.clone();
+    scaled.mul_small_assign(w);
+    Ok(&scaled + below)
+}
+
+/// Unsigned Stirling numbers of the first kind: c(m, j) = (m - 1) c(m - 1, j)
+/// + c(m - 1, j - 1), the permutations of m elements with j cycles.
+fn stirling1_weight(m: u64, _j: u64) -> u64 {
+    m - 1
+}
+
+/// Stirling numbers of the second kind: S(m, j) = j S(m - 1, j) + S(m - 1, j - 1),
+/// the partitions of m elements into j blocks.
+fn stirling2_weight(_m: u64, j: u64) -> u64 {
+    j
+}
+
+fn big_stirling(n: u32, k: u32, weight: impl Fn(u64, u64) -> u64) -> MathResult<BigInt> {
+    // Both kinds are bounded by n^n.
+    bigint::ensure_fits(n as f64 * (n as f64).log2())?;
+    stirling_band(n, k, BigInt::zero(), BigInt::one(), weight, big_step)
+}
+
+/// Bell numbers from the Bell triangle: each row starts with the last entry
+/// of the previous one and adds the entry above-left, and B(n) is the last
+/// entry of row n - 1. No entry in rows below n exceeds B(n).
+fn bell_triangle<T: Clone>(n: u32, one: T, add: impl Fn(&T, &T) -> MathResult<T>) -> MathResult<T> {
+    let mut row = vec![one];
+    for _ in 1..n {
+        let mut next = Vec::with_capacity(row.len() + 1);
+        next.push(row[row.len() - 1].clone());
+        for above in &row {
+            let value = add(&next[next.len() - 1], above)?;
+            next.push(value);
+        }
+        row = next;
+    }
+    Ok(row.pop().expect("Bell triangle rows are non-empty"))
+}
+
+fn checked_bell(n: u32) -> MathResult<u64> {
+    bell_triangle(n, 1u64, |a, b| {
+        a.checked_add(*b)
+            .ok_or_else(|| MathError::overflow(format!("Bell number B({n}) does not fit in u64")))
+    })
+}
+
+fn big_bell(n: u32) -> MathResult<BigInt> {
+    bigint::ensure_fits(n as f64 * (n as f64).log2())?;
+    bell_triangle(n, BigInt::one(), |a, b| Ok(a + b))
+}
+
+/// !n = n * !(n - 1) + (-1)^n
+fn checked_derangements(n: u32) -> MathResult<u64> {
+    (1..=n as u64)
+        .try_fold(1u64, |acc, i| {
+            let scaled = acc.checked_mul(i)?;
+            if i % 2 == 1 {
+                Some(scaled - 1)
+            } else {
+                scaled.checked_add(1)
+            }
+        })
+        .ok_or_else(|| MathError::overflow(format!("!{n} does not fit in u64")))
+}
+
+/// Binary splitting of !n = sum_{k=0..n} (-1)^k n! / k!: over `a..b` returns
+/// P = (a + 1)(a + 2)...b and S = sum_{k=a..b-1} (-1)^k (k + 1)(k + 2)...b,
+/// so that halves combine as P = P1 P2 and S = S1 P2 + S2.
+fn derangement_split(a: u64, b: u64) -> (BigInt, BigInt) {
+    if b - a == 1 {
+        let p = BigInt::from(b);
+        let s = if a.is_multiple_of(2) { p.clone() } else { -&p };
+        return (p, s);
+    }
+    let mid = a + (b - a) / 2;
+    let (p1, s1) = derangement_split(a, mid);
+    let (p2, s2) = derangement_split(mid, b);
+    (&p1 * &p2, &(&s1 * &p2) + &s2)
+}
+
+fn big_derangements(n: u32) -> MathResult<BigInt> {
+    bigint::ensure_fits(n as f64 * (n as f64).log2())?;
+    let last = BigInt::from(if n.is_multiple_of(2) { 1i64 } else { -1 });
+    if n == 0 {
+        return Ok(last);
+    }
+    Ok(&derangement_split(0, n as u64).1 + &last)
+}
+
+/// Calculate the binomial coefficient "n choose k" into `out` (zero when
+/// k > n).
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn binomial_checked(n: u64, k: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_binomial(n, k)?) })
+}
+
+/// Calculate "n choose k" exactly as a big integer, or return null on
+/// failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn binomial_big(n: u64, k: u64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_binomial(n, k).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the multinomial coefficient (k_0 + ... + k_{len-1})! /
+/// (k_0! ... k_{len-1}!) into `out`.
+///
+/// # Safety
+///
+/// `ks` must be null (with `len == 0`) or valid for reading `len` values, and
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn multinomial_checked(
+    ks: *const u64,
+    len: usize,
+    out: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let ks = unsafe { ffi::slice_ref(ks, len, "ks") }?;
+        unsafe { ffi::write_out(out, "out", checked_multinomial(ks)?) }
+    })
+}
+
+/// Calculate the multinomial coefficient exactly as a big integer, or return
+/// null on failure.
+///
+/// # Safety
+///
+/// `ks` must be null (with `len == 0`) or valid for reading `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn multinomial_big(ks: *const u64, len: usize) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        let ks = unsafe { ffi::slice_ref(ks, len, "ks") }?;
+        big_multinomial(ks).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the Catalan number C(n) = binomial(2n, n) / (n + 1) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn catalan_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_catalan(n)?) })
+}
+
+/// Calculate C(n) exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn catalan_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_catalan(n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the unsigned Stirling number of the first kind [n k] (the
+/// permutations of n elements with k cycles) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stirling1_checked(n: u32, k: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let what = "Stirling number of the first kind";
+        let value = stirling_band(n, k, 0, 1, stirling1_weight, checked_step(what))?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Calculate [n k] exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn stirling1_big(n: u32, k: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_stirling(n, k, stirling1_weight).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the Stirling number of the second kind {n k} (the partitions of
+/// n elements into k non-empty blocks) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stirling2_checked(n: u32, k: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let what = "Stirling number of the second kind";
+        let value = stirling_band(n, k, 0, 1, stirling2_weight, checked_step(what))?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Calculate {n k} exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn stirling2_big(n: u32, k: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_stirling(n, k, stirling2_weight).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the Bell number B(n) (the partitions of an n-element set) into
+/// `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bell_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_bell(n)?) })
+}
+
+/// Calculate B(n) exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn bell_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || big_bell(n).map(ffi::into_handle))
+}
+
+/// Calculate the number of derangements !n (permutations of n elements with
+/// no fixed point) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn derangements_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_derangements(n)?) })
+}
+
+/// Calculate !n exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn derangements_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_derangements(n).map(ffi::into_handle)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn low_digits(value: &BigInt) -> String {
+        let s = value.to_string();
+        s[s.len().saturating_sub(20)..].to_owned()
+    }
+
+    fn checked_stirling1(n: u32, k: u32) -> MathResult<u64> {
+        stirling_band(n, k, 0, 1, stirling1_weight, checked_step("c(n, k)"))
+    }
+
+    fn checked_stirling2(n: u32, k: u32) -> MathResult<u64> {
+        stirling_band(n, k, 0, 1, stirling2_weight, checked_step("S(n, k)"))
+    }
+
+    #[test]
+    fn test_binomial() {
+        let mut row = vec![1u128];
+        for n in 0..=67u64 {
+            for k in 0..=n + 1 {
+                let expected = row.get(k as usize).map_or(0, |&c| c as u64);
+                assert_eq!(checked_binomial(n, k).unwrap(), expected, "C({n}, {k})");
+                assert_eq!(big_binomial(n, k).unwrap(), BigInt::from(expected));
+            }
+            row = (0..=row.len())
+                .map(|k| {
+                    if k == 0 || k == row.len() {
+                        1
+                    } else {
+                        row[k - 1] + row[k]
+                    }
+                })
+                .collect();
+        }
+        assert_eq!(
+            checked_binomial(67, 33).unwrap(),
+            14_226_520_737_620_288_370
+        );
+        assert_eq!(
+            checked_binomial(68, 34).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        assert_eq!(checked_binomial(u64::MAX, 1).unwrap(), u64::MAX);
+        assert_eq!(checked_binomial(u64::MAX, u64::MAX - 1).unwrap(), u64::MAX);
+
+        let big = big_binomial(1000, 500).unwrap();
+        assert_eq!(big.to_string().len(), 300);
+        assert_eq!(low_digits(&big), "96905863799821216320");
+        // Beyond the sieve: binomial(2^40, 3) by the falling-factorial path.
+        let n = 1u64 << 40;
+        let expected = &(&BigInt::from(n) * &BigInt::from(n - 1)) * &BigInt::from(n - 2);
+        assert_eq!(big_binomial(n, 3).unwrap(), expected.divrem_small(6).0);
+        // Within the sieve range, a small k still skips the sieve.
+        let n = 1u64 << 30;
+        assert_eq!(big_binomial(n, 1).unwrap(), BigInt::from(n));
+        assert_eq!(
+            big_binomial(n, n - 2).unwrap(),
+            BigInt::from((n / 2) * (n - 1))
+        );
+    }
+
+    #[test]
+    fn test_multinomial() {
+        assert_eq!(checked_multinomial(&[]).unwrap(), 1);
+        assert_eq!(
+            checked_multinomial(&[10, 10, 10]).unwrap(),
+            5_550_996_791_340
+        );
+        assert_eq!(
+            big_multinomial(&[10, 10, 10]).unwrap(),
+            BigInt::from(5_550_996_791_340u64)
+        );
+        assert_eq!(checked_multinomial(&[3, 0, 2]).unwrap(), 10);
+        assert_eq!(
+            checked_multinomial(&[u64::MAX, 1]).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        let big = big_multinomial(&[100, 200, 300]).unwrap();
+        let by_binomials = &big_binomial(300, 100).unwrap() * &big_binomial(600, 300).unwrap();
+        assert_eq!(big, by_binomials);
+        let n = 1u64 << 30;
+        assert_eq!(
+            big_multinomial(&[1, n - 2, 1]).unwrap(),
+            BigInt::from(n * (n - 1))
+        );
+    }
+
+    #[test]
+    fn test_catalan() {
+        let small = [1u64, 1, 2, 5, 14, 42, 132, 429, 1430, 4862];
+        for (n, &c) in small.iter().enumerate() {
+            assert_eq!(checked_catalan(n as u32).unwrap(), c);
+            assert_eq!(big_catalan(n as u32).unwrap(), BigInt::from(c));
+        }
+        assert_eq!(checked_catalan(36).unwrap(), 11_959_798_385_860_453_492);
+        assert_eq!(
+            checked_catalan(37).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            low_digits(&big_catalan(1000).unwrap()),
+            "64244732001962029120"
+        );
+    }
+
+    #[test]
+    fn test_stirling() {
+        assert_eq!(checked_stirling1(0, 0).unwrap(), 1);
+        assert_eq!(checked_stirling1(5, 0).unwrap(), 0);
+        assert_eq!(checked_stirling1(10, 3).unwrap(), 1_172_700);
+        assert_eq!(checked_stirling1(20, 10).unwrap(), 381_922_055_502_195);
+        assert_eq!(checked_stirling1(20, 21).unwrap(), 0);
+        // c(n, 1) = (n - 1)!
+        assert_eq!(checked_stirling1(21, 1).unwrap(), 2_432_902_008_176_640_000);
+        assert_eq!(
+            checked_stirling1(22, 1).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+
+        assert_eq!(checked_stirling2(10, 10).unwrap(), 1);
+        assert_eq!(checked_stirling2(20, 5).unwrap(), 749_206_090_500);
+        assert_eq!(checked_stirling2(25, 12).unwrap(), 362_262_620_784_874_680);
+        assert_eq!(
+            checked_stirling2(30, 15).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        // S(n, 2) = 2^(n-1) - 1 only touches a narrow band.
+        assert_eq!(checked_stirling2(64, 2).unwrap(), (1 << 63) - 1);
+
+        for n in 0..=12 {
+            for k in 0..=n {
+                let c = big_stirling(n, k, stirling1_weight).unwrap();
+                assert_eq!(c, BigInt::from(checked_stirling1(n, k).unwrap()));
+                let s = big_stirling(n, k, stirling2_weight).unwrap();
+                assert_eq!(s, BigInt::from(checked_stirling2(n, k).unwrap()));
+            }
+        }
+        let c = big_stirling(100, 50, stirling1_weight).unwrap();
+        assert_eq!(low_digits(&c), "21125692626030268475");
+        let s = big_stirling(100, 50, stirling2_weight).unwrap();
+        assert_eq!(low_digits(&s), "45598261659992013900");
+    }
+
+    #[test]
+    fn test_bell() {
+        let small = [1u64, 1, 2, 5, 15, 52, 203, 877, 4140, 21147];
+        for (n, &b) in small.iter().enumerate() {
+            assert_eq!(checked_bell(n as u32).unwrap(), b);
+            assert_eq!(big_bell(n as u32).unwrap(), BigInt::from(b));
+        }
+        assert_eq!(checked_bell(25).unwrap(), 4_638_590_332_229_999_353);
+        assert_eq!(checked_bell(26).unwrap_err().status(), MathStatus::Overflow);
+        assert_eq!(low_digits(&big_bell(100).unwrap()), "56306953557882560751");
+    }
+
+    #[test]
+    fn test_derangements() {
+        for n in 0..=20 {
+            let checked = checked_derangements(n).unwrap();
+            assert_eq!(big_derangements(n).unwrap(), BigInt::from(checked), "!{n}");
+        }
+        assert_eq!(checked_derangements(4).unwrap(), 9);
+        assert_eq!(checked_derangements(20).unwrap(), 895_014_631_192_902_121);
+        assert_eq!(
+            checked_derangements(21).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            low_digits(&big_derangements(1000).unwrap()),
+            "44750044815550686001"
+        );
+    }
+
+    #[test]
+    fn test_exports() {
+        let mut out = 0u64;
+        assert_eq!(unsafe { binomial_checked(10, 3, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 120);
+        let ks = [2u64, 1, 1];
+        assert_eq!(
+            unsafe { multinomial_checked(ks.as_ptr(), ks.len(), &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 12);
+        assert_eq!(
+            unsafe { multinomial_checked(std::ptr::null(), 2, &mut out) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(unsafe { bell_checked(30, &mut out) }, MathStatus::Overflow);
+
+        let handle = stirling2_big(30, 15);
+        assert_eq!(unsafe { &*handle }.to_string(), "12879868072770626040000");
+        unsafe { bigint::bigint_free(handle) };
+        assert!(binomial_big(u64::MAX, u64::MAX / 2).is_null());
+    }
+}
@@ -0,0 +1,221 @@
+//! Crate-wide error model.
+//!
+//! Every fallible exported function follows the same contract:
+//!
+//! - it returns a [`MathStatus`] (or a sentinel value such as a null handle),
+//! - on failure it stores a [`MathStatus`] and a human-readable message in a
+//!   thread-local "last error" slot, readable with [`math_last_error_code`]
+//!   and [`math_last_error_message`],
+//! - on success it clears that slot, so the slot always describes the most
+//!   recent call made on the current thread,
+//! - it never unwinds into the caller: panics are caught by the guards in
+//!   `ffi` and reported as [`MathStatus::Panic`].
+
+use std::cell::RefCell;
+use std::ffi::{CString, c_char};
+use std::fmt;
+
+use crate::ffi;
+
+/// Status codes returned by the checked entry points.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathStatus {
+    Ok = 0,
+    Overflow = 1,
+    InvalidArgument = 2,
+    DivisionByZero = 3,
+    BufferTooSmall = 4,
+    Panic = 5,
+    NoSolution = 6,
+}
+
+impl MathStatus {
+    fn description(self) -> &'static str {
+        match self {
+            MathStatus::Ok => "ok",
+            MathStatus::Overflow => "overflow",
+            MathStatus::InvalidArgument => "invalid argument",
+            MathStatus::DivisionByZero => "division by zero",
+            MathStatus::BufferTooSmall => "buffer too small",
+            MathStatus::Panic => "internal panic",
+            MathStatus::NoSolution => "no solution",
+        }
+    }
+}
+
+/// An error raised inside the library, carried to the C side through the
+/// last-error slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MathError {
+    status: MathStatus,
+    message: String,
+}
+
+impl MathError {
+    pub(crate) fn new(status: MathStatus, message: impl Into<String>) -> Self {
+        debug_assert_ne!(status, MathStatus::Ok);
+        MathError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn overflow(message: impl Into<String>) -> Self {
+        MathError::new(MathStatus::Overflow, message)
+    }
+
+    pub(crate) fn invalid_argument(message: impl Into<String>) -> Self {
+        MathError::new(MathStatus::InvalidArgument, message)
+    }
+
+    pub(crate) fn status(&self) -> MathStatus {
+        self.status
+    }
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status.description(), self.message)
+    }
+}
+
+pub(crate) type MathResult<T> = Result<T, MathError>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<MathError>> = const { RefCell::new(None) };
+}
+
+/// Store `error` in the last-error slot and return its status code.
+pub(crate) fn set_last_error(error: MathError) -> MathStatus {
+    let status = error.status();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(error));
+    status
+}
+
+pub(crate) fn clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Record the outcome of a status-returning call and convert it to a code.
+pub(crate) fn report(result: MathResult<()>) -> MathStatus {
+    match result {
+        Ok(()) => {
+            clear_last_error();
+            MathStatus::Ok
+        }
+        Err(error) => set_last_error(error),
+    }
+}
+
+/// Status of the most recent call on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn math_last_error_code() -> MathStatus {
+    ffi::catch(MathStatus::Panic, || {
+        LAST_ERROR.with(|slot| {
+            slot.borrow()
+                .as_ref()
+                .map_or(MathStatus::Ok, MathError::status)
+        })
+    })
+}
+
+/// Message describing the most recent failure on this thread, or null if the
+/// most recent call succeeded.
+///
+/// The returned string is owned by the caller and must be released with
+/// `math_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn math_last_error_message() -> *mut c_char {
+    ffi::catch(std::ptr::null_mut(), || {
+        LAST_ERROR.with(|slot| match slot.borrow().as_ref() {
+            Some(error) => into_c_string(error.to_string()),
+            None => std::ptr::null_mut(),
+        })
+    })
+}
+
+/// Reset the last-error slot of this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn math_clear_last_error() {
+    ffi::catch((), clear_last_error);
+}
+
+/// Release a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer previously returned by this library that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn math_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        ffi::catch((), || drop(unsafe { CString::from_raw(s) }));
+    }
+}
+
+/// Hand a Rust string to C. Interior NULs are replaced so the conversion
+/// cannot fail.
+pub(crate) fn into_c_string(s: String) -> *mut c_char {
+    let s = if s.contains('\0') {
+        s.replace('\0', "\u{FFFD}")
+    } else {
+        s
+    };
+    CString::new(s)
+        .expect("interior NULs were replaced")
+        .into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn last_message() -> Option<String> {
+        let ptr = math_last_error_message();
+        if ptr.is_null() {
+            return None;
+        }
+        let message = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned();
+        unsafe { math_free_string(ptr) };
+        Some(message)
+    }
+
+    #[test]
+    fn test_last_error_round_trip() {
+        clear_last_error();
+        assert_eq!(math_last_error_code(), MathStatus::Ok);
+        assert_eq!(last_message(), None);
+
+        let status = report(Err(MathError::overflow("21! does not fit in u64")));
+        assert_eq!(status, MathStatus::Overflow);
+        assert_eq!(math_last_error_code(), MathStatus::Overflow);
+        assert_eq!(
+            last_message().as_deref(),
+            Some("overflow: 21! does not fit in u64")
+        );
+
+        assert_eq!(report(Ok(())), MathStatus::Ok);
+        assert_eq!(math_last_error_code(), MathStatus::Ok);
+    }
+
+    #[test]
+    fn test_last_error_is_thread_local() {
+        set_last_error(MathError::invalid_argument("main thread"));
+        std::thread::spawn(|| assert_eq!(math_last_error_code(), MathStatus::Ok))
+            .join()
+            .unwrap();
+        assert_eq!(math_last_error_code(), MathStatus::InvalidArgument);
+        math_clear_last_error();
+        assert_eq!(math_last_error_code(), MathStatus::Ok);
+    }
+
+    #[test]
+    fn test_interior_nul_is_replaced() {
+        let ptr = into_c_string("a\0b".to_owned());
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned();
+        unsafe { math_free_string(ptr) };
+        assert_eq!(s, "a\u{FFFD}b");
+    }
+}
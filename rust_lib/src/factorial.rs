@@ -0,0 +1,1016 @@
+//! n! and its relatives.
+
+use crate::MathStatus;
+use crate::bigint::{self, BigInt};
+use crate::error::{MathError, MathResult};
+use crate::int128::Uint128;
+use crate::real::Fixed;
+use crate::{ffi, primes};
+
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial(n: u32) -> u64 {
+    ffi::guard(0, || Ok(if n <= 1 { 1 } else { (1..=n as u64).product() }))
+}
+
+fn checked_factorial(n: u32) -> MathResult<u64> {
+    (2..=n as u64)
+        .try_fold(1u64, |acc, k| acc.checked_mul(k))
+        .ok_or_else(|| MathError::overflow(format!("{n}! does not fit in u64")))
+}
+
+/// Calculate n! into `out`, reporting overflow (n > 20) instead of wrapping.
+///
+/// `out` is left untouched unless `MathStatus::Ok` is returned; failures are
+/// also recorded in the last-error slot.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = checked_factorial(n)?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+fn checked_factorial_u128(n: u32) -> MathResult<u128> {
+    (2..=n as u128)
+        .try_fold(1u128, |acc, k| acc.checked_mul(k))
+        .ok_or_else(|| MathError::overflow(format!("{n}! does not fit in u128")))
+}
+
+/// Calculate n! as a 128-bit integer, or return zero (with the overflow
+/// recorded in the last-error slot) for n > 34.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_u128(n: u32) -> Uint128 {
+    ffi::guard(Uint128::default(), || {
+        checked_factorial_u128(n).map(Uint128::from)
+    })
+}
+
+/// Calculate n! into `out`, reporting overflow (n > 34) instead of wrapping.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_u128_checked(n: u32, out: *mut Uint128) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = checked_factorial_u128(n)?;
+        unsafe { ffi::write_out(out, "out", Uint128::from(value)) }
+    })
+}
+
+/// Exponent of the prime `p` in n! (Legendre's formula).
+pub(crate) fn legendre_exponent(n: u64, p: u64) -> u64 {
+    let mut exponent = 0;
+    let mut m = n;
+    while m >= p {
+        m /= p;
+        exponent += m;
+    }
+    exponent
+}
+
+/// Rough bit length of n!, used to refuse results that would not fit in
+/// memory before sieving.
+fn factorial_bits_estimate(n: u64) -> f64 {
+    if n < 2 {
+        return 1.0;
+    }
+    let n = n as f64;
+    (n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln()) / std::f64::consts::LN_2
+}
+
+/// n! from its prime factorisation.
+///
+/// Every prime `p <= n` appears with the exponent given by Legendre's
+/// formula; the odd part is assembled by [`bigint::power_product`], which
+/// keeps every multiplication balanced so the Karatsuba/Toom paths do the
+/// heavy lifting, and the power of two is applied as a final shift.
+/// `threads > 1` splits the product trees across threads.
+pub(crate) fn big_factorial(n: u64, threads: usize) -> MathResult<BigInt> {
+    bigint::ensure_fits(factorial_bits_estimate(n))?;
+    let powers: Vec<(u64, u64)> = primes::primes_up_to(n)
+        .into_iter()
+        .skip(1)
+        .map(|p| (p, legendre_exponent(n, p)))
+        .collect();
+    bigint::power_product(&powers, threads)?.shl(legendre_exponent(n, 2))
+}
+
+/// Calculate n! exactly as a big integer, or return null on failure.
+///
+/// The handle must be released with `bigint_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_factorial(n as u64, 1).map(ffi::into_handle)
+    })
+}
+
+/// Like `factorial_big`, but spreads the multiplications over `threads`
+/// threads (0 picks the number of available cores). The result does not
+/// depend on the thread count.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_big_parallel(n: u32, threads: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_factorial(n as u64, ffi::thread_count(threads)).map(ffi::into_handle)
+    })
+}
+
+/// n(n-k)(n-2k)... over the positive terms.
+fn checked_multifactorial(n: u32, k: u32) -> MathResult<u64> {
+    check_step(k)?;
+    (1..=n)
+        .rev()
+        .step_by(k as usize)
+        .try_fold(1u64, |acc, term| acc.checked_mul(term as u64))
+        .ok_or_else(|| MathError::overflow(format!("{n}!({k}) does not fit in u64")))
+}
+
+fn big_multifactorial(n: u32, k: u32) -> MathResult<BigInt> {
+    check_step(k)?;
+    let terms = if n == 0 { 0 } else { (n - 1) / k + 1 };
+    // At least half of the terms are >= n / 2.
+    bigint::ensure_fits(terms as f64 / 2.0 * (n as f64 / 2.0).log2().max(0.0))?;
+    Ok(bigint::product_of(
+        terms as usize,
+        &|i| n as u64 - i as u64 * k as u64,
+        1,
+    ))
+}
+
+fn check_step(k: u32) -> MathResult<()> {
+    if k == 0 {
+        Err(MathError::invalid_argument(
+            "multifactorial step must be positive",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// x(x+1)...(x+n-1), the Pochhammer symbol.
+fn checked_rising(x: u64, n: u32) -> MathResult<u64> {
+    (0..n as u64)
+        .try_fold(1u64, |acc, i| acc.checked_mul(x.checked_add(i)?))
+        .ok_or_else(|| {
+            MathError::overflow(format!("rising factorial {x}^({n}) does not fit in u64"))
+        })
+}
+
+fn big_rising(x: u64, n: u32) -> MathResult<BigInt> {
+    if n == 0 {
+        return Ok(BigInt::one());
+    }
+    if x == 0 {
+        return Ok(BigInt::zero());
+    }
+    if x.checked_add(n as u64 - 1).is_none() {
+        return Err(MathError::overflow(format!(
+            "rising factorial {x}^({n}) has a factor above u64::MAX"
+        )));
+    }
+    bigint::ensure_fits(n as f64 * (x as f64).log2())?;
+    Ok(bigint::product_of(n as usize, &|i| x + i as u64, 1))
+}
+
+/// x(x-1)...(x-n+1); zero once the terms reach zero.
+fn checked_falling(x: u64, n: u32) -> MathResult<u64> {
+    if n as u64 > x {
+        return Ok(0);
+    }
+    (0..n as u64)
+        .try_fold(1u64, |acc, i| acc.checked_mul(x - i))
+        .ok_or_else(|| {
+            MathError::overflow(format!("falling factorial {x}_({n}) does not fit in u64"))
+        })
+}
+
+fn big_falling(x: u64, n: u32) -> MathResult<BigInt> {
+    if n as u64 > x {
+        return Ok(BigInt::zero());
+    }
+    bigint::ensure_fits(n as f64 * ((x - n as u64 + 1) as f64).log2())?;
+    Ok(bigint::product_of(n as usize, &|i| x - i as u64, 1))
+}
+
+/// Product of the primes `<= n`.
+fn checked_primorial(n: u32) -> MathResult<u64> {
+    // 53# is the first primorial above u64::MAX, so a short sieve suffices.
+    primes::primes_up_to((n as u64).min(64))
+        .into_iter()
+        .try_fold(1u64, |acc, p| acc.checked_mul(p))
+        .ok_or_else(|| MathError::overflow(format!("{n}# does not fit in u64")))
+}
+
+fn big_primorial(n: u32) -> MathResult<BigInt> {
+    // log2(n#) is about n / ln 2 by the prime number theorem.
+    bigint::ensure_fits(n as f64 / std::f64::consts::LN_2 * 0.9)?;
+    Ok(bigint::product(&primes::primes_up_to(n as u64), 1))
+}
+
+/// 1! * 2! * ... * n!
+fn checked_superfactorial(n: u32) -> MathResult<u64> {
+    let mut factorial = 1u64;
+    (2..=n as u64)
+        .try_fold(1u64, |acc, k| {
+            factorial = factorial.checked_mul(k)?;
+            acc.checked_mul(factorial)
+        })
+        .ok_or_else(|| MathError::overflow(format!("superfactorial of {n} does not fit in u64")))
+}
+
+/// 1^1 * 2^2 * ... * n^n
+fn checked_hyperfactorial(n: u32) -> MathResult<u64> {
+    (2..=n)
+        .try_fold(1u64, |acc, k| acc.checked_mul((k as u64).checked_pow(k)?))
+        .ok_or_else(|| MathError::overflow(format!("hyperfactorial of {n} does not fit in u64")))
+}
+
+/// `prod_{k=2..=n} k^exponent(k)` for the super- and hyperfactorial.
+fn big_power_family(n: u32, exponent: impl Fn(u64) -> u64) -> MathResult<BigInt> {
+    // Sum the size while building the list so that absurd `n` fail fast.
+    let mut bits = 0.0;
+    let mut powers = Vec::new();
+    for k in 2..=n as u64 {
+        bits += exponent(k) as f64 * (k as f64).log2();
+        bigint::ensure_fits(bits)?;
+        powers.push((k, exponent(k)));
+    }
+    bigint::power_product(&powers, 1)
+}
+
+fn big_superfactorial(n: u32) -> MathResult<BigInt> {
+    big_power_family(n, |k| n as u64 + 1 - k)
+}
+
+fn big_hyperfactorial(n: u32) -> MathResult<BigInt> {
+    big_power_family(n, |k| k)
+}
+
+/// Calculate the double factorial n!! = n(n-2)(n-4)... into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn double_factorial_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_multifactorial(n, 2)?) })
+}
+
+/// Calculate n!! exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn double_factorial_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_multifactorial(n, 2).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the k-multifactorial n(n-k)(n-2k)... into `out`; `k` must be
+/// positive.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn multifactorial_checked(n: u32, k: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_multifactorial(n, k)?) })
+}
+
+/// Calculate the k-multifactorial exactly as a big integer, or return null
+/// on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn multifactorial_big(n: u32, k: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_multifactorial(n, k).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the rising factorial x(x+1)...(x+n-1) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rising_factorial_checked(x: u64, n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_rising(x, n)?) })
+}
+
+/// Calculate the rising factorial exactly as a big integer, or return null
+/// on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn rising_factorial_big(x: u64, n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_rising(x, n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the falling factorial x(x-1)...(x-n+1) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn falling_factorial_checked(x: u64, n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_falling(x, n)?) })
+}
+
+/// Calculate the falling factorial exactly as a big integer, or return null
+/// on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn falling_factorial_big(x: u64, n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_falling(x, n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the primorial n# (product of the primes <= n) into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn primorial_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_primorial(n)?) })
+}
+
+/// Calculate n# exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn primorial_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_primorial(n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the superfactorial 1! * 2! * ... * n! into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn superfactorial_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_superfactorial(n)?) })
+}
+
+/// Calculate the superfactorial exactly as a big integer, or return null on
+/// failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn superfactorial_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_superfactorial(n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the hyperfactorial 1^1 * 2^2 * ... * n^n into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hyperfactorial_checked(n: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_hyperfactorial(n)?) })
+}
+
+/// Calculate the hyperfactorial exactly as a big integer, or return null on
+/// failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn hyperfactorial_big(n: u32) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_hyperfactorial(n).map(ffi::into_handle)
+    })
+}
+
+/// Exponent of the prime `p` in n!; fails if `p` is not prime.
+fn checked_prime_valuation(n: u64, p: u64) -> MathResult<u64> {
+    if !primes::is_prime(p) {
+        return Err(MathError::invalid_argument(format!("{p} is not prime")));
+    }
+    Ok(legendre_exponent(n, p))
+}
+
+/// Number of trailing zeros of n! written in `base`: the minimum over the
+/// prime powers `p^k` exactly dividing `base` of `floor(e_p(n!) / k)`.
+fn checked_trailing_zeros(n: u64, base: u32) -> MathResult<u64> {
+    if base < 2 {
+        return Err(MathError::invalid_argument(format!(
+            "base {base} is below 2"
+        )));
+    }
+    let mut rest = base as u64;
+    let mut zeros = u64::MAX;
+    let mut p = 2;
+    while p * p <= rest {
+        if rest.is_multiple_of(p) {
+            let mut k = 0;
+            while rest.is_multiple_of(p) {
+                rest /= p;
+                k += 1;
+            }
+            zeros = zeros.min(legendre_exponent(n, p) / k);
+        }
+        p += 1;
+    }
+    if rest > 1 {
+        zeros = zeros.min(legendre_exponent(n, rest));
+    }
+    Ok(zeros)
+}
+
+/// Below this, `factorial_approx` takes the logarithm of the exact n!;
+/// from here on Stirling's series is accurate far beyond f64.
+const STIRLING_MIN: u64 = 10_000;
+/// Fractional bits used for log10(n!); n ln n needs 70 bits of headroom.
+const LOG_PREC: usize = 192;
+/// Precision of the second attempt at a digit count whose log10(n!) lies
+/// too close to an integer for `LOG_PREC`.
+const LOG_PREC_RETRY: usize = 448;
+/// Below this, an unresolved digit count is read off the exact n!.
+const DIGITS_EXACT_MAX: u64 = 1 << 12;
+/// B_2k / (2k (2k - 1)) for k = 1..=8, the coefficients of 1/n^(2k-1) in
+/// the Stirling series.
+const STIRLING_TERMS: [(i64, u64); 8] = [
+    (1, 12),
+    (-1, 360),
+    (1, 1260),
+    (-1, 1680),
+    (1, 1188),
+    (-691, 360_360),
+    (1, 156),
+    (-3617, 122_400),
+];
+
+/// log10(n!) for `n >= 1`, via the Stirling series for ln Γ(n+1)
+/// (Kamenetsky's formula with correction terms):
+///
+/// ln n! = (n + 1/2) ln n - n + ln(2π)/2 + 1/(12n) - 1/(360n³) + ...
+///
+/// See [`log10_factorial_error`] for the accuracy.
+pub(crate) fn log10_factorial(n: u64, prec: usize) -> Fixed {
+    debug_assert!(n >= 1);
+    let x = Fixed::from_u64(n, prec);
+    let ln_n = x.ln();
+    let half_ln_2pi = Fixed::pi(prec).mul_u64(2).ln().div_u64(2);
+
+    let mut ln_fact = &(&x * &ln_n) + &ln_n.div_u64(2);
+    ln_fact = &ln_fact - &x;
+    ln_fact = &ln_fact + &half_ln_2pi;
+    let mut power = Fixed::one(prec).div_u64(n);
+    for (numerator, denominator) in STIRLING_TERMS {
+        let term = power.mul_u64(numerator.unsigned_abs()).div_u64(denominator);
+        ln_fact = if numerator > 0 {
+            &ln_fact + &term
+        } else {
+            &ln_fact - &term
+        };
+        power = power.div_u64(n).div_u64(n);
+    }
+    &ln_fact / &Fixed::ln10(prec)
+}
+
+/// A bound on the absolute error of [`log10_factorial`]. The series
+/// alternates, so the truncation error is below the first omitted term,
+/// 43867 / (244188 n^17) < n^-17; the fixed-point rounding of a few dozen
+/// operations on values up to 2^70 stays below 2^(80 - prec).
+fn log10_factorial_error(n: u64, prec: usize) -> f64 {
+    (n as f64).powi(-17) + 2f64.powi(80 - prec as i32)
+}
+
+/// Number of decimal digits of n!, floor(log10 n!) + 1.
+///
+/// The floor is taken from Stirling's series when the error bound keeps
+/// log10(n!) away from an integer; otherwise it is recomputed at a higher
+/// precision, or from the exact n! for small `n`. log10(n!) is never an
+/// integer for n >= 2, so the retry only fails if it lies within about
+/// n^-17 of one.
+fn checked_digit_count(n: u64) -> MathResult<u128> {
+    if n < 2 {
+        return Ok(1);
+    }
+    for prec in [LOG_PREC, LOG_PREC_RETRY] {
+        let log = log10_factorial(n, prec);
+        let floor = log.floor();
+        let fraction = &log - &Fixed::from_int(&floor, prec);
+        let error = log10_factorial_error(n, prec);
+        if fraction.to_f64() > error && (&Fixed::one(prec) - &fraction).to_f64() > error {
+            let digits = floor
+                .limbs()
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &limb| acc << 64 | limb as u128);
+            return Ok(digits + 1);
+        }
+        if n < DIGITS_EXACT_MAX {
+            return Ok(big_factorial(n, 1)?.to_string().len() as u128);
+        }
+    }
+    Err(MathError::overflow(format!(
+        "log10({n}!) is within {} of an integer, beyond the working precision",
+        log10_factorial_error(n, LOG_PREC_RETRY)
+    )))
+}
+
+/// n! written as `mantissa * 10^exponent` with `1 <= mantissa < 10`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FactorialApprox {
+    pub mantissa: f64,
+    pub exponent: u64,
+}
+
+fn approx_factorial(n: u64) -> MathResult<FactorialApprox> {
+    let log = if n < STIRLING_MIN {
+        &Fixed::from_int(&big_factorial(n, 1)?, LOG_PREC).ln() / &Fixed::ln10(LOG_PREC)
+    } else {
+        log10_factorial(n, LOG_PREC)
+    };
+    let floor = log.floor();
+    let mut exponent = floor.to_u64().ok_or_else(|| {
+        MathError::overflow(format!("decimal exponent of {n}! does not fit in u64"))
+    })?;
+    let fraction = &log - &Fixed::from_int(&floor, LOG_PREC);
+    let mut mantissa = 10f64.powf(fraction.to_f64());
+    if mantissa >= 10.0 {
+        // The fraction rounded up to 1.0 in f64.
+        mantissa /= 10.0;
+        exponent += 1;
+    }
+    Ok(FactorialApprox { mantissa, exponent })
+}
+
+/// n! as a decimal mantissa and exponent, for any n up to about 1.05e18,
+/// e.g. 1000! = 4.0238726007709377e2567.
+///
+/// The mantissa is accurate to a few units in the last place. On overflow
+/// of the exponent the mantissa is NaN and `MathStatus::Overflow` is
+/// recorded in the last-error slot.
+#[unsafe(no_mangle)]
+pub extern "C" fn factorial_approx(n: u64) -> FactorialApprox {
+    let failed = FactorialApprox {
+        mantissa: f64::NAN,
+        exponent: 0,
+    };
+    ffi::guard(failed, || approx_factorial(n))
+}
+
+/// Store the exponent of the prime `p` in n! (Legendre's formula) in `out`.
+///
+/// Returns `MathStatus::InvalidArgument` if `p` is not prime.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_prime_valuation(n: u64, p: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_prime_valuation(n, p)?) })
+}
+
+/// Store the number of trailing zeros of n! written in `base` (at least 2)
+/// in `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_trailing_zeros(n: u64, base: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_trailing_zeros(n, base)?) })
+}
+
+/// Store the number of decimal digits of n! in `out`, for any `n`.
+///
+/// The count comes from Stirling's series with a rigorous error bound; only
+/// when that bound cannot settle it is the series recomputed at a higher
+/// precision, or n! evaluated for n below 4096. It exceeds `uint64_t` from
+/// n ≈ 1.05e18 on, so it is written as a `Uint128`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `Uint128`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_digit_count(n: u64, out: *mut Uint128) -> MathStatus {
+    ffi::guard_status(|| {
+        let digits = checked_digit_count(n)?;
+        unsafe { ffi::write_out(out, "out", Uint128::from(digits)) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use std::ptr;
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(factorial(0), 1);
+        assert_eq!(factorial(1), 1);
+        assert_eq!(factorial(5), 120);
+        assert_eq!(factorial(10), 3628800);
+    }
+
+    #[test]
+    fn test_factorial_checked() {
+        let mut out = 0u64;
+        assert_eq!(unsafe { factorial_checked(0, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 1);
+        assert_eq!(unsafe { factorial_checked(20, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 2432902008176640000);
+
+        out = 7;
+        assert_eq!(
+            unsafe { factorial_checked(21, &mut out) },
+            MathStatus::Overflow
+        );
+        assert_eq!(out, 7);
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+        assert_eq!(
+            unsafe { factorial_checked(5, ptr::null_mut()) },
+            MathStatus::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_factorial_u128() {
+        for n in 0..=20 {
+            assert_eq!(factorial_u128(n), Uint128::from(factorial(n) as u128));
+        }
+        let expected: u128 = 295_232_799_039_604_140_847_618_609_643_520_000_000;
+        assert_eq!(factorial_u128(34), Uint128::from(expected));
+        assert_eq!(factorial_u128(35), Uint128::default());
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+
+        let mut out = Uint128::default();
+        assert_eq!(
+            unsafe { factorial_u128_checked(34, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(u128::from(out), expected);
+        assert_eq!(
+            unsafe { factorial_u128_checked(35, &mut out) },
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            unsafe { factorial_u128_checked(5, ptr::null_mut()) },
+            MathStatus::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_factorial_big() {
+        for n in 0..=20 {
+            let exact = big_factorial(n as u64, 1).unwrap();
+            assert_eq!(exact.to_string(), factorial(n).to_string());
+        }
+        assert_eq!(
+            big_factorial(30, 1).unwrap().to_string(),
+            "265252859812191058636308480000000"
+        );
+
+        let handle = factorial_big(1000);
+        let digits = ffi::take_string(unsafe { crate::bigint::bigint_to_string(handle) });
+        unsafe { crate::bigint::bigint_free(handle) };
+        assert_eq!(digits.len(), 2568);
+        assert!(digits.starts_with("402387260077093773543702433923003985719374864210"));
+        assert!(digits.ends_with(&"0".repeat(249)));
+        assert!(!digits.ends_with(&"0".repeat(250)));
+    }
+
+    fn naive_factorial(n: u64) -> BigInt {
+        let mut acc = BigInt::one();
+        for k in 2..=n {
+            acc.mul_small_assign(k);
+        }
+        acc
+    }
+
+    #[test]
+    fn test_big_factorial_matches_naive_fold() {
+        for n in (0..300).chain([511, 512, 1000, 4567]) {
+            assert_eq!(big_factorial(n, 1).unwrap(), naive_factorial(n), "{n}!");
+        }
+        assert_eq!(big_factorial(4567, 4).unwrap(), naive_factorial(4567));
+    }
+
+    #[test]
+    fn test_big_factorial_matches_naive_fold_at_scale() {
+        let n = 30_000;
+        assert_eq!(big_factorial(n, 1).unwrap(), naive_factorial(n));
+    }
+
+    /// Wall-clock comparison; run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore = "timing benchmark"]
+    fn bench_big_factorial_beats_naive_fold() {
+        use std::time::Instant;
+
+        let n = 30_000;
+        let start = Instant::now();
+        let naive = naive_factorial(n);
+        let naive_time = start.elapsed();
+
+        let start = Instant::now();
+        let fast = big_factorial(n, 1).unwrap();
+        let fast_time = start.elapsed();
+
+        assert_eq!(fast, naive);
+        assert!(
+            fast_time < naive_time,
+            "prime factorisation took {fast_time:?}, naive fold {naive_time:?}"
+        );
+    }
+
+    #[test]
+    fn test_factorial_big_parallel() {
+        let sequential = factorial_big(3000);
+        for threads in [0, 1, 2, 5] {
+            let parallel = factorial_big_parallel(3000, threads);
+            assert_eq!(
+                unsafe { crate::bigint::bigint_compare(sequential, parallel) },
+                0
+            );
+            unsafe { crate::bigint::bigint_free(parallel) };
+        }
+        unsafe { crate::bigint::bigint_free(sequential) };
+
+        assert!(factorial_big(u32::MAX).is_null());
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+    }
+
+    fn big(n: u64) -> BigInt {
+        big_factorial(n, 1).unwrap()
+    }
+
+    /// The checked value must agree with the big one whenever it fits.
+    fn assert_consistent(checked: MathResult<u64>, big: MathResult<BigInt>) {
+        let big = big.unwrap();
+        match checked {
+            Ok(value) => assert_eq!(BigInt::from(value), big),
+            Err(err) => {
+                assert_eq!(err.status(), MathStatus::Overflow);
+                assert!(big > BigInt::from(u64::MAX));
+            }
+        }
+    }
+
+    #[test]
+    fn test_factorial_family_small_values() {
+        let double: Vec<u64> = (0..10)
+            .map(|n| checked_multifactorial(n, 2).unwrap())
+            .collect();
+        assert_eq!(double, [1, 1, 2, 3, 8, 15, 48, 105, 384, 945]);
+        assert_eq!(checked_multifactorial(10, 3).unwrap(), 280);
+        assert_eq!(checked_rising(3, 4).unwrap(), 360);
+        assert_eq!(checked_rising(0, 0).unwrap(), 1);
+        assert_eq!(checked_rising(0, 3).unwrap(), 0);
+        assert_eq!(checked_falling(7, 3).unwrap(), 210);
+        assert_eq!(checked_falling(3, 5).unwrap(), 0);
+        assert_eq!(checked_primorial(10).unwrap(), 210);
+        assert_eq!(checked_primorial(47).unwrap(), 614_889_782_588_491_410);
+        let superf: Vec<u64> = (0..6).map(|n| checked_superfactorial(n).unwrap()).collect();
+        assert_eq!(superf, [1, 1, 2, 12, 288, 34_560]);
+        let hyper: Vec<u64> = (0..6).map(|n| checked_hyperfactorial(n).unwrap()).collect();
+        assert_eq!(hyper, [1, 1, 4, 108, 27_648, 86_400_000]);
+
+        assert_eq!(
+            checked_multifactorial(5, 0).unwrap_err().status(),
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            checked_primorial(53).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+    }
+
+    #[test]
+    fn test_factorial_family_checked_matches_big() {
+        for n in 0..80 {
+            for k in 1..4 {
+                assert_consistent(checked_multifactorial(n, k), big_multifactorial(n, k));
+            }
+            assert_consistent(checked_rising(n as u64, 12), big_rising(n as u64, 12));
+            assert_consistent(
+                checked_falling(n as u64 * 3, n),
+                big_falling(n as u64 * 3, n),
+            );
+            assert_consistent(checked_primorial(n), big_primorial(n));
+            assert_consistent(checked_superfactorial(n), big_superfactorial(n));
+            assert_consistent(checked_hyperfactorial(n), big_hyperfactorial(n));
+        }
+    }
+
+    #[test]
+    fn test_factorial_family_identities() {
+        // n!! * (n-1)!! = n!
+        let n = 501;
+        let product = &big_multifactorial(n, 2).unwrap() * &big_multifactorial(n - 1, 2).unwrap();
+        assert_eq!(product, big(n as u64));
+
+        // x^(n) = (x+n-1)! / (x-1)! and x_(n) = x! / (x-n)!
+        let (q, r) = big(700).divrem(&big(299)).unwrap();
+        assert!(r.is_zero());
+        assert_eq!(big_rising(300, 401).unwrap(), q);
+        assert_eq!(big_falling(700, 401).unwrap(), q);
+
+        // H(n) * sf(n-1) = (n!)^n
+        let n = 60;
+        let lhs = &big_hyperfactorial(n).unwrap() * &big_superfactorial(n - 1).unwrap();
+        assert_eq!(lhs, big(n as u64).pow(n).unwrap());
+
+        assert!(big_rising(u64::MAX, 2).is_err());
+        assert!(big_superfactorial(u32::MAX).is_err());
+        assert!(big_hyperfactorial(u32::MAX).is_err());
+        assert!(big_primorial(u32::MAX).is_err());
+        assert!(big_multifactorial(u32::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_factorial_family_exports() {
+        let mut out = 0u64;
+        assert_eq!(
+            unsafe { double_factorial_checked(9, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 945);
+        assert_eq!(
+            unsafe { multifactorial_checked(9, 0, &mut out) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { rising_factorial_checked(2, 3, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 24);
+        assert_eq!(
+            unsafe { falling_factorial_checked(5, 2, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 20);
+        assert_eq!(
+            unsafe { primorial_checked(100, &mut out) },
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            unsafe { superfactorial_checked(4, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 288);
+        assert_eq!(
+            unsafe { hyperfactorial_checked(3, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 108);
+
+        let handles = [
+            double_factorial_big(20),
+            multifactorial_big(20, 3),
+            rising_factorial_big(5, 3),
+            falling_factorial_big(5, 3),
+            primorial_big(20),
+            superfactorial_big(5),
+            hyperfactorial_big(4),
+        ];
+        let values: Vec<String> = handles
+            .iter()
+            .map(|&h| ffi::take_string(unsafe { crate::bigint::bigint_to_string(h) }))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                "3715891200",
+                "4188800",
+                "210",
+                "60",
+                "9699690",
+                "34560",
+                "27648"
+            ]
+        );
+        for handle in handles {
+            unsafe { crate::bigint::bigint_free(handle) };
+        }
+        assert!(multifactorial_big(3, 0).is_null());
+    }
+
+    #[test]
+    fn test_prime_valuation_and_trailing_zeros() {
+        assert_eq!(checked_prime_valuation(100, 5).unwrap(), 24);
+        assert_eq!(checked_prime_valuation(u64::MAX, 2).unwrap(), u64::MAX - 64);
+        assert_eq!(
+            checked_prime_valuation(100, 6).unwrap_err().status(),
+            MathStatus::InvalidArgument
+        );
+
+        assert_eq!(checked_trailing_zeros(100, 10).unwrap(), 24);
+        assert_eq!(checked_trailing_zeros(1000, 10).unwrap(), 249);
+        // 12 = 2^2 * 3: min(floor(97 / 2), 48) for 100!
+        assert_eq!(checked_trailing_zeros(100, 12).unwrap(), 48);
+        assert_eq!(checked_trailing_zeros(5, 2).unwrap(), 3);
+        assert_eq!(checked_trailing_zeros(10, 4_294_967_291).unwrap(), 0);
+        assert_eq!(
+            checked_trailing_zeros(u64::MAX, 10).unwrap(),
+            4_611_686_018_427_387_890
+        );
+        assert!(checked_trailing_zeros(10, 1).is_err());
+
+        for base in [2u32, 3, 10, 16, 36] {
+            let digits = big_factorial(300, 1)
+                .unwrap()
+                .to_string_radix(base)
+                .unwrap();
+            let zeros = digits.len() - digits.trim_end_matches('0').len();
+            assert_eq!(
+                checked_trailing_zeros(300, base).unwrap(),
+                zeros as u64,
+                "base {base}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_digit_count() {
+        assert_eq!(checked_digit_count(0).unwrap(), 1);
+        assert_eq!(checked_digit_count(10).unwrap(), 7);
+        assert_eq!(checked_digit_count(1000).unwrap(), 2568);
+        // The series alone settles these, down to n = 2.
+        let mut fact = BigInt::one();
+        for n in 1..300 {
+            fact.mul_small_assign(n);
+            let exact = fact.to_string().len() as u128;
+            assert_eq!(checked_digit_count(n).unwrap(), exact, "{n}!");
+        }
+        for n in [DIGITS_EXACT_MAX - 1, DIGITS_EXACT_MAX, STIRLING_MIN] {
+            let exact = big_factorial(n, 1).unwrap().to_string().len() as u128;
+            assert_eq!(checked_digit_count(n).unwrap(), exact, "{n}!");
+        }
+        // Reference values from mpmath with 300-bit log-gamma.
+        let cases = [
+            (100_000, 456_574),
+            (1_000_000_000, 8_565_705_523),
+            (1_048_579_145_728, 12_149_159_617_558),
+            (123_456_789_012_345_678, 2_056_446_956_221_538_710),
+            (1_000_000_000_000_000_000, 17_565_705_518_096_748_182),
+            (1_000_000_000_000_000_007, 17_565_705_518_096_748_308),
+            (10_000_000_000_000_000_000, 185_657_055_180_967_481_734),
+            (u64::MAX, 347_382_171_305_201_285_695),
+        ];
+        for (n, digits) in cases {
+            assert_eq!(checked_digit_count(n).unwrap(), digits, "{n}!");
+        }
+    }
+
+    #[test]
+    fn test_factorial_approx() {
+        let close = |n: u64, mantissa: f64, exponent: u64| {
+            let approx = factorial_approx(n);
+            assert_eq!(approx.exponent, exponent, "{n}!");
+            assert!(
+                (approx.mantissa - mantissa).abs() < 1e-14,
+                "{n}!: {approx:?}"
+            );
+        };
+        close(0, 1.0, 0);
+        close(1, 1.0, 0);
+        close(25, 1.551_121_004_333_098_6, 25);
+        close(170, 7.257_415_615_307_999, 306);
+        close(1000, 4.023_872_600_770_938, 2567);
+        close(STIRLING_MIN - 1, 2.846_259_680_917_054_5, 35_655);
+        close(STIRLING_MIN, 2.846_259_680_917_054_5, 35_659);
+        close(1_000_000, 8.263_931_688_331_24, 5_565_708);
+        close(
+            1_000_000_000_000_000_000,
+            5.597_073_567_310_395,
+            17_565_705_518_096_748_181,
+        );
+
+        let failed = factorial_approx(u64::MAX);
+        assert!(failed.mantissa.is_nan());
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+    }
+
+    #[test]
+    fn test_analytics_exports() {
+        let mut out = 0u64;
+        assert_eq!(
+            unsafe { factorial_prime_valuation(10, 2, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 8);
+        assert_eq!(
+            unsafe { factorial_trailing_zeros(25, 10, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 6);
+        let mut digits = Uint128::default();
+        assert_eq!(
+            unsafe { factorial_digit_count(20, &mut digits) },
+            MathStatus::Ok
+        );
+        assert_eq!(digits, Uint128::from(19u128));
+        assert_eq!(
+            unsafe { factorial_digit_count(1, ptr::null_mut()) },
+            MathStatus::InvalidArgument
+        );
+    }
+}
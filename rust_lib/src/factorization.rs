@@ -0,0 +1,619 @@
+//! Factorisation of 64-bit and big integers into prime powers.
+//!
+//! 64-bit inputs go through trial division by the primes below 2^10, then
+//! split recursively with Pollard's rho in Brent's variant until every part
+//! passes the deterministic Miller-Rabin test, so their factorisations are
+//! exact and fast (a few milliseconds at worst).
+//!
+//! Big integers are trial divided by the primes below 2^16; any part that
+//! still exceeds 64 bits and is not a probable prime is split with the
+//! elliptic curve method (stage 1 only, Suyama curves in Montgomery form).
+//! That finds prime factors of up to about 20 digits in reasonable time.
+//! When every curve of its schedule fails, `MathStatus::NoSolution` is
+//! returned. Prime factors above 2^64 are probable primes: they pass strong
+//! tests to the first twelve prime bases.
+
+use std::collections::BTreeMap;
+
+use crate::bigint::BigInt;
+use crate::error::{MathError, MathResult};
+use crate::number_theory::{gcd, mul_mod};
+use crate::primes::{self, SMALL_PRIMES};
+use crate::{MathStatus, ffi};
+
+/// Trial division bound for 64-bit inputs.
+const TRIAL_LIMIT: u64 = 1 << 10;
+/// Trial division bound for big integers.
+const BIG_TRIAL_LIMIT: u64 = 1 << 16;
+/// ECM stage-1 bounds and curve counts, tuned for factors of about 12, 15
+/// and 20 digits.
+const ECM_SCHEDULE: [(u64, u32); 3] = [(500, 20), (2_000, 30), (11_000, 90)];
+
+/// A prime and its exponent in a factorisation.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrimePower {
+    pub prime: u64,
+    pub exponent: u32,
+}
+
+/// A nontrivial factor of the odd composite `n` from Pollard's rho with
+/// Brent's cycle detection and batched gcds, iterating `x^2 + c`; `None` if
+/// this `c` fails.
+fn pollard_brent(n: u64, c: u64) -> Option<u64> {
+    const BATCH: u64 = 128;
+    let f = |x: u64| ((x as u128 * x as u128 + c as u128) % n as u128) as u64;
+    let (mut x, mut y, mut saved) = (0, 2, 2);
+    let (mut q, mut g, mut r) = (1, 1, 1u64);
+    while g == 1 {
+        x = y;
+        for _ in 0..r {
+            y = f(y);
+        }
+        let mut k = 0;
+        while k < r && g == 1 {
+            saved = y;
+            for _ in 0..BATCH.min(r - k) {
+                y = f(y);
+                q = mul_mod(q, x.abs_diff(y), n);
+            }
+            g = gcd(q, n);
+            k += BATCH;
+        }
+        r *= 2;
+    }
+    if g == n {
+        // The batch overshot: retrace it one step at a time.
+        loop {
+            saved = f(saved);
+            g = gcd(x.abs_diff(saved), n);
+            if g > 1 {
+                break;
+            }
+        }
+    }
+    (g != n).then_some(g)
+}
+
+/// Add the prime factors of `n`, which has no factor below [`TRIAL_LIMIT`],
+/// to `factors`.
+fn split_u64(n: u64, factors: &mut BTreeMap<u64, u32>) {
+    if n == 1 {
+        return;
+    }
+    if primes::is_prime(n) {
+        *factors.entry(n).or_default() += 1;
+        return;
+    }
+    let d = (1..)
+        .find_map(|c| pollard_brent(n, c))
+        .expect("some c splits a composite");
+    split_u64(d, factors);
+    split_u64(n / d, factors);
+}
+
+/// The prime factorisation of `n > 0`, in increasing order of the primes.
+pub(crate) fn factor_u64(mut n: u64) -> BTreeMap<u64, u32> {
+    let mut factors = BTreeMap::new();
+    for p in primes::primes_up_to(TRIAL_LIMIT) {
+        if p * p > n {
+            break;
+        }
+        while n.is_multiple_of(p) {
+            *factors.entry(p).or_default() += 1;
+            n /= p;
+        }
+    }
+    if n > 1 && n < TRIAL_LIMIT * TRIAL_LIMIT {
+        *factors.entry(n).or_default() += 1;
+    } else {
+        split_u64(n, &mut factors);
+    }
+    factors
+}
+
+/// Arithmetic modulo a big odd `n`, on residues in `0..n`.
+struct BigModulus {
+    n: BigInt,
+}
+
+impl BigModulus {
+    fn reduce(&self, x: &BigInt) -> BigInt {
+        let (_, r) = x.divrem(&self.n).expect("the modulus is not zero");
+        if r.is_negative() { &r + &self.n } else { r }
+    }
+
+    fn add(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        self.reduce(&(a + b))
+    }
+
+    fn sub(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        self.reduce(&(a - b))
+    }
+
+    fn mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        self.reduce(&(a * b))
+    }
+
+    fn pow(&self, base: &BigInt, exponent: &BigInt) -> BigInt {
+        let mut result = BigInt::one();
+        for bit in (0..exponent.bit_len()).rev() {
+            result = self.mul(&result, &result);
+            if exponent.shr(bit).limbs()[0] & 1 == 1 {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+}
+
+/// Strong probable-prime test of `n` to the bases in [`SMALL_PRIMES`];
+/// deterministic when `n` fits in 64 bits.
+fn is_probable_prime(n: &BigInt) -> bool {
+    if let Some(small) = n.to_u64() {
+        return primes::is_prime(small);
+    }
+    if n.limbs()[0].is_multiple_of(2) {
+        return false;
+    }
+    let modulus = BigModulus { n: n.clone() };
+    let minus_one = n - &BigInt::one();
+    let s = minus_one.limbs()[0].trailing_zeros();
+    let d = minus_one.shr(s as u64);
+    SMALL_PRIMES.iter().all(|&a| {
+        let mut x = modulus.pow(&BigInt::from(a), &d);
+        if x == BigInt::one() || x == minus_one {
+            return true;
+        }
+        (1..s).any(|_| {
+            x = modulus.mul(&x, &x);
+            x == minus_one
+        })
+    })
+}
+
+/// A point `(X : Z)` on a Montgomery curve, without its y coordinate.
+#[derive(Clone)]
+struct Point {
+    x: BigInt,
+    z: BigInt,
+}
+
+/// The x-only arithmetic of a Montgomery curve `B y^2 = x^3 + A x^2 + x`
+/// modulo `n`, with `(A + 2) / 4` kept as the fraction `a24_num / a24_den`.
+struct Curve<'a> {
+    m: &'a BigModulus,
+    a24_num: BigInt,
+    a24_den: BigInt,
+}
+
+impl Curve<'_> {
+    fn double(&self, p: &Point) -> Point {
+        let m = self.m;
+        let sum = m.add(&p.x, &p.z);
+        let diff = m.sub(&p.x, &p.z);
+        let (sum2, diff2) = (m.mul(&sum, &sum), m.mul(&diff, &diff));
+        let cross = m.sub(&sum2, &diff2);
+        Point {
+            x: m.mul(&m.mul(&sum2, &diff2), &self.a24_den),
+            z: m.mul(
+                &cross,
+                &m.add(&m.mul(&diff2, &self.a24_den), &m.mul(&cross, &self.a24_num)),
+            ),
+        }
+    }
+
+    /// `p + q`, given `p - q`.
+    fn add(&self, p: &Point, q: &Point, difference: &Point) -> Point {
+        let m = self.m;
+        let u = m.mul(&m.sub(&p.x, &p.z), &m.add(&q.x, &q.z));
+        let v = m.mul(&m.add(&p.x, &p.z), &m.sub(&q.x, &q.z));
+        let (plus, minus) = (m.add(&u, &v), m.sub(&u, &v));
+        Point {
+            x: m.mul(&difference.z, &m.mul(&plus, &plus)),
+            z: m.mul(&difference.x, &m.mul(&minus, &minus)),
+        }
+    }
+
+    /// `k * p` for `k >= 1`, with the Montgomery ladder.
+    fn multiply(&self, p: &Point, k: u64) -> Point {
+        let (mut low, mut high) = (p.clone(), self.double(p));
+        for bit in (0..k.ilog2()).rev() {
+            if k >> bit & 1 == 1 {
+                low = self.add(&high, &low, p);
+                high = self.double(&high);
+            } else {
+                high = self.add(&low, &high, p);
+                low = self.double(&low);
+            }
+        }
+        low
+    }
+}
+
+/// A factor `1 < d < n` if `g` is one.
+fn proper_factor(g: BigInt, n: &BigInt) -> Option<BigInt> {
+    (g > BigInt::one() && &g < n).then_some(g)
+}
+
+/// Run stage 1 of ECM on Suyama's curve for `sigma`, multiplying by every
+/// prime power up to `b1`.
+fn ecm_curve(m: &BigModulus, sigma: u64, primes: &[u64], b1: u64) -> Option<BigInt> {
+    let n = &m.n;
+    let small = |k: u64| BigInt::from(k);
+    let sigma = small(sigma);
+    let u = m.sub(&m.mul(&sigma, &sigma), &small(5));
+    let v = m.mul(&small(4), &sigma);
+    let u3 = m.mul(&m.mul(&u, &u), &u);
+    let v_minus_u = m.sub(&v, &u);
+    let curve = Curve {
+        m,
+        a24_num: m.mul(
+            &m.mul(&m.mul(&v_minus_u, &v_minus_u), &v_minus_u),
+            &m.add(&m.mul(&small(3), &u), &v),
+        ),
+        a24_den: m.mul(&small(16), &m.mul(&u3, &v)),
+    };
+    if let Some(d) = proper_factor(curve.a24_den.gcd(n), n) {
+        return Some(d);
+    }
+    let mut point = Point {
+        x: u3,
+        z: m.mul(&m.mul(&v, &v), &v),
+    };
+    for &p in primes {
+        let mut power = p;
+        while power <= b1 / p {
+            power *= p;
+        }
+        point = curve.multiply(&point, power);
+    }
+    proper_factor(point.z.gcd(n), n)
+}
+
+/// A proper factor of the odd composite `n`, or an error once every curve
+/// of [`ECM_SCHEDULE`] has failed.
+fn ecm(n: &BigInt) -> MathResult<BigInt> {
+    let m = BigModulus { n: n.clone() };
+    let mut sigma = 6;
+    for (b1, curves) in ECM_SCHEDULE {
+        let primes = primes::primes_up_to(b1);
+        for _ in 0..curves {
+            if let Some(d) = ecm_curve(&m, sigma, &primes, b1) {
+                return Ok(d);
+            }
+            sigma += 1;
+        }
+    }
+    Err(MathError::new(
+        MathStatus::NoSolution,
+        format!(
+            "no factor of the {}-bit composite {n} found by ECM",
+            n.bit_len()
+        ),
+    ))
+}
+
+/// Add the prime factors of `n`, which has no factor below
+/// [`BIG_TRIAL_LIMIT`], to `factors`, `multiplicity` times each.
+fn split_big(n: BigInt, multiplicity: u32, factors: &mut BTreeMap<BigInt, u32>) -> MathResult<()> {
+    if let Some(small) = n.to_u64() {
+        for (p, e) in factor_u64(small) {
+            *factors.entry(BigInt::from(p)).or_default() += e * multiplicity;
+        }
+        return Ok(());
+    }
+    if is_probable_prime(&n) {
+        *factors.entry(n).or_default() += multiplicity;
+        return Ok(());
+    }
+    // ECM cannot split a square of a prime by a gcd with it.
+    let root = n.sqrt_floor();
+    if &root * &root == n {
+        return split_big(root, 2 * multiplicity, factors);
+    }
+    let d = ecm(&n)?;
+    let (cofactor, _) = n.divrem(&d)?;
+    split_big(d, multiplicity, factors)?;
+    split_big(cofactor, multiplicity, factors)
+}
+
+/// The prime factorisation of `n > 0`, in increasing order of the primes.
+pub(crate) fn factor_big(n: &BigInt) -> MathResult<BTreeMap<BigInt, u32>> {
+    let mut factors = BTreeMap::new();
+    let mut n = n.clone();
+    for p in primes::primes_up_to(BIG_TRIAL_LIMIT) {
+        let mut exponent = 0;
+        loop {
+            let (quotient, rem) = n.divrem_small(p);
+            if rem != 0 {
+                break;
+            }
+            n = quotient;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.insert(BigInt::from(p), exponent);
+        }
+    }
+    if n != BigInt::one() {
+        split_big(n, 1, &mut factors)?;
+    }
+    Ok(factors)
+}
+
+/// Write the prime factorisation of `n` to `out_buf` as prime/exponent pairs
+/// in increasing order of the primes, and their number to `out_len` (if
+/// non-null). 1 has no factors; a `uint64_t` has at most 15.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero. With more pairs
+/// than `capacity`, the first `capacity` are written and
+/// `MathStatus::BufferTooSmall` is returned; `out_len` still receives the
+/// full count.
+///
+/// # Safety
+///
+/// `out_buf` must be null (with `capacity == 0`) or valid for writing
+/// `capacity` pairs, and `out_len` must be null or valid for writing a
+/// single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorize_u64(
+    n: u64,
+    out_buf: *mut PrimePower,
+    capacity: usize,
+    out_len: *mut usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        if n == 0 {
+            return Err(MathError::invalid_argument("0 has no prime factorisation"));
+        }
+        let factors = factor_u64(n);
+        let buf = unsafe { ffi::slice_mut(out_buf, capacity, "out_buf") }?;
+        for (slot, (&prime, &exponent)) in buf.iter_mut().zip(&factors) {
+            *slot = PrimePower { prime, exponent };
+        }
+        if !out_len.is_null() {
+            unsafe { out_len.write(factors.len()) };
+        }
+        if factors.len() > capacity {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{} prime factors, {capacity} available", factors.len()),
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Factor the positive big integer `n` into primes, writing a new handle for
+/// each distinct prime to `out_primes` and its exponent to `out_exponents`,
+/// in increasing order of the primes, and their number to `out_len` (if
+/// non-null).
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is not positive and
+/// `MathStatus::NoSolution` if no curve of the ECM schedule splits a
+/// composite part. With more primes
+/// than `capacity`, nothing is written, `out_len` receives the count and
+/// `MathStatus::BufferTooSmall` is returned. Each handle must be released
+/// with `bigint_free`.
+///
+/// # Safety
+///
+/// `n` must be null or a live handle, `out_primes` and `out_exponents` must
+/// be null (with `capacity == 0`) or valid for writing `capacity` values,
+/// and `out_len` must be null or valid for writing a single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bigint_factorize(
+    n: *const BigInt,
+    out_primes: *mut *mut BigInt,
+    out_exponents: *mut u32,
+    capacity: usize,
+    out_len: *mut usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let n = unsafe { ffi::handle_ref(n, "n") }?;
+        if n.signum() <= 0 {
+            return Err(MathError::invalid_argument(format!(
+                "{n} has no prime factorisation"
+            )));
+        }
+        let primes = unsafe { ffi::slice_mut(out_primes, capacity, "out_primes") }?;
+        let exponents = unsafe { ffi::slice_mut(out_exponents, capacity, "out_exponents") }?;
+        let factors = factor_big(n)?;
+        if !out_len.is_null() {
+            unsafe { out_len.write(factors.len()) };
+        }
+        if factors.len() > capacity {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{} prime factors, {capacity} available", factors.len()),
+            ));
+        }
+        for (i, (prime, exponent)) in factors.into_iter().enumerate() {
+            primes[i] = ffi::into_handle(prime);
+            exponents[i] = exponent;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factorize(n: u64) -> Vec<(u64, u32)> {
+        factor_u64(n).into_iter().collect()
+    }
+
+    fn product(factors: &[(u64, u32)]) -> u64 {
+        factors.iter().map(|&(p, e)| p.pow(e)).product()
+    }
+
+    #[test]
+    fn test_factor_u64() {
+        assert!(factorize(1).is_empty());
+        assert_eq!(factorize(2), [(2, 1)]);
+        assert_eq!(factorize(1 << 63), [(2, 63)]);
+        assert_eq!(factorize(360), [(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(
+            factorize(u64::MAX),
+            [
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65_537, 1),
+                (6_700_417, 1)
+            ]
+        );
+        assert_eq!(
+            factorize(18_446_744_073_709_551_557),
+            [(18_446_744_073_709_551_557, 1)]
+        );
+        // Two 32-bit primes, and the square of one.
+        assert_eq!(
+            factorize(4_294_967_291 * 4_294_967_279),
+            [(4_294_967_279, 1), (4_294_967_291, 1)]
+        );
+        assert_eq!(
+            factorize(4_294_967_291 * 4_294_967_291),
+            [(4_294_967_291, 2)]
+        );
+        // Strong pseudoprime to bases 2..=23.
+        assert_eq!(
+            product(&factorize(3_825_123_056_546_413_051)),
+            3_825_123_056_546_413_051
+        );
+        let mut x = 0x9e37_79b9_7f4a_7c15u64;
+        for _ in 0..200 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let factors = factorize(x);
+            assert_eq!(product(&factors), x);
+            assert!(factors.iter().all(|&(p, _)| primes::is_prime(p)));
+        }
+    }
+
+    #[test]
+    fn test_factorize_u64_export() {
+        let mut buf = [PrimePower::default(); 15];
+        let mut len = 0;
+        let status = unsafe { factorize_u64(720, buf.as_mut_ptr(), buf.len(), &mut len) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(
+            buf[..len],
+            [
+                PrimePower {
+                    prime: 2,
+                    exponent: 4
+                },
+                PrimePower {
+                    prime: 3,
+                    exponent: 2
+                },
+                PrimePower {
+                    prime: 5,
+                    exponent: 1
+                }
+            ]
+        );
+        let status = unsafe { factorize_u64(1, std::ptr::null_mut(), 0, &mut len) };
+        assert_eq!((status, len), (MathStatus::Ok, 0));
+
+        let status = unsafe { factorize_u64(u64::MAX, buf.as_mut_ptr(), 2, &mut len) };
+        assert_eq!((status, len), (MathStatus::BufferTooSmall, 7));
+        assert_eq!(
+            buf[1],
+            PrimePower {
+                prime: 5,
+                exponent: 1
+            }
+        );
+
+        let status = unsafe { factorize_u64(0, buf.as_mut_ptr(), buf.len(), &mut len) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_factor_big() {
+        let big = |s: &str| BigInt::from_str_radix(s, 10).unwrap();
+        let fermat = &BigInt::one().shl(64).unwrap() + &BigInt::one();
+        let factors: Vec<_> = factor_big(&fermat).unwrap().into_iter().collect();
+        assert_eq!(factors, [(big("274177"), 1), (big("67280421310721"), 1)]);
+
+        // A 36-bit prime squared times a probable prime above 2^100.
+        let mut large = &BigInt::one().shl(100).unwrap() + &BigInt::one();
+        while !is_probable_prime(&large) {
+            large = &large + &BigInt::from(2u64);
+        }
+        let small = BigInt::from(68_719_476_767u64);
+        let n = &(&small * &small) * &large;
+        let factors: Vec<_> = factor_big(&n).unwrap().into_iter().collect();
+        assert_eq!(factors, [(small, 2), (large, 1)]);
+
+        // 100! has Legendre's exponents.
+        let mut factorial = BigInt::one();
+        for k in 2..=100 {
+            factorial.mul_small_assign(k);
+        }
+        for (p, e) in factor_big(&factorial).unwrap() {
+            let p = p.to_u64().unwrap();
+            let legendre: u32 = (1..)
+                .map(|i| 100 / p.pow(i))
+                .take_while(|&q| q > 0)
+                .sum::<u64>() as u32;
+            assert_eq!(e, legendre, "{p}");
+        }
+    }
+
+    #[test]
+    fn test_bigint_factorize_export() {
+        let n = ffi::into_handle(BigInt::from(1_000_000_007u64 * 12));
+        let mut primes = [std::ptr::null_mut(); 3];
+        let mut exponents = [0u32; 3];
+        let mut len = 0;
+        let status = unsafe {
+            bigint_factorize(n, primes.as_mut_ptr(), exponents.as_mut_ptr(), 3, &mut len)
+        };
+        assert_eq!((status, len), (MathStatus::Ok, 3));
+        assert_eq!(exponents, [2, 1, 1]);
+        let primes: Vec<_> = primes
+            .into_iter()
+            .map(|p| unsafe { Box::from_raw(p) }.to_u64().unwrap())
+            .collect();
+        assert_eq!(primes, [2, 3, 1_000_000_007]);
+
+        let mut out = std::ptr::null_mut();
+        let status = unsafe { bigint_factorize(n, &mut out, exponents.as_mut_ptr(), 1, &mut len) };
+        assert_eq!((status, len), (MathStatus::BufferTooSmall, 3));
+        assert!(out.is_null());
+
+        let zero = ffi::into_handle(BigInt::zero());
+        let status = unsafe {
+            bigint_factorize(
+                zero,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+                &mut len,
+            )
+        };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        let status = unsafe {
+            bigint_factorize(
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+                &mut len,
+            )
+        };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        unsafe {
+            crate::bigint::bigint_free(n);
+            crate::bigint::bigint_free(zero);
+        }
+    }
+}
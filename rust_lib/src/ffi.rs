@@ -0,0 +1,171 @@
+//! Helpers shared by the exported `extern "C"` functions.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::error::{self, MathError, MathResult, MathStatus};
+
+/// Run the body of a value-returning export.
+///
+/// Success clears the last-error slot. An error or a panic is recorded in the
+/// slot and `fallback` is returned instead, so no unwind ever reaches C.
+pub(crate) fn guard<T>(fallback: T, f: impl FnOnce() -> MathResult<T>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            error::clear_last_error();
+            value
+        }
+        Ok(Err(err)) => {
+            error::set_last_error(err);
+            fallback
+        }
+        Err(payload) => {
+            error::set_last_error(panic_error(payload));
+            fallback
+        }
+    }
+}
+
+/// Run the body of a status-returning export; see [`guard`].
+pub(crate) fn guard_status(f: impl FnOnce() -> MathResult<()>) -> MathStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => error::report(result),
+        Err(payload) => error::set_last_error(panic_error(payload)),
+    }
+}
+
+/// Move `value` to the heap and hand ownership to C as an opaque handle.
+pub(crate) fn into_handle<T>(value: T) -> *mut T {
+    Box::into_raw(Box::new(value))
+}
+
+/// Borrow the value behind an opaque handle, rejecting null pointers.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle created by [`into_handle`].
+pub(crate) unsafe fn handle_ref<'a, T>(handle: *const T, name: &str) -> MathResult<&'a T> {
+    unsafe { handle.as_ref() }
+        .ok_or_else(|| MathError::invalid_argument(format!("`{name}` is null")))
+}
+
+/// Release a handle created by [`into_handle`]; null is ignored.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle created by [`into_handle`], and must
+/// not be used afterwards.
+pub(crate) unsafe fn free_handle<T>(handle: *mut T) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Borrow a caller-owned output buffer. A null pointer is accepted only
+/// together with a zero length.
+///
+/// # Safety
+///
+/// `ptr` must be null or valid for writing `len` elements of `T`.
+pub(crate) unsafe fn slice_mut<'a, T>(
+    ptr: *mut T,
+    len: usize,
+    name: &str,
+) -> MathResult<&'a mut [T]> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    if ptr.is_null() {
+        return Err(MathError::invalid_argument(format!("`{name}` is null")));
+    }
+    Ok(unsafe { std::slice::from_raw_parts_mut(ptr, len) })
+}
+
+/// Borrow a caller-owned input array; see [`slice_mut`].
+///
+/// # Safety
+///
+/// `ptr` must be null or valid for reading `len` elements of `T`.
+pub(crate) unsafe fn slice_ref<'a, T>(
+    ptr: *const T,
+    len: usize,
+    name: &str,
+) -> MathResult<&'a [T]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(MathError::invalid_argument(format!("`{name}` is null")));
+    }
+    Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+/// Resolve a caller's thread count, where 0 picks the number of available
+/// cores.
+pub(crate) fn thread_count(threads: u32) -> usize {
+    match threads {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        t => t as usize,
+    }
+}
+
+/// Catch a panic without touching the last-error slot. Only meant for the
+/// error accessors themselves, which must not overwrite the slot they read.
+pub(crate) fn catch<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+fn panic_error(payload: Box<dyn Any + Send>) -> MathError {
+    let message = if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_owned()
+    };
+    MathError::new(MathStatus::Panic, message)
+}
+
+/// Write `value` through an out-parameter, rejecting null pointers.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `T`.
+pub(crate) unsafe fn write_out<T>(out: *mut T, name: &str, value: T) -> MathResult<()> {
+    if out.is_null() {
+        return Err(MathError::invalid_argument(format!("`{name}` is null")));
+    }
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+/// Copy and free a string returned by the library.
+#[cfg(test)]
+pub(crate) fn take_string(ptr: *mut std::ffi::c_char) -> String {
+    assert!(!ptr.is_null(), "expected a string, got null");
+    let s = unsafe { std::ffi::CStr::from_ptr(ptr) }
+        .to_str()
+        .unwrap()
+        .to_owned();
+    unsafe { error::math_free_string(ptr) };
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_reports_errors_and_panics() {
+        let value = guard(-1, || Err(MathError::overflow("too big")));
+        assert_eq!(value, -1);
+        assert_eq!(error::math_last_error_code(), MathStatus::Overflow);
+
+        let value = guard(-1, || -> MathResult<i32> { panic::panic_any(42u8) });
+        assert_eq!(value, -1);
+        assert_eq!(error::math_last_error_code(), MathStatus::Panic);
+
+        assert_eq!(guard_status(|| Ok(())), MathStatus::Ok);
+        assert_eq!(error::math_last_error_code(), MathStatus::Ok);
+    }
+}
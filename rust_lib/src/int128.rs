@@ -0,0 +1,136 @@
+//! 128-bit integers passed by value across the C ABI.
+//!
+//! C and C++ have no portable `__int128`, so [`Uint128`] and [`Int128`] carry
+//! the value as two 64-bit halves: `value = hi * 2^64 + lo`, where `hi` is
+//! signed for [`Int128`] (two's complement, as in Rust's `i128`). They are
+//! formatted with `uint128_to_string` and `int128_to_string`; the arithmetic
+//! lives with the other widths in `arith` (`u128_add`, `i128_mul`, ...).
+
+use std::ffi::c_char;
+
+use crate::error;
+use crate::ffi;
+
+/// Unsigned 128-bit integer `hi * 2^64 + lo`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Uint128 {
+    pub hi: u64,
+    pub lo: u64,
+}
+
+/// Signed 128-bit integer `hi * 2^64 + lo` in two's complement.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Int128 {
+    pub hi: i64,
+    pub lo: u64,
+}
+
+impl From<u128> for Uint128 {
+    fn from(value: u128) -> Self {
+        Uint128 {
+            hi: (value >> 64) as u64,
+            lo: value as u64,
+        }
+    }
+}
+
+impl From<Uint128> for u128 {
+    fn from(value: Uint128) -> Self {
+        (value.hi as u128) << 64 | value.lo as u128
+    }
+}
+
+impl From<i128> for Int128 {
+    fn from(value: i128) -> Self {
+        Int128 {
+            hi: (value >> 64) as i64,
+            lo: value as u64,
+        }
+    }
+}
+
+impl From<Int128> for i128 {
+    fn from(value: Int128) -> Self {
+        (value.hi as i128) << 64 | value.lo as i128
+    }
+}
+
+/// Widen a `uint64_t` to a `Uint128`.
+#[unsafe(no_mangle)]
+pub extern "C" fn uint128_from_u64(value: u64) -> Uint128 {
+    ffi::guard(Uint128::default(), || Ok(Uint128::from(value as u128)))
+}
+
+/// Widen an `int64_t` to an `Int128`, extending the sign into `hi`.
+#[unsafe(no_mangle)]
+pub extern "C" fn int128_from_i64(value: i64) -> Int128 {
+    ffi::guard(Int128::default(), || Ok(Int128::from(value as i128)))
+}
+
+/// Format `value` in decimal, or return null on failure.
+///
+/// The string must be released with `math_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn uint128_to_string(value: Uint128) -> *mut c_char {
+    ffi::guard(std::ptr::null_mut(), || {
+        Ok(error::into_c_string(u128::from(value).to_string()))
+    })
+}
+
+/// Format `value` in decimal with a leading `-` when negative, or return null
+/// on failure.
+///
+/// The string must be released with `math_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn int128_to_string(value: Int128) -> *mut c_char {
+    ffi::guard(std::ptr::null_mut(), || {
+        Ok(error::into_c_string(i128::from(value).to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halves_round_trip() {
+        for value in [0u128, 1, u64::MAX as u128, 1 << 64, u128::MAX] {
+            assert_eq!(u128::from(Uint128::from(value)), value);
+        }
+        for value in [0i128, -1, i64::MIN as i128, 1 << 64, i128::MIN, i128::MAX] {
+            assert_eq!(i128::from(Int128::from(value)), value);
+        }
+        assert_eq!(
+            Int128::from(-1i128),
+            Int128 {
+                hi: -1,
+                lo: u64::MAX
+            }
+        );
+        assert_eq!(
+            int128_from_i64(-2),
+            Int128 {
+                hi: -1,
+                lo: u64::MAX - 1
+            }
+        );
+        assert_eq!(uint128_from_u64(7), Uint128 { hi: 0, lo: 7 });
+    }
+
+    #[test]
+    fn test_to_string() {
+        let max = Uint128::from(u128::MAX);
+        assert_eq!(
+            ffi::take_string(uint128_to_string(max)),
+            "340282366920938463463374607431768211455"
+        );
+        let min = Int128::from(i128::MIN);
+        assert_eq!(
+            ffi::take_string(int128_to_string(min)),
+            "-170141183460469231731687303715884105728"
+        );
+        assert_eq!(ffi::take_string(int128_to_string(Int128::default())), "0");
+    }
+}
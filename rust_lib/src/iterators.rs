@@ -0,0 +1,517 @@
+//! Enumerators of combinatorial objects behind an opaque [`CombIter`] handle.
+//!
+//! Each constructor returns a handle positioned before its first item;
+//! `comb_iter_next` copies the current item into a caller buffer and moves
+//! on, and `comb_iter_free` releases the handle. Items are arrays of `u32`:
+//!
+//! - permutations of `0..n`, in lexicographic or Heap's-algorithm order;
+//! - k-combinations of `0..n` in lexicographic order (increasing elements);
+//! - subsets of `0..n` (increasing elements) in binary-counter or Gray-code
+//!   order, where consecutive subsets differ by exactly one element;
+//! - integer partitions of `n` (non-increasing parts), from `[n]` down to
+//!   `[1, ..., 1]`;
+//! - compositions of `n` (ordered positive parts), starting with `[n]`.
+
+use crate::MathStatus;
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+
+/// Enumeration state; `item` always holds the next item to hand out.
+enum Kind {
+    Lexicographic,
+    /// Heap's algorithm with its explicit stack `c` and position `i`.
+    Heap {
+        c: Vec<usize>,
+        i: usize,
+    },
+    Combination {
+        n: u32,
+    },
+    /// Binary counter over the `n` element bits (`gray` maps it through
+    /// `k ^ (k >> 1)`).
+    Subset {
+        n: u32,
+        counter: u64,
+        gray: bool,
+    },
+    Partition,
+    /// Binary counter over the `n - 1` places where a composition may be cut.
+    Composition {
+        n: u32,
+        counter: u64,
+    },
+}
+
+/// Opaque iterator over combinatorial objects.
+pub struct CombIter {
+    item: Vec<u32>,
+    done: bool,
+    kind: Kind,
+}
+
+impl CombIter {
+    fn new(item: Vec<u32>, kind: Kind) -> CombIter {
+        CombIter {
+            item,
+            done: false,
+            kind,
+        }
+    }
+
+    fn empty(kind: Kind) -> CombIter {
+        CombIter {
+            item: Vec::new(),
+            done: true,
+            kind,
+        }
+    }
+
+    /// Replace `item` with its successor, or mark the iterator exhausted.
+    fn advance(&mut self) {
+        let more = match &mut self.kind {
+            Kind::Lexicographic => next_permutation(&mut self.item),
+            Kind::Heap { c, i } => next_heap(&mut self.item, c, i),
+            Kind::Combination { n } => next_combination(&mut self.item, *n),
+            Kind::Subset { n, counter, gray } => {
+                *counter += 1;
+                if *counter >> *n != 0 {
+                    false
+                } else {
+                    let mask = if *gray {
+                        *counter ^ (*counter >> 1)
+                    } else {
+                        *counter
+                    };
+                    self.item = mask_elements(mask);
+                    true
+                }
+            }
+            Kind::Partition => next_partition(&mut self.item),
+            Kind::Composition { n, counter } => {
+                *counter += 1;
+                // Zero has only the empty composition.
+                if *n == 0 || *counter >> (*n - 1) != 0 {
+                    false
+                } else {
+                    self.item = composition_from_cuts(*n, *counter);
+                    true
+                }
+            }
+        };
+        self.done = !more;
+    }
+}
+
+impl Iterator for CombIter {
+    type Item = Vec<u32>;
+
+    fn next(&mut self) -> Option<Vec<u32>> {
+        if self.done {
+            return None;
+        }
+        let item = self.item.clone();
+        self.advance();
+        Some(item)
+    }
+}
+
+/// Rearrange into the lexicographically next permutation, returning false
+/// after the last one.
+fn next_permutation(perm: &mut [u32]) -> bool {
+    let Some(i) = perm.windows(2).rposition(|w| w[0] < w[1]) else {
+        return false;
+    };
+    let j = perm
+        .iter()
+        .rposition(|&x| x > perm[i])
+        .expect("a larger element follows the pivot");
+    perm.swap(i, j);
+    perm[i + 1..].reverse();
+    true
+}
+
+/// One step of the iterative form of Heap's algorithm: every permutation
+/// differs from the previous one by a single swap.
+fn next_heap(perm: &mut [u32], c: &mut [usize], i: &mut usize) -> bool {
+    while *i < perm.len() {
+        if c[*i] < *i {
+            if (*i).is_multiple_of(2) {
+                perm.swap(0, *i);
+            } else {
+                perm.swap(c[*i], *i);
+            }
+            c[*i] += 1;
+            *i = 1;
+            return true;
+        }
+        c[*i] = 0;
+        *i += 1;
+    }
+    false
+}
+
+fn next_combination(comb: &mut [u32], n: u32) -> bool {
+    let k = comb.len() as u32;
+    // Element i can grow while it stays below its maximum n - k + i.
+    let Some(i) = (0..comb.len()).rev().find(|&i| comb[i] < n - k + i as u32) else {
+        return false;
+    };
+    comb[i] += 1;
+    for j in i + 1..comb.len() {
+        comb[j] = comb[j - 1] + 1;
+    }
+    true
+}
+
+fn mask_elements(mask: u64) -> Vec<u32> {
+    (0..u64::BITS).filter(|&bit| mask >> bit & 1 == 1).collect()
+}
+
+/// Lower the last part above 1 by one and refill the rest greedily with
+/// parts no larger than it.
+fn next_partition(parts: &mut Vec<u32>) -> bool {
+    let ones = parts.iter().rev().take_while(|&&p| p == 1).count();
+    parts.truncate(parts.len() - ones);
+    let Some(last) = parts.pop() else {
+        return false;
+    };
+    let largest = last - 1;
+    let mut rest = ones as u32 + 1;
+    parts.push(largest);
+    while rest > 0 {
+        let part = rest.min(largest);
+        parts.push(part);
+        rest -= part;
+    }
+    true
+}
+
+fn composition_from_cuts(n: u32, cuts: u64) -> Vec<u32> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for position in 1..n {
+        if cuts >> (position - 1) & 1 == 1 {
+            parts.push(position - start);
+            start = position;
+        }
+    }
+    parts.push(n - start);
+    parts
+}
+
+fn permutations(n: u32, heap: bool) -> CombIter {
+    let kind = if heap {
+        Kind::Heap {
+            c: vec![0; n as usize],
+            i: 1,
+        }
+    } else {
+        Kind::Lexicographic
+    };
+    CombIter::new((0..n).collect(), kind)
+}
+
+fn combinations(n: u32, k: u32) -> CombIter {
+    let kind = Kind::Combination { n };
+    if k > n {
+        return CombIter::empty(kind);
+    }
+    CombIter::new((0..k).collect(), kind)
+}
+
+fn subsets(n: u32, gray: bool) -> MathResult<CombIter> {
+    if n >= u64::BITS {
+        return Err(MathError::invalid_argument(format!(
+            "subsets of {n} elements cannot be counted in u64"
+        )));
+    }
+    let kind = Kind::Subset {
+        n,
+        counter: 0,
+        gray,
+    };
+    Ok(CombIter::new(Vec::new(), kind))
+}
+
+fn compositions(n: u32) -> MathResult<CombIter> {
+    if n > u64::BITS {
+        return Err(MathError::invalid_argument(format!(
+            "compositions of {n} cannot be counted in u64"
+        )));
+    }
+    let first = if n == 0 { Vec::new() } else { vec![n] };
+    Ok(CombIter::new(first, Kind::Composition { n, counter: 0 }))
+}
+
+fn partitions(n: u32) -> CombIter {
+    CombIter::new(if n == 0 { Vec::new() } else { vec![n] }, Kind::Partition)
+}
+
+/// Run an iterator constructor, returning null on failure.
+fn iter_result(f: impl FnOnce() -> MathResult<CombIter>) -> *mut CombIter {
+    ffi::guard(std::ptr::null_mut(), || f().map(ffi::into_handle))
+}
+
+/// Iterate over the `n!` permutations of `0..n` in lexicographic order.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn permutation_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| Ok(permutations(n, false)))
+}
+
+/// Iterate over the permutations of `0..n` in the order of Heap's algorithm,
+/// where each permutation is one swap away from the previous one.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn permutation_heap_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| Ok(permutations(n, true)))
+}
+
+/// Iterate over the k-element subsets of `0..n` in lexicographic order, each
+/// as `k` increasing elements. There are none when `k > n`.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn combination_iter_new(n: u32, k: u32) -> *mut CombIter {
+    iter_result(|| Ok(combinations(n, k)))
+}
+
+/// Iterate over all `2^n` subsets of `0..n` (`n < 64`) in binary-counter
+/// order, each as its increasing elements.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn subset_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| subsets(n, false))
+}
+
+/// Iterate over all `2^n` subsets of `0..n` (`n < 64`) in reflected
+/// Gray-code order, so consecutive subsets differ by one element.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn subset_gray_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| subsets(n, true))
+}
+
+/// Iterate over the integer partitions of `n` as non-increasing parts, in
+/// reverse lexicographic order from `[n]` to `[1, ..., 1]`. Zero has the
+/// single empty partition.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn partition_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| Ok(partitions(n)))
+}
+
+/// Iterate over the `2^(n-1)` compositions of `n` (`n <= 64`), ordered
+/// sequences of positive parts summing to `n`, starting with `[n]`.
+///
+/// The handle must be released with `comb_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn composition_iter_new(n: u32) -> *mut CombIter {
+    iter_result(|| compositions(n))
+}
+
+/// Copy the next item of `iter` to `out_buf` and advance.
+///
+/// Returns true if an item was written. The item length is stored in
+/// `out_len` (if non-null) whenever an item is available; if it exceeds
+/// `capacity`, nothing is copied, the iterator does not move and false is
+/// returned with `MathStatus::BufferTooSmall` in the last-error slot. Once
+/// the iterator is exhausted, false is returned and the slot reads
+/// `MathStatus::Ok`.
+///
+/// # Safety
+///
+/// `iter` must be null or a live handle, `out_buf` must be null or valid for
+/// writing `capacity` values, and `out_len` must be null or valid for
+/// writing a single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn comb_iter_next(
+    iter: *mut CombIter,
+    out_buf: *mut u32,
+    capacity: usize,
+    out_len: *mut usize,
+) -> bool {
+    ffi::guard(false, || {
+        let iter = unsafe { iter.as_mut() }
+            .ok_or_else(|| MathError::invalid_argument("`iter` is null"))?;
+        if iter.done {
+            return Ok(false);
+        }
+        let len = iter.item.len();
+        if !out_len.is_null() {
+            unsafe { out_len.write(len) };
+        }
+        if capacity < len {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{len} values required, {capacity} available"),
+            ));
+        }
+        unsafe { ffi::slice_mut(out_buf, len, "out_buf") }?.copy_from_slice(&iter.item);
+        iter.advance();
+        Ok(true)
+    })
+}
+
+/// Release an iterator handle. Passing null is a no-op. The last error is
+/// left untouched.
+///
+/// # Safety
+///
+/// `iter` must be null or a live handle returned by this library, and must
+/// not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn comb_iter_free(iter: *mut CombIter) {
+    ffi::catch((), || unsafe { ffi::free_handle(iter) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    #[test]
+    fn test_lexicographic_permutations() {
+        let all: Vec<_> = permutations(3, false).collect();
+        assert_eq!(
+            all,
+            [
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 0, 2],
+                [1, 2, 0],
+                [2, 0, 1],
+                [2, 1, 0]
+            ]
+        );
+        assert_eq!(
+            permutations(0, false).collect::<Vec<_>>(),
+            [Vec::<u32>::new()]
+        );
+        assert_eq!(permutations(7, false).count(), 5040);
+    }
+
+    #[test]
+    fn test_heap_permutations() {
+        for n in 0..=6u32 {
+            let all: Vec<_> = permutations(n, true).collect();
+            let mut sorted = all.clone();
+            sorted.sort();
+            assert_eq!(
+                sorted,
+                permutations(n, false).collect::<Vec<_>>(),
+                "n = {n}"
+            );
+            for pair in all.windows(2) {
+                let moved = pair[0].iter().zip(&pair[1]).filter(|(a, b)| a != b).count();
+                assert_eq!(moved, 2, "{pair:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_combinations() {
+        let all: Vec<_> = combinations(4, 2).collect();
+        assert_eq!(all, [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]]);
+        assert_eq!(combinations(5, 0).collect::<Vec<_>>(), [Vec::<u32>::new()]);
+        assert_eq!(combinations(3, 4).count(), 0);
+        assert_eq!(combinations(20, 10).count(), 184_756);
+    }
+
+    #[test]
+    fn test_subsets() {
+        let binary: Vec<_> = subsets(3, false).unwrap().collect();
+        assert_eq!(binary[..4], [vec![], vec![0], vec![1], vec![0, 1]]);
+        assert_eq!(binary.len(), 8);
+
+        let gray: Vec<_> = subsets(10, true).unwrap().collect();
+        assert_eq!(gray.len(), 1024);
+        let mut seen: Vec<_> = gray.clone();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 1024);
+        for pair in gray.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let changed = a.iter().filter(|x| !b.contains(x)).count()
+                + b.iter().filter(|x| !a.contains(x)).count();
+            assert_eq!(changed, 1, "{a:?} -> {b:?}");
+        }
+        assert!(subsets(64, true).is_err());
+    }
+
+    #[test]
+    fn test_partitions() {
+        let all: Vec<_> = partitions(5).collect();
+        assert_eq!(
+            all,
+            [
+                vec![5],
+                vec![4, 1],
+                vec![3, 2],
+                vec![3, 1, 1],
+                vec![2, 2, 1],
+                vec![2, 1, 1, 1],
+                vec![1, 1, 1, 1, 1]
+            ]
+        );
+        assert_eq!(partitions(0).count(), 1);
+        assert_eq!(partitions(30).count(), 5604);
+        assert!(partitions(12).all(|p| p.iter().sum::<u32>() == 12));
+    }
+
+    #[test]
+    fn test_compositions() {
+        let all: Vec<_> = compositions(4).unwrap().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], [4]);
+        assert_eq!(all[7], [1, 1, 1, 1]);
+        let mut sorted = all.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 8);
+        assert!(all.iter().all(|c| c.iter().sum::<u32>() == 4));
+        assert_eq!(
+            compositions(0).unwrap().collect::<Vec<_>>(),
+            [Vec::<u32>::new()]
+        );
+        assert!(compositions(65).is_err());
+    }
+
+    #[test]
+    fn test_exports() {
+        let iter = combination_iter_new(3, 2);
+        let mut buf = [0u32; 2];
+        let mut len = 0usize;
+        let mut items = Vec::new();
+        while unsafe { comb_iter_next(iter, buf.as_mut_ptr(), buf.len(), &mut len) } {
+            items.push(buf[..len].to_vec());
+        }
+        assert_eq!(error::math_last_error_code(), MathStatus::Ok);
+        assert_eq!(items, [[0, 1], [0, 2], [1, 2]]);
+        unsafe { comb_iter_free(iter) };
+
+        // A short buffer reports the size and leaves the iterator in place.
+        let iter = partition_iter_new(3);
+        assert!(unsafe { comb_iter_next(iter, buf.as_mut_ptr(), 2, &mut len) });
+        assert!(unsafe { comb_iter_next(iter, buf.as_mut_ptr(), 2, &mut len) });
+        assert!(!unsafe { comb_iter_next(iter, buf.as_mut_ptr(), 2, &mut len) });
+        assert_eq!(error::math_last_error_code(), MathStatus::BufferTooSmall);
+        assert_eq!(len, 3);
+        let mut wide = [0u32; 3];
+        assert!(unsafe { comb_iter_next(iter, wide.as_mut_ptr(), 3, &mut len) });
+        assert_eq!(wide, [1, 1, 1]);
+        unsafe { comb_iter_free(iter) };
+
+        let spare = partition_iter_new(2);
+        assert!(subset_iter_new(64).is_null());
+        assert!(!unsafe { comb_iter_next(std::ptr::null_mut(), buf.as_mut_ptr(), 2, &mut len) });
+        assert_eq!(error::math_last_error_code(), MathStatus::InvalidArgument);
+        unsafe { comb_iter_free(spare) };
+        assert_eq!(error::math_last_error_code(), MathStatus::InvalidArgument);
+    }
+}
@@ -1,15 +1,41 @@
-#[unsafe(no_mangle)]
-pub extern "C" fn add_numbers(a: i32, b: i32) -> i32 {
-    a + b
-}
+mod arith;
+mod arrays;
+pub mod bigint;
+mod combinatorics;
+pub mod error;
+mod factorial;
+mod factorization;
+mod ffi;
+mod gamma;
+mod int128;
+mod iterators;
+mod limbs;
+mod modular;
+mod multiplicative;
+mod number_theory;
+mod partitions;
+mod permutation;
+mod primes;
+mod real;
+mod reductions;
+mod summation;
 
+pub use arith::OverflowPolicy;
+pub use bigint::BigInt;
+pub use error::MathStatus;
+pub use factorization::PrimePower;
+pub use int128::{Int128, Uint128};
+pub use number_theory::{Bezout, Congruence, DiophantineSolution};
+pub use primes::PrimeIter;
+pub use reductions::ScanKind;
+pub use summation::SumAlgorithm;
+
+/// Add two integers with plain `+`. Overflow is reported as
+/// `MathStatus::Panic` in debug builds and wraps in release builds; use
+/// `i32_add` to choose the overflow behaviour explicitly.
 #[unsafe(no_mangle)]
-pub extern "C" fn factorial(n: u32) -> u64 {
-    if n <= 1 {
-        1
-    } else {
-        (1..=n as u64).product()
-    }
+pub extern "C" fn add_numbers(a: i32, b: i32) -> i32 {
+    ffi::guard(0, || Ok(a + b))
 }
 
 #[cfg(test)]
@@ -22,11 +48,21 @@ mod tests {
         assert_eq!(add_numbers(-1, 1), 0);
     }
 
+    // Plain `+` and `product()` only panic on overflow with debug assertions;
+    // release builds wrap instead.
+    #[cfg(debug_assertions)]
     #[test]
-    fn test_factorial() {
-        assert_eq!(factorial(0), 1);
-        assert_eq!(factorial(1), 1);
-        assert_eq!(factorial(5), 120);
-        assert_eq!(factorial(10), 3628800);
+    fn test_panics_are_caught_at_the_boundary() {
+        assert_eq!(add_numbers(i32::MAX, 1), 0);
+        assert_eq!(error::math_last_error_code(), MathStatus::Panic);
+
+        assert_eq!(add_numbers(2, 3), 5);
+        assert_eq!(error::math_last_error_code(), MathStatus::Ok);
+
+        assert_eq!(factorial::factorial(25), 0);
+        assert_eq!(error::math_last_error_code(), MathStatus::Panic);
+        let text = ffi::take_string(error::math_last_error_message());
+        assert!(text.starts_with("internal panic: "), "{text}");
+        assert!(text.contains("overflow"), "{text}");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,369 @@
+//! Multi-precision building blocks on 64-bit limbs.
+//!
+//! Single-limb primitives (add with carry, subtract with borrow, widening
+//! multiply and 128-by-64 division) and routines over little-endian limb
+//! arrays, least significant limb first. The array routines read all of
+//! their inputs before writing any output, so `out` may overlap `a` or `b`.
+
+use crate::MathStatus;
+use crate::bigint::mul;
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+use crate::int128::Uint128;
+
+fn add_with_carry(a: u64, b: u64, carry: bool) -> (u64, bool) {
+    let (sum, c1) = a.overflowing_add(b);
+    let (sum, c2) = sum.overflowing_add(carry as u64);
+    (sum, c1 || c2)
+}
+
+fn sub_with_borrow(a: u64, b: u64, borrow: bool) -> (u64, bool) {
+    let (diff, b1) = a.overflowing_sub(b);
+    let (diff, b2) = diff.overflowing_sub(borrow as u64);
+    (diff, b1 || b2)
+}
+
+/// (hi * 2^64 + lo) / divisor and the remainder; the quotient must fit in a
+/// limb, which holds exactly when `hi < divisor`.
+fn div_wide(hi: u64, lo: u64, divisor: u64) -> MathResult<(u64, u64)> {
+    if divisor == 0 {
+        return Err(MathError::new(
+            MathStatus::DivisionByZero,
+            "division of a 128-bit value by zero",
+        ));
+    }
+    if hi >= divisor {
+        return Err(MathError::overflow(format!(
+            "quotient of a 128-bit value with high limb {hi} by {divisor} does not fit in 64 bits"
+        )));
+    }
+    let wide = (hi as u128) << 64 | lo as u128;
+    Ok((
+        (wide / divisor as u128) as u64,
+        (wide % divisor as u128) as u64,
+    ))
+}
+
+/// Limb-wise `a + b` (or `a - b` when `subtract`) and the carry (or borrow)
+/// out of the top limb.
+fn add_sub_n(a: &[u64], b: &[u64], subtract: bool) -> (Vec<u64>, bool) {
+    let step = if subtract {
+        sub_with_borrow
+    } else {
+        add_with_carry
+    };
+    let mut carry = false;
+    let out = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let (limb, c) = step(x, y, carry);
+            carry = c;
+            limb
+        })
+        .collect();
+    (out, carry)
+}
+
+/// Shared body of `limbs_add` and `limbs_sub`.
+///
+/// # Safety
+///
+/// See [`limbs_add`].
+unsafe fn add_sub_export(
+    a: *const u64,
+    b: *const u64,
+    out: *mut u64,
+    len: usize,
+    carry: *mut bool,
+    subtract: bool,
+) -> MathStatus {
+    let carry_name = if subtract { "borrow" } else { "carry" };
+    ffi::guard_status(|| {
+        let (limbs, c) = {
+            let a = unsafe { ffi::slice_ref(a, len, "a") }?;
+            let b = unsafe { ffi::slice_ref(b, len, "b") }?;
+            add_sub_n(a, b, subtract)
+        };
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        out.copy_from_slice(&limbs);
+        unsafe { ffi::write_out(carry, carry_name, c) }
+    })
+}
+
+/// Return `a + b + *carry` modulo 2^64 and set `*carry` to the carry out.
+///
+/// Returns zero (and records an error) if `carry` is null.
+///
+/// # Safety
+///
+/// `carry` must be null or valid for reading and writing a single `bool`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limb_add_with_carry(a: u64, b: u64, carry: *mut bool) -> u64 {
+    ffi::guard(0, || {
+        let carry_in = *unsafe { carry.as_ref() }
+            .ok_or_else(|| MathError::invalid_argument("`carry` is null"))?;
+        let (sum, carry_out) = add_with_carry(a, b, carry_in);
+        unsafe { ffi::write_out(carry, "carry", carry_out) }?;
+        Ok(sum)
+    })
+}
+
+/// Return `a - b - *borrow` modulo 2^64 and set `*borrow` to the borrow out.
+///
+/// Returns zero (and records an error) if `borrow` is null.
+///
+/// # Safety
+///
+/// `borrow` must be null or valid for reading and writing a single `bool`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limb_sub_with_borrow(a: u64, b: u64, borrow: *mut bool) -> u64 {
+    ffi::guard(0, || {
+        let borrow_in = *unsafe { borrow.as_ref() }
+            .ok_or_else(|| MathError::invalid_argument("`borrow` is null"))?;
+        let (diff, borrow_out) = sub_with_borrow(a, b, borrow_in);
+        unsafe { ffi::write_out(borrow, "borrow", borrow_out) }?;
+        Ok(diff)
+    })
+}
+
+/// Return the full 128-bit product `a * b`.
+#[unsafe(no_mangle)]
+pub extern "C" fn limb_mul_wide(a: u64, b: u64) -> Uint128 {
+    ffi::guard(Uint128::default(), || {
+        Ok(Uint128::from(a as u128 * b as u128))
+    })
+}
+
+/// Divide `hi * 2^64 + lo` by `divisor` into `quotient` and `remainder`.
+///
+/// Fails with `MathStatus::DivisionByZero` for a zero divisor and with
+/// `MathStatus::Overflow` when `hi >= divisor`, where the quotient would not
+/// fit in 64 bits.
+///
+/// # Safety
+///
+/// `quotient` and `remainder` must each be null or valid for writing a
+/// single `uint64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limb_div_wide(
+    hi: u64,
+    lo: u64,
+    divisor: u64,
+    quotient: *mut u64,
+    remainder: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let (q, r) = div_wide(hi, lo, divisor)?;
+        unsafe { ffi::write_out(quotient, "quotient", q) }?;
+        unsafe { ffi::write_out(remainder, "remainder", r) }
+    })
+}
+
+/// Add the `len`-limb numbers `a` and `b` into `out` (`len` limbs) and store
+/// the carry out of the top limb in `carry`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// limbs, `out` must be null (with `len == 0`) or valid for writing `len`
+/// limbs, and `carry` must be null or valid for writing a single `bool`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limbs_add(
+    a: *const u64,
+    b: *const u64,
+    out: *mut u64,
+    len: usize,
+    carry: *mut bool,
+) -> MathStatus {
+    unsafe { add_sub_export(a, b, out, len, carry, false) }
+}
+
+/// Subtract the `len`-limb number `b` from `a` into `out` (`len` limbs,
+/// modulo 2^(64 len)) and store the borrow out of the top limb in `borrow`.
+///
+/// # Safety
+///
+/// As for `limbs_add`, with `borrow` in place of `carry`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limbs_sub(
+    a: *const u64,
+    b: *const u64,
+    out: *mut u64,
+    len: usize,
+    borrow: *mut bool,
+) -> MathStatus {
+    unsafe { add_sub_export(a, b, out, len, borrow, true) }
+}
+
+/// Multiply `a` (`a_len` limbs) by `b` (`b_len` limbs) into `out`.
+///
+/// The product takes `a_len + b_len` limbs; `out_len` must be at least that
+/// (otherwise `MathStatus::BufferTooSmall` is returned) and any limbs beyond
+/// it are zeroed.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with a zero length) or valid for reading their
+/// lengths in limbs, and `out` must be null (with `out_len == 0`) or valid for
+/// writing `out_len` limbs.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn limbs_mul(
+    a: *const u64,
+    a_len: usize,
+    b: *const u64,
+    b_len: usize,
+    out: *mut u64,
+    out_len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let needed = a_len
+            .checked_add(b_len)
+            .ok_or_else(|| MathError::overflow("product length overflows size_t"))?;
+        if out_len < needed {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{needed} limbs required, {out_len} available"),
+            ));
+        }
+        let product = {
+            let a = unsafe { ffi::slice_ref(a, a_len, "a") }?;
+            let b = unsafe { ffi::slice_ref(b, b_len, "b") }?;
+            mul::mul(a, b)
+        };
+        let out = unsafe { ffi::slice_mut(out, out_len, "out") }?;
+        let (low, high) = out.split_at_mut(product.len());
+        low.copy_from_slice(&product);
+        high.fill(0);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigInt;
+    use crate::bigint::mag::tests::XorShift;
+
+    fn value(limbs: &[u64]) -> BigInt {
+        BigInt::from_limbs(false, limbs.to_vec())
+    }
+
+    #[test]
+    fn test_single_limb_primitives() {
+        let mut carry = true;
+        assert_eq!(unsafe { limb_add_with_carry(u64::MAX, 0, &mut carry) }, 0);
+        assert!(carry);
+        assert_eq!(unsafe { limb_add_with_carry(1, 2, &mut carry) }, 4);
+        assert!(!carry);
+
+        let mut borrow = false;
+        assert_eq!(unsafe { limb_sub_with_borrow(0, 1, &mut borrow) }, u64::MAX);
+        assert!(borrow);
+        assert_eq!(unsafe { limb_sub_with_borrow(5, 3, &mut borrow) }, 1);
+        assert!(!borrow);
+        assert_eq!(
+            unsafe { limb_add_with_carry(1, 2, std::ptr::null_mut()) },
+            0
+        );
+        assert_eq!(
+            crate::error::math_last_error_code(),
+            MathStatus::InvalidArgument
+        );
+
+        assert_eq!(
+            limb_mul_wide(u64::MAX, u64::MAX),
+            Uint128 {
+                hi: u64::MAX - 1,
+                lo: 1
+            }
+        );
+
+        let (mut q, mut r) = (0u64, 0u64);
+        assert_eq!(
+            unsafe { limb_div_wide(3, 7, 10, &mut q, &mut r) },
+            MathStatus::Ok
+        );
+        let wide = (3u128 << 64) + 7;
+        assert_eq!((q as u128, r as u128), (wide / 10, wide % 10));
+        assert_eq!(
+            unsafe { limb_div_wide(10, 0, 10, &mut q, &mut r) },
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            unsafe { limb_div_wide(0, 1, 0, &mut q, &mut r) },
+            MathStatus::DivisionByZero
+        );
+    }
+
+    #[test]
+    fn test_limb_arrays_match_bigint() {
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        for len in [1usize, 2, 5, 40] {
+            // `limbs` trims high zero limbs; pad back to the full length.
+            let (mut a, mut b) = (rng.limbs(len), rng.limbs(len));
+            a.resize(len, 0);
+            b.resize(len, 0);
+            let modulus = BigInt::one().shl_bits(64 * len);
+
+            let mut out = vec![0u64; len];
+            let mut carry = false;
+            let status =
+                unsafe { limbs_add(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), len, &mut carry) };
+            assert_eq!(status, MathStatus::Ok);
+            let sum = &value(&a) + &value(&b);
+            assert_eq!(value(&out), sum.divrem(&modulus).unwrap().1);
+            assert_eq!(carry, sum >= modulus);
+
+            let status =
+                unsafe { limbs_sub(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), len, &mut carry) };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(carry, value(&a) < value(&b));
+            let diff = &(&value(&a) - &value(&b)) + &if carry { modulus } else { BigInt::zero() };
+            assert_eq!(value(&out), diff);
+
+            let mut product = vec![u64::MAX; 2 * len + 1];
+            let status = unsafe {
+                limbs_mul(
+                    a.as_ptr(),
+                    len,
+                    b.as_ptr(),
+                    len,
+                    product.as_mut_ptr(),
+                    product.len(),
+                )
+            };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(product[2 * len], 0);
+            assert_eq!(value(&product), &value(&a) * &value(&b));
+        }
+    }
+
+    #[test]
+    fn test_limb_arrays_in_place_and_errors() {
+        let mut a = vec![u64::MAX, u64::MAX, 1];
+        let b = [1u64, 0, 0];
+        let mut carry = false;
+        let status = unsafe { limbs_add(a.as_ptr(), b.as_ptr(), a.as_mut_ptr(), 3, &mut carry) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(a, [0, 0, 2]);
+        assert!(!carry);
+
+        let mut out = [0u64; 3];
+        let status = unsafe { limbs_mul(a.as_ptr(), 3, b.as_ptr(), 1, out.as_mut_ptr(), 3) };
+        assert_eq!(status, MathStatus::BufferTooSmall);
+        let status = unsafe {
+            limbs_add(
+                std::ptr::null(),
+                b.as_ptr(),
+                out.as_mut_ptr(),
+                3,
+                &mut carry,
+            )
+        };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        let status = unsafe { limbs_mul(std::ptr::null(), 0, b.as_ptr(), 1, out.as_mut_ptr(), 3) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, [0, 0, 0]);
+    }
+}
@@ -0,0 +1,616 @@
+//! Factorials and binomial coefficients modulo an integer.
+//!
+//! For a prime `p`, n! mod p only depends on `min(n, p - 1 - n)` thanks to
+//! Wilson's theorem, and the remaining product of up to p/2 terms is computed
+//! in O(sqrt(p) log p) with the sample-shifting algorithm below. Its memory
+//! grows with sqrt(p), so `min(n, p - 1 - n)` is limited to about 2^32: every
+//! `n` is supported for primes below 2^33. Composite moduli are split into
+//! prime powers and recombined with the Chinese remainder theorem.
+
+use crate::MathStatus;
+use crate::error::{MathError, MathResult};
+use crate::factorial::legendre_exponent;
+use crate::factorization::factor_u64;
+use crate::ffi;
+use crate::number_theory::{inv_mod, mul_mod, pow_mod};
+use crate::primes;
+
+/// Below this many remaining factors a plain loop beats the fast algorithm.
+const FAST_MIN: u64 = 1 << 12;
+/// Largest block size (about sqrt(min(n, p - n))) the fast algorithm accepts;
+/// its memory use and running time grow linearly with it, to a few MB and about
+/// a second at this bound.
+const MAX_BLOCK: u64 = 1 << 16;
+/// Most factors multiplied one by one for a prime power `p^e` with `e > 1`.
+const MAX_DIRECT: u64 = 1 << 26;
+
+/// (a + b) mod m without overflowing for moduli close to 2^64.
+fn add_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 + b as u128) % m as u128) as u64
+}
+
+/// Inverse of `a` modulo a prime `p`.
+fn inv_mod_prime(a: u64, p: u64) -> u64 {
+    pow_mod(a, p - 2, p)
+}
+
+/// Product of `lo..=hi` modulo `m`, stopping early once it reaches zero.
+fn range_product_mod(lo: u64, hi: u64, m: u64) -> u64 {
+    let mut acc = 1 % m;
+    let mut k = lo;
+    while k <= hi && acc != 0 {
+        acc = mul_mod(acc, k % m, m);
+        k += 1;
+    }
+    acc
+}
+
+/// n! mod p for a prime `p`.
+pub(crate) fn factorial_mod_prime(n: u64, p: u64) -> MathResult<u64> {
+    if n >= p {
+        return Ok(0);
+    }
+    // Wilson: (p-1)! = -1, so n! = -1 / ((n+1)...(p-1))
+    //                            = (-1)^(p-n) / (p-1-n)!   (mod p)
+    let mirror = p - 1 - n;
+    if mirror >= n {
+        return product_up_to(n, p);
+    }
+    let inverse = inv_mod_prime(product_up_to(mirror, p)?, p);
+    Ok(if (p - n).is_multiple_of(2) {
+        inverse
+    } else {
+        (p - inverse) % p
+    })
+}
+
+/// 1 * 2 * ... * k mod the prime `p`, for `k < p / 2 + 1`.
+fn product_up_to(k: u64, p: u64) -> MathResult<u64> {
+    if k < FAST_MIN {
+        return Ok(range_product_mod(1, k, p));
+    }
+    let v = k.isqrt();
+    if v > MAX_BLOCK {
+        return Err(MathError::overflow(format!(
+            "{k}! mod {p} needs blocks of {v} terms, above the supported {MAX_BLOCK}"
+        )));
+    }
+    let samples = block_products(v, p);
+    let blocks = samples[..v as usize]
+        .iter()
+        .fold(1, |acc, &g| mul_mod(acc, g, p));
+    Ok(mul_mod(blocks, range_product_mod(v * v + 1, k, p), p))
+}
+
+/// Values of g(x) = (vx + 1)(vx + 2)...(vx + v) at x = 0, 1, ..., v, so that
+/// the first `v` of them multiply to (v²)! mod p.
+///
+/// Works with g_d(x) = prod_{i=1..d} (vx + i) sampled at x = 0..=d and
+/// doubles `d` via g_2d(x) = g_d(x) g_d(x + d/v), with the extra samples
+/// obtained by shifting the sampling points of g_d (min_25's algorithm).
+/// Requires `v² + 2v < p`, which holds because v² <= p / 2.
+fn block_products(v: u64, p: u64) -> Vec<u64> {
+    let inv_v = inv_mod_prime(v % p, p);
+    let mut g = vec![1, (v + 1) % p];
+    let mut d = 1u64;
+    for bit in (0..u64::BITS - 1 - v.leading_zeros()).rev() {
+        let upper = shift_samples(&g, d + 1, p);
+        let offset = mul_mod(d, inv_v, p);
+        let mut shifted = shift_samples(&g, offset, p);
+        shifted.extend(shift_samples(&g, add_mod(offset, d + 1, p), p));
+        g.extend(upper);
+        g.truncate(2 * d as usize + 1);
+        for (value, other) in g.iter_mut().zip(&shifted) {
+            *value = mul_mod(*value, *other, p);
+        }
+        d *= 2;
+
+        if v >> bit & 1 == 1 {
+            // g_{d+1}(x) = g_d(x) (vx + d + 1), plus one new sample at x = d + 1.
+            for (x, value) in g.iter_mut().enumerate() {
+                let factor = add_mod(mul_mod(v, x as u64, p), d + 1, p);
+                *value = mul_mod(*value, factor, p);
+            }
+            let base = mul_mod(v, d + 1, p);
+            g.push(range_product_mod_offset(base, d + 1, p));
+            d += 1;
+        }
+    }
+    g
+}
+
+/// (base + 1)(base + 2)...(base + count) mod p.
+fn range_product_mod_offset(base: u64, count: u64, p: u64) -> u64 {
+    (1..=count).fold(1, |acc, i| mul_mod(acc, add_mod(base, i, p), p))
+}
+
+/// Given h(0), ..., h(d) for a polynomial h of degree at most `d`, return
+/// h(m), ..., h(m + d) by Lagrange interpolation:
+///
+/// h(m + k) = prod_{j=0..d} (m + k - j) * sum_i a_i / (m + k - i),
+/// a_i = h(i) / (i! (d - i)! (-1)^(d - i)),
+///
+/// where the sum is one convolution. All of m - d, ..., m + d must be
+/// non-zero modulo `p`.
+fn shift_samples(h: &[u64], m: u64, p: u64) -> Vec<u64> {
+    let d = h.len() - 1;
+    let mut inv_fact = vec![1u64; d + 1];
+    let mut fact = 1u64;
+    for i in 1..=d {
+        fact = mul_mod(fact, i as u64, p);
+    }
+    inv_fact[d] = inv_mod_prime(fact, p);
+    for i in (1..=d).rev() {
+        inv_fact[i - 1] = mul_mod(inv_fact[i], i as u64, p);
+    }
+    let a: Vec<u64> = (0..=d)
+        .map(|i| {
+            let value = mul_mod(h[i], mul_mod(inv_fact[i], inv_fact[d - i], p), p);
+            if (d - i) % 2 == 1 {
+                (p - value) % p
+            } else {
+                value
+            }
+        })
+        .collect();
+
+    // The points m - d + j for j = 0..=2d, their prefix products and inverses.
+    let points: Vec<u64> = (0..=2 * d as u64)
+        .map(|j| add_mod(add_mod(m, p - d as u64 % p, p), j, p))
+        .collect();
+    let mut prefix = Vec::with_capacity(points.len() + 1);
+    prefix.push(1u64);
+    for &x in &points {
+        prefix.push(mul_mod(
+            *prefix.last().expect("prefix starts non-empty"),
+            x,
+            p,
+        ));
+    }
+    let mut inverse = vec![0u64; points.len()];
+    let mut running = inv_mod_prime(prefix[points.len()], p);
+    for j in (0..points.len()).rev() {
+        inverse[j] = mul_mod(running, prefix[j], p);
+        running = mul_mod(running, points[j], p);
+    }
+
+    let c = ntt::convolve_mod(&a, &inverse, p);
+    (0..=d)
+        .map(|k| {
+            // prod_{j=0..d} (m + k - j) = prefix[k + d + 1] / prefix[k]
+            let window = mul_mod(prefix[k + d + 1], inv_mod_prime(prefix[k], p), p);
+            mul_mod(c[d + k], window, p)
+        })
+        .collect()
+}
+
+/// Number-theoretic transforms over three 62-bit primes, combined with
+/// Garner's algorithm so that convolutions of arbitrary residues below 2^64
+/// can be reduced modulo any 64-bit modulus.
+mod ntt {
+    use super::add_mod;
+    use crate::number_theory::{mul_mod, pow_mod};
+
+    /// (prime, primitive root); each prime is c * 2^30 + 1.
+    const PRIMES: [(u64, u64); 3] = [
+        (4_611_685_944_339_202_049, 3),
+        (4_611_685_941_117_976_577, 3),
+        (4_611_685_917_495_656_449, 11),
+    ];
+
+    fn transform(a: &mut [u64], invert: bool, (p, root): (u64, u64)) {
+        let n = a.len();
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                a.swap(i, j);
+            }
+        }
+        let mut len = 2;
+        while len <= n {
+            let mut w_len = pow_mod(root, (p - 1) / len as u64, p);
+            if invert {
+                w_len = pow_mod(w_len, p - 2, p);
+            }
+            for chunk in a.chunks_mut(len) {
+                let (lo, hi) = chunk.split_at_mut(len / 2);
+                let mut w = 1u64;
+                for (x, y) in lo.iter_mut().zip(hi.iter_mut()) {
+                    let u = *x;
+                    let t = mul_mod(*y, w, p);
+                    *x = if u + t >= p { u + t - p } else { u + t };
+                    *y = if u >= t { u - t } else { u + p - t };
+                    w = mul_mod(w, w_len, p);
+                }
+            }
+            len <<= 1;
+        }
+        if invert {
+            let n_inv = pow_mod(n as u64, p - 2, p);
+            for x in a.iter_mut() {
+                *x = mul_mod(*x, n_inv, p);
+            }
+        }
+    }
+
+    fn convolve_prime(a: &[u64], b: &[u64], prime: (u64, u64)) -> Vec<u64> {
+        let len = (a.len() + b.len() - 1).next_power_of_two();
+        let p = prime.0;
+        let mut fa: Vec<u64> = a.iter().map(|&x| x % p).collect();
+        let mut fb: Vec<u64> = b.iter().map(|&x| x % p).collect();
+        fa.resize(len, 0);
+        fb.resize(len, 0);
+        transform(&mut fa, false, prime);
+        transform(&mut fb, false, prime);
+        for (x, y) in fa.iter_mut().zip(&fb) {
+            *x = mul_mod(*x, *y, p);
+        }
+        transform(&mut fa, true, prime);
+        fa.truncate(a.len() + b.len() - 1);
+        fa
+    }
+
+    /// Cyclic-free convolution of `a` and `b` with every entry reduced
+    /// modulo `m`. Exact as long as `min(len) * max(entry)^2 < 2^186`.
+    pub(super) fn convolve_mod(a: &[u64], b: &[u64], m: u64) -> Vec<u64> {
+        let [(p0, _), (p1, _), (p2, _)] = PRIMES;
+        let r: Vec<Vec<u64>> = PRIMES
+            .iter()
+            .map(|&prime| convolve_prime(a, b, prime))
+            .collect();
+        let p0_inv_p1 = pow_mod(p0 % p1, p1 - 2, p1);
+        let p01_inv_p2 = pow_mod(mul_mod(p0 % p2, p1 % p2, p2), p2 - 2, p2);
+        let p0_m = p0 % m;
+        let p01_m = mul_mod(p0_m, p1 % m, m);
+        (0..r[0].len())
+            .map(|i| {
+                let (r0, r1, r2) = (r[0][i], r[1][i], r[2][i]);
+                // x = r0 + p0 t1 + p0 p1 t2 with t1 < p1, t2 < p2.
+                let t1 = mul_mod((r1 + p1 - r0 % p1) % p1, p0_inv_p1, p1);
+                let x01 = (r0 % p2 + mul_mod(p0 % p2, t1 % p2, p2)) % p2;
+                let t2 = mul_mod((r2 + p2 - x01) % p2, p01_inv_p2, p2);
+                let low = add_mod(r0 % m, mul_mod(p0_m, t1 % m, m), m);
+                add_mod(low, mul_mod(p01_m, t2 % m, m), m)
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_convolution_of_large_residues() {
+            let m = u64::MAX - 58; // 2^64 - 59 is prime
+            let a = [m - 1, m - 2, 3, m - 4];
+            let b = [m - 5, 6, m - 7];
+            let mut expected = vec![0u64; a.len() + b.len() - 1];
+            for (i, &x) in a.iter().enumerate() {
+                for (j, &y) in b.iter().enumerate() {
+                    expected[i + j] =
+                        ((expected[i + j] as u128 + mul_mod(x, y, m) as u128) % m as u128) as u64;
+                }
+            }
+            assert_eq!(convolve_mod(&a, &b, m), expected);
+        }
+    }
+}
+
+/// n! mod m, splitting `m` into prime powers and recombining them with the
+/// Chinese remainder theorem.
+fn factorial_mod(n: u64, m: u64) -> MathResult<u64> {
+    if m == 0 {
+        return Err(MathError::new(
+            MathStatus::DivisionByZero,
+            "modulus is zero",
+        ));
+    }
+    if m == 1 || n >= m {
+        // m divides n! whenever n >= m.
+        return Ok(0);
+    }
+
+    let mut residue = 0u64;
+    let mut modulus = 1u64;
+    for (p, e) in factor_u64(m) {
+        let power = p.pow(e);
+        let r = if e == 1 {
+            factorial_mod_prime(n, p)?
+        } else if legendre_exponent(n, p) >= e as u64 {
+            0
+        } else if n <= MAX_DIRECT {
+            // p^e does not divide n!, so n < p * e: a plain loop.
+            range_product_mod(2, n, power)
+        } else {
+            return Err(MathError::overflow(format!(
+                "{n}! mod {p}^{e} needs a direct product of {n} terms, above the supported {MAX_DIRECT}"
+            )));
+        };
+        residue = crt_pair(residue, modulus, r, power);
+        modulus *= power;
+    }
+    Ok(residue)
+}
+
+/// The x mod m1*m2 with x = r1 (mod m1) and x = r2 (mod m2), for coprime moduli.
+fn crt_pair(r1: u64, m1: u64, r2: u64, m2: u64) -> u64 {
+    let inv = inv_mod(m1 % m2, m2).expect("CRT moduli are coprime");
+    let diff = (r2 + m2 - r1 % m2) % m2;
+    let t = mul_mod(diff, inv, m2);
+    (r1 as u128 + m1 as u128 * t as u128) as u64
+}
+
+/// binomial(n, k) mod p via Lucas' theorem: the product over base-p digits
+/// of binomial(n_i, k_i), each computed from factorials mod p.
+fn binomial_mod_prime(mut n: u64, mut k: u64, p: u64) -> MathResult<u64> {
+    if k > n {
+        return Ok(0);
+    }
+    let mut result = 1 % p;
+    while k > 0 && result != 0 {
+        let (ni, ki) = (n % p, k % p);
+        if ki > ni {
+            return Ok(0);
+        }
+        let numerator = factorial_mod_prime(ni, p)?;
+        let denominator = mul_mod(
+            factorial_mod_prime(ki, p)?,
+            factorial_mod_prime(ni - ki, p)?,
+            p,
+        );
+        result = mul_mod(
+            result,
+            mul_mod(numerator, inv_mod_prime(denominator, p), p),
+            p,
+        );
+        n /= p;
+        k /= p;
+    }
+    Ok(result)
+}
+
+fn check_prime(p: u64) -> MathResult<()> {
+    if primes::is_prime(p) {
+        Ok(())
+    } else {
+        Err(MathError::invalid_argument(format!("{p} is not prime")))
+    }
+}
+
+/// Store n! mod `m` in `out`.
+///
+/// `m` is factored, each prime factor `p` is handled as in
+/// `factorial_mod_p`, and the results are combined with the Chinese
+/// remainder theorem. A repeated factor `p^e` with `n < p * e` needs a
+/// product of `n` terms, so it is limited to `n <= 2^26`.
+///
+/// Returns `MathStatus::DivisionByZero` if `m` is zero and
+/// `MathStatus::Overflow` if `n` is out of the supported range.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mod_m(n: u64, m: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", factorial_mod(n, m)?) })
+}
+
+/// Store n! mod `p` in `out` for a prime `p`, in O(sqrt(k) log k) for
+/// `k = min(n, p - 1 - n)`.
+///
+/// `k` must be below about 2^32, which holds for every `n` when `p < 2^33`;
+/// the work grows with sqrt(k), up to a few MB and about a second.
+/// Returns `MathStatus::InvalidArgument` if `p` is not prime and
+/// `MathStatus::Overflow` if `k` is larger.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factorial_mod_p(n: u64, p: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        check_prime(p)?;
+        unsafe { ffi::write_out(out, "out", factorial_mod_prime(n, p)?) }
+    })
+}
+
+/// Store binomial(n, k) mod `p` in `out` for a prime `p` (Lucas' theorem).
+///
+/// Each base-`p` digit needs factorials mod `p` as in `factorial_mod_p`, so
+/// every `n` and `k` are supported for primes below 2^33. Returns
+/// `MathStatus::InvalidArgument` if `p` is not prime and
+/// `MathStatus::Overflow` if a digit is out of the supported range.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn binomial_mod_p(n: u64, k: u64, p: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        check_prime(p)?;
+        unsafe { ffi::write_out(out, "out", binomial_mod_prime(n, k, p)?) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(n: u64, m: u64) -> u64 {
+        range_product_mod(1, n, m)
+    }
+
+    #[test]
+    fn test_shift_samples() {
+        // h(x) = x^2 + 3x + 5 sampled at 0..=2, shifted to 10..=12.
+        let p = 1_000_003;
+        let h = |x: u64| (x * x + 3 * x + 5) % p;
+        let samples = [h(0), h(1), h(2)];
+        assert_eq!(shift_samples(&samples, 10, p), vec![h(10), h(11), h(12)]);
+    }
+
+    #[test]
+    fn test_factorial_mod_prime_matches_naive() {
+        let p = 1_000_003;
+        for n in [
+            0,
+            1,
+            5,
+            FAST_MIN - 1,
+            FAST_MIN,
+            65_537,
+            250_000,
+            499_999,
+            500_001,
+            777_777,
+            p - 2,
+            p - 1,
+            p,
+        ] {
+            assert_eq!(
+                factorial_mod_prime(n, p).unwrap(),
+                naive(n, p),
+                "{n}! mod {p}"
+            );
+        }
+        for p in [2u64, 3, 5, 7, 10_007, 65_537] {
+            for n in [0, 1, p / 3, p / 2, p - 1] {
+                assert_eq!(
+                    factorial_mod_prime(n, p).unwrap(),
+                    naive(n, p),
+                    "{n}! mod {p}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_factorial_mod_large_prime() {
+        // Consecutive factorials take separate fast paths; their ratio is n + 1.
+        let p = 2_305_843_009_213_693_951; // 2^61 - 1
+        let n = 1_000_000_123;
+        let a = factorial_mod_prime(n, p).unwrap();
+        let b = factorial_mod_prime(n + 1, p).unwrap();
+        assert_eq!(b, mul_mod(a, n + 1, p));
+        assert_eq!(factorial_mod_prime(p - 1, p).unwrap(), p - 1);
+        assert!(factorial_mod_prime(p / 2, p).is_err());
+        assert!(factorial_mod_prime((MAX_BLOCK + 1).pow(2), p).is_err());
+    }
+
+    #[test]
+    fn test_factorial_mod_composite() {
+        for m in [
+            4u64,
+            6,
+            12,
+            100,
+            1024,
+            3 * 1_000_003,
+            720_720,
+            65_521 * 65_537,
+        ] {
+            for n in [0, 1, 3, 7, 20, 100, 5000] {
+                assert_eq!(factorial_mod(n, m).unwrap(), naive(n, m), "{n}! mod {m}");
+            }
+        }
+        assert_eq!(
+            factorial_mod(1_000_000, 2 * 1_000_003).unwrap(),
+            naive(1_000_000, 2 * 1_000_003)
+        );
+        assert_eq!(
+            factorial_mod(5, 0).unwrap_err().status(),
+            MathStatus::DivisionByZero
+        );
+        assert_eq!(factorial_mod(5, 1).unwrap(), 0);
+
+        // Two 32-bit primes: each takes the prime path (via Wilson's theorem).
+        let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+        let n = q - 1000;
+        let r = factorial_mod(n, p * q).unwrap();
+        assert_eq!(r % p, factorial_mod_prime(n, p).unwrap());
+        assert_eq!(r % q, factorial_mod_prime(n, q).unwrap());
+        assert_eq!(factorial_mod(1 << 40, p * q).unwrap(), 0);
+        // The square of a 31-bit prime needs a direct product of n terms.
+        let p = 2_147_483_647u64;
+        assert_eq!(
+            factorial_mod(p + 5, p * p).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        assert_eq!(factorial_mod(2 * p, p * p).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_binomial_mod_prime() {
+        let mut row = vec![1u64];
+        for n in 1..=60u64 {
+            let mut next = vec![1u64; n as usize + 1];
+            for k in 1..n as usize {
+                next[k] = row[k - 1] + row[k];
+            }
+            row = next;
+            for p in [2u64, 3, 7, 13, 1_000_003] {
+                for k in 0..=n {
+                    assert_eq!(
+                        binomial_mod_prime(n, k, p).unwrap(),
+                        row[k as usize] % p,
+                        "C({n}, {k}) mod {p}"
+                    );
+                }
+            }
+        }
+        assert_eq!(binomial_mod_prime(5, 6, 7).unwrap(), 0);
+        // C(10^18, 10^9) mod 13 via Lucas digits.
+        let (n, k, p) = (1_000_000_000_000_000_000u64, 1_000_000_000u64, 13u64);
+        let mut expected = 1;
+        let (mut nn, mut kk) = (n, k);
+        while kk > 0 {
+            expected = expected * binomial_mod_prime(nn % p, kk % p, p).unwrap() % p;
+            nn /= p;
+            kk /= p;
+        }
+        assert_eq!(binomial_mod_prime(n, k, p).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_exports() {
+        let mut out = 0u64;
+        assert_eq!(unsafe { factorial_mod_p(10, 11, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 10);
+        assert_eq!(
+            unsafe { factorial_mod_p(10, 12, &mut out) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { factorial_mod_m(10, 1_000, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 800);
+        assert_eq!(
+            unsafe { binomial_mod_p(10, 3, 7, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 1);
+        assert_eq!(
+            unsafe { binomial_mod_p(10, 3, 8, &mut out) },
+            MathStatus::InvalidArgument
+        );
+
+        // Near either end of a 64-bit prime, but not in the middle.
+        let p = 18_446_744_073_709_551_557;
+        assert_eq!(
+            unsafe { factorial_mod_p(p - 2, p, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 1);
+        assert_eq!(
+            unsafe { factorial_mod_p(1_000_000_000_000_000_000, p, &mut out) },
+            MathStatus::Overflow
+        );
+    }
+}
@@ -0,0 +1,492 @@
+//! Arithmetic functions of the prime factorisation.
+//!
+//! Euler's totient φ, the Möbius function μ, Liouville's λ, the divisor sums
+//! σ_k (σ_0 counts the divisors), the sorted list of divisors and the
+//! Carmichael function λ. Each is evaluated for a single `uint64_t` from its
+//! factorisation, or tabulated for every `n` below a bound with a linear
+//! sieve of smallest prime factors, writing into a caller-owned array.
+
+use crate::error::{MathError, MathResult};
+use crate::factorization::factor_u64;
+use crate::number_theory::lcm;
+use crate::{MathStatus, ffi};
+
+/// φ(p^e).
+fn totient_prime_power(p: u64, e: u32) -> Option<u64> {
+    Some(p.pow(e - 1) * (p - 1))
+}
+
+/// μ(p^e).
+fn mobius_prime_power(_: u64, e: u32) -> Option<i32> {
+    Some(if e == 1 { -1 } else { 0 })
+}
+
+/// λ(p^e) for Liouville's λ.
+fn liouville_prime_power(_: u64, e: u32) -> Option<i32> {
+    Some(if e % 2 == 1 { -1 } else { 1 })
+}
+
+/// σ_k(p^e) = 1 + p^k + ... + p^(ek), if it fits.
+fn sigma_prime_power(p: u64, e: u32, k: u32) -> Option<u64> {
+    (0..=e).try_fold(0u64, |sum, i| {
+        sum.checked_add(p.checked_pow(i.checked_mul(k)?)?)
+    })
+}
+
+/// λ(p^e) for Carmichael's λ: φ(p^e), halved for powers of two from 8 on.
+fn carmichael_prime_power(p: u64, e: u32) -> Option<u64> {
+    if p == 2 && e >= 3 {
+        Some(1 << (e - 2))
+    } else {
+        totient_prime_power(p, e)
+    }
+}
+
+fn undefined_at_zero(name: &str) -> MathError {
+    MathError::invalid_argument(format!("{name}(0) is undefined"))
+}
+
+fn too_large(name: &str, n: u64) -> MathError {
+    MathError::overflow(format!("{name}({n}) does not fit in u64"))
+}
+
+/// `f(n)` for `n > 0`, folding `combine` over the values of `f` at the prime
+/// powers of `n`, starting from `f(1)`.
+fn evaluate<T>(
+    name: &str,
+    n: u64,
+    one: T,
+    prime_power: impl Fn(u64, u32) -> Option<T>,
+    combine: impl Fn(T, T) -> Option<T>,
+) -> MathResult<T> {
+    if n == 0 {
+        return Err(undefined_at_zero(name));
+    }
+    factor_u64(n)
+        .into_iter()
+        .try_fold(one, |acc, (p, e)| combine(acc, prime_power(p, e)?))
+        .ok_or_else(|| too_large(name, n))
+}
+
+/// The smallest prime factor of every `n < len` (0 for 0 and 1), from a
+/// linear sieve that visits each composite once.
+fn smallest_prime_factors(len: usize) -> Vec<usize> {
+    let mut lowest = vec![0; len];
+    let mut primes = Vec::new();
+    for i in 2..len {
+        if lowest[i] == 0 {
+            lowest[i] = i;
+            primes.push(i);
+        }
+        for &p in &primes {
+            if p > lowest[i] || p > (len - 1) / i {
+                break;
+            }
+            lowest[i * p] = p;
+        }
+    }
+    lowest
+}
+
+/// Fill `out[n]` with `f(n)` for every `n`, as in [`evaluate`], with
+/// `out[0] = zero`. Each value splits off the power of the smallest prime
+/// factor and reuses the entry for the cofactor.
+fn tabulate<T: Copy>(
+    name: &str,
+    out: &mut [T],
+    (zero, one): (T, T),
+    prime_power: impl Fn(u64, u32) -> Option<T>,
+    combine: impl Fn(T, T) -> Option<T>,
+) -> MathResult<()> {
+    let lowest = smallest_prime_factors(out.len());
+    for n in 0..out.len() {
+        out[n] = match n {
+            0 => zero,
+            1 => one,
+            _ => {
+                let p = lowest[n];
+                let (mut rest, mut e) = (n, 0);
+                while rest % p == 0 {
+                    rest /= p;
+                    e += 1;
+                }
+                prime_power(p as u64, e)
+                    .and_then(|value| combine(out[rest], value))
+                    .ok_or_else(|| too_large(name, n as u64))?
+            }
+        };
+    }
+    Ok(())
+}
+
+/// The divisors of `n > 0` in increasing order.
+fn divisors(n: u64) -> Vec<u64> {
+    let mut divisors = vec![1];
+    for (p, e) in factor_u64(n) {
+        let smaller = divisors.len();
+        let mut power = 1;
+        for _ in 0..e {
+            power *= p;
+            let start = divisors.len();
+            divisors.extend_from_within(..smaller);
+            for d in &mut divisors[start..] {
+                *d *= power;
+            }
+        }
+    }
+    divisors.sort_unstable();
+    divisors
+}
+
+/// Store Euler's totient φ(n), the count of `1 <= k <= n` coprime to `n`,
+/// in `out`.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn euler_totient_u64(n: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = evaluate("totient", n, 1, totient_prime_power, u64::checked_mul)?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Store the Möbius function μ(n) in `out`: 0 if a square divides `n`,
+/// otherwise -1 or 1 for an odd or even number of prime factors.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mobius_u64(n: u64, out: *mut i32) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = evaluate("mobius", n, 1, mobius_prime_power, i32::checked_mul)?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Store Liouville's λ(n) in `out`: -1 or 1 for an odd or even number of
+/// prime factors counted with multiplicity.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn liouville_u64(n: u64, out: *mut i32) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = evaluate("liouville", n, 1, liouville_prime_power, i32::checked_mul)?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Store σ_k(n), the sum of the `k`-th powers of the divisors of `n`, in
+/// `out`. σ_0 is the number of divisors and σ_1 their sum.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero and
+/// `MathStatus::Overflow` if the sum does not fit in `u64`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn divisor_sigma_u64(n: u64, k: u32, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let name = format!("sigma_{k}");
+        let sigma = |p, e| sigma_prime_power(p, e, k);
+        let value = evaluate(&name, n, 1, sigma, u64::checked_mul)?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Store the Carmichael function λ(n), the exponent of the multiplicative
+/// group modulo `n`, in `out`.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn carmichael_lambda_u64(n: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        let value = evaluate("carmichael_lambda", n, 1, carmichael_prime_power, |a, b| {
+            lcm(a, b).ok()
+        })?;
+        unsafe { ffi::write_out(out, "out", value) }
+    })
+}
+
+/// Write the divisors of `n` to `out_buf` in increasing order, and their
+/// number to `out_len` (if non-null). A `uint64_t` has at most 184320.
+///
+/// Returns `MathStatus::InvalidArgument` if `n` is zero. With more divisors
+/// than `capacity`, the first `capacity` are written and
+/// `MathStatus::BufferTooSmall` is returned; `out_len` still receives the
+/// full count.
+///
+/// # Safety
+///
+/// `out_buf` must be null (with `capacity == 0`) or valid for writing
+/// `capacity` values, and `out_len` must be null or valid for writing a
+/// single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn divisors_u64(
+    n: u64,
+    out_buf: *mut u64,
+    capacity: usize,
+    out_len: *mut usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        if n == 0 {
+            return Err(MathError::invalid_argument("every integer divides 0"));
+        }
+        let divisors = divisors(n);
+        let buf = unsafe { ffi::slice_mut(out_buf, capacity, "out_buf") }?;
+        for (slot, &d) in buf.iter_mut().zip(&divisors) {
+            *slot = d;
+        }
+        if !out_len.is_null() {
+            unsafe { out_len.write(divisors.len()) };
+        }
+        if divisors.len() > capacity {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("{} divisors, {capacity} available", divisors.len()),
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Fill `out[n]` with φ(n) for every `n < len`; `out[0]` is 0.
+///
+/// # Safety
+///
+/// `out` must be null (with `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn euler_totient_table(out: *mut u64, len: usize) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        tabulate(
+            "totient",
+            out,
+            (0, 1),
+            totient_prime_power,
+            u64::checked_mul,
+        )
+    })
+}
+
+/// Fill `out[n]` with μ(n) for every `n < len`; `out[0]` is 0.
+///
+/// # Safety
+///
+/// `out` must be null (with `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mobius_table(out: *mut i32, len: usize) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        tabulate("mobius", out, (0, 1), mobius_prime_power, i32::checked_mul)
+    })
+}
+
+/// Fill `out[n]` with Liouville's λ(n) for every `n < len`; `out[0]` is 0.
+///
+/// # Safety
+///
+/// `out` must be null (with `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn liouville_table(out: *mut i32, len: usize) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        tabulate(
+            "liouville",
+            out,
+            (0, 1),
+            liouville_prime_power,
+            i32::checked_mul,
+        )
+    })
+}
+
+/// Fill `out[n]` with σ_k(n) for every `n < len`; `out[0]` is 0.
+///
+/// Returns `MathStatus::Overflow` at the first `n` whose sum does not fit
+/// in `u64`; the entries before it are written.
+///
+/// # Safety
+///
+/// `out` must be null (with `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn divisor_sigma_table(k: u32, out: *mut u64, len: usize) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        let sigma = |p, e| sigma_prime_power(p, e, k);
+        tabulate(&format!("sigma_{k}"), out, (0, 1), sigma, u64::checked_mul)
+    })
+}
+
+/// Fill `out[n]` with Carmichael's λ(n) for every `n < len`; `out[0]` is 0.
+///
+/// # Safety
+///
+/// `out` must be null (with `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn carmichael_lambda_table(out: *mut u64, len: usize) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(out, len, "out") }?;
+        tabulate(
+            "carmichael_lambda",
+            out,
+            (0, 1),
+            carmichael_prime_power,
+            |a, b| lcm(a, b).ok(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use crate::number_theory::{gcd, pow_mod};
+
+    const LEN: usize = 2000;
+
+    fn scalar<T: Default>(f: unsafe extern "C" fn(u64, *mut T) -> MathStatus, n: u64) -> T {
+        let mut out = T::default();
+        assert_eq!(unsafe { f(n, &mut out) }, MathStatus::Ok, "{n}");
+        out
+    }
+
+    fn table<T: Default + Clone>(f: unsafe extern "C" fn(*mut T, usize) -> MathStatus) -> Vec<T> {
+        let mut out = vec![T::default(); LEN];
+        assert_eq!(unsafe { f(out.as_mut_ptr(), LEN) }, MathStatus::Ok);
+        out
+    }
+
+    fn sigma(n: u64, k: u32) -> Result<u64, MathStatus> {
+        let mut out = 0;
+        match unsafe { divisor_sigma_u64(n, k, &mut out) } {
+            MathStatus::Ok => Ok(out),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    fn test_against_definitions() {
+        let totients = table(euler_totient_table);
+        let mobius = table(mobius_table);
+        let liouville = table(liouville_table);
+        let carmichael = table(carmichael_lambda_table);
+        let mut sigma_2 = vec![0; LEN];
+        let status = unsafe { divisor_sigma_table(2, sigma_2.as_mut_ptr(), LEN) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!((totients[0], mobius[0], carmichael[0]), (0, 0, 0));
+
+        for n in 1..LEN as u64 {
+            let i = n as usize;
+            let coprime = (1..=n).filter(|&k| gcd(k, n) == 1);
+            let totient = coprime.clone().count() as u64;
+            // The least exponent with k^λ == 1 for every unit k.
+            let lambda = (1..=n)
+                .find(|&e| coprime.clone().all(|k| pow_mod(k, e, n) == 1 % n))
+                .unwrap();
+            let divisors: Vec<u64> = (1..=n).filter(|d| n % d == 0).collect();
+            let (mut rest, mut omega, mut square_free) = (n, 0, true);
+            for p in 2..=n {
+                let mut e = 0;
+                while rest % p == 0 {
+                    rest /= p;
+                    e += 1;
+                }
+                omega += e;
+                square_free &= e < 2;
+            }
+            let parity = if omega % 2 == 0 { 1 } else { -1 };
+
+            assert_eq!(scalar(euler_totient_u64, n), totient);
+            assert_eq!(totients[i], totient, "{n}");
+            assert_eq!(scalar(carmichael_lambda_u64, n), lambda);
+            assert_eq!(carmichael[i], lambda, "{n}");
+            let mu = if square_free { parity } else { 0 };
+            assert_eq!(scalar(mobius_u64, n), mu);
+            assert_eq!(mobius[i], mu, "{n}");
+            assert_eq!(scalar(liouville_u64, n), parity);
+            assert_eq!(liouville[i], parity, "{n}");
+            assert_eq!(sigma(n, 0).unwrap(), divisors.len() as u64);
+            assert_eq!(sigma(n, 1).unwrap(), divisors.iter().sum::<u64>());
+            assert_eq!(sigma_2[i], divisors.iter().map(|d| d * d).sum::<u64>());
+
+            let mut buf = [0; 64];
+            let mut len = 0;
+            let status = unsafe { divisors_u64(n, buf.as_mut_ptr(), buf.len(), &mut len) };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(buf[..len], divisors);
+        }
+    }
+
+    #[test]
+    fn test_large_arguments() {
+        // u64::MAX = 3 * 5 * 17 * 257 * 641 * 65537 * 6700417.
+        let primes = [3u64, 5, 17, 257, 641, 65_537, 6_700_417];
+        let totient: u64 = primes.iter().map(|p| p - 1).product();
+        assert_eq!(scalar(euler_totient_u64, u64::MAX), totient);
+        assert_eq!(scalar(mobius_u64, u64::MAX), -1);
+        let lambda = primes.iter().try_fold(1, |l, p| lcm(l, p - 1)).unwrap();
+        assert_eq!(scalar(carmichael_lambda_u64, u64::MAX), lambda);
+        assert_eq!(scalar(carmichael_lambda_u64, 1 << 63), 1 << 61);
+        assert_eq!(sigma(u64::MAX, 0).unwrap(), 128);
+        assert_eq!(sigma(1 << 63, 1).unwrap(), u64::MAX);
+        assert_eq!(sigma(1 << 63, 2).unwrap_err(), MathStatus::Overflow);
+        assert_eq!(
+            ffi::take_string(error::math_last_error_message()),
+            "overflow: sigma_2(9223372036854775808) does not fit in u64"
+        );
+        // The most divisors of any u64.
+        let mut len = 0;
+        let n = 18_401_055_938_125_660_800;
+        let status = unsafe { divisors_u64(n, std::ptr::null_mut(), 0, &mut len) };
+        assert_eq!((status, len), (MathStatus::BufferTooSmall, 184_320));
+        let mut buf = vec![0; len];
+        let status = unsafe { divisors_u64(n, buf.as_mut_ptr(), len, &mut len) };
+        assert_eq!(status, MathStatus::Ok);
+        assert!(buf.windows(2).all(|w| w[0] < w[1] && n % w[0] == 0));
+        assert_eq!(buf.last(), Some(&n));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut out = 0;
+        let status = unsafe { euler_totient_u64(0, &mut out) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        assert_eq!(
+            ffi::take_string(error::math_last_error_message()),
+            "invalid argument: totient(0) is undefined"
+        );
+        let status = unsafe { divisors_u64(0, std::ptr::null_mut(), 0, std::ptr::null_mut()) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        let status = unsafe { euler_totient_table(std::ptr::null_mut(), 4) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        assert_eq!(
+            unsafe { euler_totient_table(std::ptr::null_mut(), 0) },
+            MathStatus::Ok
+        );
+
+        // σ_7 leaves u64 a little above n = 2^9; the table stops there.
+        let mut table = vec![0; 1 << 12];
+        let status = unsafe { divisor_sigma_table(7, table.as_mut_ptr(), table.len()) };
+        let first = (1..table.len() as u64)
+            .find(|&n| sigma(n, 7).is_err())
+            .unwrap();
+        assert_eq!(status, MathStatus::Overflow);
+        assert_eq!(table[first as usize - 1], sigma(first - 1, 7).unwrap());
+    }
+}
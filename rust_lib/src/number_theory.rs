@@ -0,0 +1,747 @@
+//! Core number theory on machine integers.
+//!
+//! gcd and lcm, the extended Euclidean algorithm, modular inverses and
+//! modular exponentiation, the Chinese Remainder Theorem and linear
+//! congruence and Diophantine solvers. Products of residues are formed in
+//! 128 bits, so every modulus up to `u64::MAX` is supported. Systems without
+//! a solution report `MathStatus::NoSolution`.
+
+use crate::MathStatus;
+use crate::bigint::BigInt;
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+
+/// Bézout coefficients of `a` and `b`: `a * x + b * y == gcd`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bezout {
+    pub gcd: u64,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// The congruence `x ≡ residue (mod modulus)`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Congruence {
+    pub residue: u64,
+    pub modulus: u64,
+}
+
+/// The integer solutions `(x + k * step_x, y + k * step_y)`, for every
+/// integer `k`, of a linear Diophantine equation `a * x + b * y == c`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiophantineSolution {
+    pub x: i64,
+    pub y: i64,
+    pub step_x: i64,
+    pub step_y: i64,
+}
+
+/// Binary (Stein's) gcd; `gcd(0, 0) == 0`.
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    if a == 0 || b == 0 {
+        return a | b;
+    }
+    let shift = (a | b).trailing_zeros();
+    a >>= a.trailing_zeros();
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            (a, b) = (b, a);
+        }
+        b -= a;
+        if b == 0 {
+            return a << shift;
+        }
+    }
+}
+
+/// Least common multiple; `lcm(a, 0) == 0`.
+pub(crate) fn lcm(a: u64, b: u64) -> MathResult<u64> {
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    (a / gcd(a, b))
+        .checked_mul(b)
+        .ok_or_else(|| MathError::overflow(format!("lcm({a}, {b}) does not fit in u64")))
+}
+
+/// The extended Euclidean algorithm, with a non-negative gcd. The
+/// coefficients satisfy `|x| <= |b| / gcd` and `|y| <= |a| / gcd`, so they
+/// always fit in `i64`.
+pub(crate) fn extended_gcd(a: i64, b: i64) -> Bezout {
+    let (mut old_r, mut r) = (a as i128, b as i128);
+    let (mut old_x, mut x) = (1i128, 0i128);
+    let (mut old_y, mut y) = (0i128, 1i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    let sign = if old_r < 0 { -1 } else { 1 };
+    Bezout {
+        gcd: (sign * old_r) as u64,
+        x: (sign * old_x) as i64,
+        y: (sign * old_y) as i64,
+    }
+}
+
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+pub(crate) fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Inverse of `a` modulo an arbitrary `m`, if `gcd(a, m) == 1`.
+pub(crate) fn inv_mod(a: u64, m: u64) -> Option<u64> {
+    let (mut old_r, mut r) = (a as i128 % m as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    (old_r == 1).then(|| old_s.rem_euclid(m as i128) as u64)
+}
+
+fn check_modulus(m: u64) -> MathResult<()> {
+    if m == 0 {
+        return Err(MathError::new(
+            MathStatus::DivisionByZero,
+            "modulus is zero",
+        ));
+    }
+    Ok(())
+}
+
+fn no_solution(message: String) -> MathError {
+    MathError::new(MathStatus::NoSolution, message)
+}
+
+/// The least `x >= 0` satisfying every congruence, and the lcm of the
+/// moduli, which need not be coprime. Each step merges one congruence into
+/// the combined one: with `x = r + M * t`, it solves
+/// `M * t ≡ r_i - r (mod m_i)`, which needs `gcd(M, m_i)` to divide the
+/// right-hand side.
+pub(crate) fn crt(congruences: &[Congruence]) -> MathResult<(BigInt, BigInt)> {
+    let (mut residue, mut modulus) = (BigInt::zero(), BigInt::one());
+    for (
+        i,
+        &Congruence {
+            residue: r,
+            modulus: m,
+        },
+    ) in congruences.iter().enumerate()
+    {
+        if m == 0 {
+            return Err(MathError::new(
+                MathStatus::DivisionByZero,
+                format!("congruence {i}: modulus is zero"),
+            ));
+        }
+        let r = r % m;
+        let current = residue.divrem_small(m).1;
+        let step = modulus.divrem_small(m).1;
+        let g = gcd(step, m);
+        let diff = if r >= current {
+            r - current
+        } else {
+            r + (m - current)
+        };
+        if diff % g != 0 {
+            return Err(no_solution(format!(
+                "congruence {i} (x ≡ {r} mod {m}) contradicts the ones before it"
+            )));
+        }
+        let reduced = m / g;
+        let inverse = inv_mod(step / g, reduced).expect("coprime once the gcd is divided out");
+        let mut shift = modulus.clone();
+        shift.mul_small_assign(mul_mod(diff / g, inverse, reduced));
+        residue = &residue + &shift;
+        modulus.mul_small_assign(reduced);
+    }
+    Ok((residue, modulus))
+}
+
+/// The solutions of `a * x ≡ b (mod m)`, as a single congruence modulo
+/// `m / gcd(a, m)`.
+pub(crate) fn linear_congruence(a: u64, b: u64, m: u64) -> MathResult<Congruence> {
+    check_modulus(m)?;
+    let g = gcd(a % m, m);
+    if !(b % m).is_multiple_of(g) {
+        return Err(no_solution(format!(
+            "{a} * x ≡ {b} (mod {m}) has no solution: gcd({a}, {m}) = {g} does not divide {b}"
+        )));
+    }
+    let modulus = m / g;
+    let inverse = inv_mod(a % m / g, modulus).expect("coprime once the gcd is divided out");
+    Ok(Congruence {
+        residue: mul_mod(b % m / g, inverse, modulus),
+        modulus,
+    })
+}
+
+/// The solutions of `a * x + b * y == c`, with the least `x >= 0` unless
+/// `b` is zero (then `y` is free and 0 is reported).
+pub(crate) fn linear_diophantine(a: i64, b: i64, c: i64) -> MathResult<DiophantineSolution> {
+    let equation = format!("{a} * x + {b} * y = {c}");
+    if a == 0 && b == 0 {
+        return Err(if c == 0 {
+            MathError::invalid_argument(format!("every pair solves {equation}"))
+        } else {
+            no_solution(format!("{equation} has no solution"))
+        });
+    }
+    let bezout = extended_gcd(a, b);
+    let (a, b, c, g) = (a as i128, b as i128, c as i128, bezout.gcd as i128);
+    if c % g != 0 {
+        return Err(no_solution(format!(
+            "{equation} has no solution: gcd = {g} does not divide {c}"
+        )));
+    }
+    let (step_x, step_y) = (b / g, -a / g);
+    let mut x = bezout.x as i128 * (c / g);
+    let y = if b == 0 {
+        0
+    } else {
+        x = x.rem_euclid(step_x.abs());
+        (c - a * x) / b
+    };
+    let fit = |v: i128| {
+        i64::try_from(v).map_err(|_| {
+            MathError::overflow(format!("the solution of {equation} does not fit in i64"))
+        })
+    };
+    Ok(DiophantineSolution {
+        x: fit(x)?,
+        y: fit(y)?,
+        step_x: fit(step_x)?,
+        step_y: fit(step_y)?,
+    })
+}
+
+/// Greatest common divisor of `a` and `b`; `gcd_u64(0, 0)` is 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn gcd_u64(a: u64, b: u64) -> u64 {
+    ffi::guard(0, || Ok(gcd(a, b)))
+}
+
+/// Store the least common multiple of `a` and `b` in `out`, or return
+/// `MathStatus::Overflow` if it does not fit in `u64`. The lcm of 0 and
+/// anything is 0.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lcm_u64_checked(a: u64, b: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", lcm(a, b)?) })
+}
+
+/// Compute `gcd(a, b)` together with coefficients `x` and `y` such that
+/// `a * x + b * y == gcd`.
+#[unsafe(no_mangle)]
+pub extern "C" fn extended_gcd_i64(a: i64, b: i64) -> Bezout {
+    ffi::guard(Bezout::default(), || Ok(extended_gcd(a, b)))
+}
+
+/// Store the inverse of `a` modulo `m` in `out`.
+///
+/// Returns `MathStatus::DivisionByZero` if `m` is zero and
+/// `MathStatus::InvalidArgument` if `a` and `m` are not coprime.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mod_inverse_checked(a: u64, m: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| {
+        check_modulus(m)?;
+        let inverse = inv_mod(a, m)
+            .ok_or_else(|| MathError::invalid_argument(format!("{a} has no inverse modulo {m}")))?;
+        unsafe { ffi::write_out(out, "out", inverse) }
+    })
+}
+
+/// Store `base^exponent mod m` in `out`.
+///
+/// Returns `MathStatus::DivisionByZero` if `m` is zero.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mod_pow_checked(
+    base: u64,
+    exponent: u64,
+    m: u64,
+    out: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        check_modulus(m)?;
+        unsafe { ffi::write_out(out, "out", pow_mod(base, exponent, m)) }
+    })
+}
+
+/// Solve the system `x ≡ residue (mod modulus)` of `len` congruences, whose
+/// moduli need not be coprime, and store the least non-negative solution and
+/// the lcm of the moduli in `out`. No congruences give `x ≡ 0 (mod 1)`.
+///
+/// Returns `MathStatus::DivisionByZero` if a modulus is zero,
+/// `MathStatus::NoSolution` if the congruences contradict each other and
+/// `MathStatus::Overflow` if the lcm does not fit in `u64`; `crt_bigint`
+/// has no such limit.
+///
+/// # Safety
+///
+/// `congruences` must be null (with `len == 0`) or valid for reading `len`
+/// values, and `out` must be null or valid for writing a single value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn crt_u64(
+    congruences: *const Congruence,
+    len: usize,
+    out: *mut Congruence,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let congruences = unsafe { ffi::slice_ref(congruences, len, "congruences") }?;
+        let (residue, modulus) = crt(congruences)?;
+        let (Some(residue), Some(modulus)) = (residue.to_u64(), modulus.to_u64()) else {
+            return Err(MathError::overflow(format!(
+                "the combined modulus {modulus} does not fit in u64"
+            )));
+        };
+        unsafe { ffi::write_out(out, "out", Congruence { residue, modulus }) }
+    })
+}
+
+/// Solve a system of congruences as `crt_u64` does, storing the solution
+/// and the lcm of the moduli as new handles in `out_residue` and
+/// `out_modulus`.
+///
+/// Returns `MathStatus::DivisionByZero` if a modulus is zero and
+/// `MathStatus::NoSolution` if the congruences contradict each other. Both
+/// handles must be released with `bigint_free`.
+///
+/// # Safety
+///
+/// `congruences` must be null (with `len == 0`) or valid for reading `len`
+/// values; each out-pointer must be null or valid for writing a single
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn crt_bigint(
+    congruences: *const Congruence,
+    len: usize,
+    out_residue: *mut *mut BigInt,
+    out_modulus: *mut *mut BigInt,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let congruences = unsafe { ffi::slice_ref(congruences, len, "congruences") }?;
+        if out_residue.is_null() || out_modulus.is_null() {
+            return Err(MathError::invalid_argument("an out-pointer is null"));
+        }
+        let (residue, modulus) = crt(congruences)?;
+        unsafe {
+            out_residue.write(ffi::into_handle(residue));
+            out_modulus.write(ffi::into_handle(modulus));
+        }
+        Ok(())
+    })
+}
+
+/// Solve `a * x ≡ b (mod m)`, storing the solutions as the single
+/// congruence `x ≡ residue (mod m / gcd(a, m))` in `out`.
+///
+/// Returns `MathStatus::DivisionByZero` if `m` is zero and
+/// `MathStatus::NoSolution` if `gcd(a, m)` does not divide `b`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn linear_congruence_u64(
+    a: u64,
+    b: u64,
+    m: u64,
+    out: *mut Congruence,
+) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", linear_congruence(a, b, m)?) })
+}
+
+/// Solve `a * x + b * y == c` in integers, storing a particular solution
+/// with the least `x >= 0` and the steps between solutions in `out`.
+///
+/// Returns `MathStatus::NoSolution` if `gcd(a, b)` does not divide `c`,
+/// `MathStatus::InvalidArgument` if `a`, `b` and `c` are all zero (every
+/// pair is a solution) and `MathStatus::Overflow` if the solution does not
+/// fit in `int64_t`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single value.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn linear_diophantine_i64(
+    a: i64,
+    b: i64,
+    c: i64,
+    out: *mut DiophantineSolution,
+) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", linear_diophantine(a, b, c)?) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn euclid(a: u64, b: u64) -> u64 {
+        if b == 0 { a } else { euclid(b, a % b) }
+    }
+
+    #[test]
+    fn test_gcd_and_lcm() {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        for _ in 0..1_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let (a, b) = (state >> (state % 64), state.rotate_left(17) >> (state % 61));
+            assert_eq!(gcd(a, b), euclid(a, b), "gcd({a}, {b})");
+            let (a, b, shift) = (a >> 20, b >> 20, state % 20);
+            assert_eq!(gcd(a << shift, b << shift), euclid(a, b) << shift);
+        }
+        assert_eq!(gcd_u64(0, 0), 0);
+        assert_eq!(gcd_u64(48, 180), 12);
+        assert_eq!(gcd_u64(u64::MAX, u64::MAX - 1), 1);
+        assert_eq!(gcd_u64(1 << 63, 1 << 40), 1 << 40);
+
+        let mut out = 0;
+        assert_eq!(unsafe { lcm_u64_checked(4, 6, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 12);
+        assert_eq!(unsafe { lcm_u64_checked(0, 6, &mut out) }, MathStatus::Ok);
+        assert_eq!(out, 0);
+        assert_eq!(
+            unsafe { lcm_u64_checked(1 << 32, 3 << 31, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 3 << 32);
+        assert_eq!(
+            unsafe { lcm_u64_checked(u64::MAX, u64::MAX - 1, &mut out) },
+            MathStatus::Overflow
+        );
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let cases = [
+            (240, 46),
+            (-240, 46),
+            (0, 0),
+            (0, -7),
+            (5, 0),
+            (i64::MIN, i64::MIN),
+            (i64::MIN, -1),
+            (i64::MIN, i64::MAX),
+            (i64::MAX, i64::MAX - 1),
+            (1, i64::MIN),
+        ];
+        for (a, b) in cases {
+            let bezout = extended_gcd_i64(a, b);
+            assert_eq!(
+                bezout.gcd as u128,
+                euclid(a.unsigned_abs(), b.unsigned_abs()) as u128
+            );
+            assert_eq!(
+                a as i128 * bezout.x as i128 + b as i128 * bezout.y as i128,
+                bezout.gcd as i128,
+                "{a}, {b}: {bezout:?}"
+            );
+        }
+        assert_eq!(
+            extended_gcd_i64(240, 46),
+            Bezout {
+                gcd: 2,
+                x: -9,
+                y: 47
+            }
+        );
+        assert_eq!(extended_gcd_i64(i64::MIN, 0).gcd, 1 << 63);
+    }
+
+    #[test]
+    fn test_mod_inverse_and_pow() {
+        let mut out = 0;
+        assert_eq!(
+            unsafe { mod_inverse_checked(3, 11, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 4);
+        assert_eq!(
+            unsafe { mod_inverse_checked(10, 1, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 0);
+        let m = u64::MAX;
+        assert_eq!(
+            unsafe { mod_inverse_checked(2, m, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(mul_mod(out, 2, m), 1);
+        assert_eq!(
+            unsafe { mod_inverse_checked(6, 9, &mut out) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { mod_inverse_checked(6, 0, &mut out) },
+            MathStatus::DivisionByZero
+        );
+
+        assert_eq!(
+            unsafe { mod_pow_checked(2, 64, u64::MAX, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 1);
+        let p = 18_446_744_073_709_551_557;
+        assert_eq!(
+            unsafe { mod_pow_checked(u64::MAX, p - 1, p, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 1);
+        assert_eq!(
+            unsafe { mod_pow_checked(0, 0, 1, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 0);
+        assert_eq!(
+            unsafe { mod_pow_checked(2, 3, 0, &mut out) },
+            MathStatus::DivisionByZero
+        );
+    }
+
+    fn solve(congruences: &[(u64, u64)]) -> Result<Congruence, MathStatus> {
+        let congruences: Vec<_> = congruences
+            .iter()
+            .map(|&(residue, modulus)| Congruence { residue, modulus })
+            .collect();
+        let mut out = Congruence::default();
+        match unsafe { crt_u64(congruences.as_ptr(), congruences.len(), &mut out) } {
+            MathStatus::Ok => Ok(out),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(
+            solve(&[]),
+            Ok(Congruence {
+                residue: 0,
+                modulus: 1
+            })
+        );
+        assert_eq!(
+            solve(&[(2, 3), (3, 5), (2, 7)]),
+            Ok(Congruence {
+                residue: 23,
+                modulus: 105
+            })
+        );
+        // Non-coprime moduli: consistent, then contradictory.
+        assert_eq!(
+            solve(&[(10, 12), (4, 18), (1, 1)]),
+            Ok(Congruence {
+                residue: 22,
+                modulus: 36
+            })
+        );
+        assert_eq!(solve(&[(1, 4), (2, 6)]), Err(MathStatus::NoSolution));
+        assert_eq!(
+            ffi::take_string(crate::error::math_last_error_message()),
+            "no solution: congruence 1 (x ≡ 2 mod 6) contradicts the ones before it"
+        );
+        assert_eq!(solve(&[(1, 4), (2, 0)]), Err(MathStatus::DivisionByZero));
+        let big = u64::MAX - 58; // The largest prime below 2^64.
+        assert_eq!(
+            solve(&[(big + 7, big), (7, big)]),
+            Ok(Congruence {
+                residue: 7,
+                modulus: big
+            })
+        );
+        assert_eq!(solve(&[(1, big), (1, 3)]), Err(MathStatus::Overflow));
+
+        // Every small system against a search for its least solution.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..500 {
+            let pairs: Vec<(u64, u64)> = (0..3)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    (state % 50, state % 12 + 1)
+                })
+                .collect();
+            let modulus = pairs.iter().try_fold(1, |l, &(_, m)| lcm(l, m)).unwrap();
+            let least = (0..modulus).find(|x| pairs.iter().all(|&(r, m)| x % m == r % m));
+            let expected = least
+                .map(|residue| Congruence { residue, modulus })
+                .ok_or(MathStatus::NoSolution);
+            assert_eq!(solve(&pairs), expected, "{pairs:?}");
+        }
+    }
+
+    #[test]
+    fn test_crt_bigint() {
+        let primes = [u64::MAX - 58, u64::MAX - 82, u64::MAX - 94];
+        let congruences: Vec<_> = primes
+            .iter()
+            .enumerate()
+            .map(|(i, &modulus)| Congruence {
+                residue: i as u64 + 1,
+                modulus,
+            })
+            .collect();
+        let (mut residue, mut modulus) = (std::ptr::null_mut(), std::ptr::null_mut());
+        let status = unsafe { crt_bigint(congruences.as_ptr(), 3, &mut residue, &mut modulus) };
+        assert_eq!(status, MathStatus::Ok);
+        let (residue, modulus) = unsafe { (Box::from_raw(residue), Box::from_raw(modulus)) };
+        assert_eq!(modulus.bit_len(), 192);
+        assert!(*residue < *modulus);
+        for c in &congruences {
+            assert_eq!(residue.divrem_small(c.modulus).1, c.residue);
+            assert_eq!(modulus.divrem_small(c.modulus).1, 0);
+        }
+        let status = unsafe {
+            crt_bigint(
+                congruences.as_ptr(),
+                3,
+                &mut std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_linear_congruence() {
+        let mut out = Congruence::default();
+        assert_eq!(
+            unsafe { linear_congruence_u64(6, 4, 10, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(
+            out,
+            Congruence {
+                residue: 4,
+                modulus: 5
+            }
+        );
+        assert_eq!(
+            unsafe { linear_congruence_u64(0, 10, 5, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(
+            out,
+            Congruence {
+                residue: 0,
+                modulus: 1
+            }
+        );
+        assert_eq!(
+            unsafe { linear_congruence_u64(6, 3, 10, &mut out) },
+            MathStatus::NoSolution
+        );
+        assert_eq!(
+            unsafe { linear_congruence_u64(6, 3, 0, &mut out) },
+            MathStatus::DivisionByZero
+        );
+        for m in 1..40u64 {
+            for a in 0..2 * m {
+                for b in 0..m {
+                    let solutions: Vec<u64> = (0..m).filter(|x| (a * x) % m == b).collect();
+                    match linear_congruence(a, b, m) {
+                        Ok(c) => {
+                            let expected: Vec<u64> =
+                                (c.residue..m).step_by(c.modulus as usize).collect();
+                            assert_eq!(solutions, expected, "{a} x = {b} mod {m}");
+                        }
+                        Err(e) => {
+                            assert_eq!(e.status(), MathStatus::NoSolution);
+                            assert!(solutions.is_empty(), "{a} x = {b} mod {m}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_linear_diophantine() {
+        let mut out = DiophantineSolution::default();
+        assert_eq!(
+            unsafe { linear_diophantine_i64(6, 10, 8, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(
+            out,
+            DiophantineSolution {
+                x: 3,
+                y: -1,
+                step_x: 5,
+                step_y: -3
+            }
+        );
+        assert_eq!(
+            unsafe { linear_diophantine_i64(6, 10, 7, &mut out) },
+            MathStatus::NoSolution
+        );
+        assert_eq!(
+            unsafe { linear_diophantine_i64(0, 0, 0, &mut out) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { linear_diophantine_i64(0, 0, 3, &mut out) },
+            MathStatus::NoSolution
+        );
+        // -a / gcd = 2^63 is not an i64.
+        assert_eq!(
+            unsafe { linear_diophantine_i64(i64::MIN, 1, 0, &mut out) },
+            MathStatus::Overflow
+        );
+        let cases = [
+            (4, 0, -12),
+            (0, -3, 9),
+            (i64::MAX, i64::MAX - 1, i64::MIN + 1),
+            (-240, 46, 2),
+            (7, -7, 0),
+        ];
+        for (a, b, c) in cases {
+            let s = linear_diophantine(a, b, c).unwrap();
+            for k in -1..=1i128 {
+                let x = s.x as i128 + k * s.step_x as i128;
+                let y = s.y as i128 + k * s.step_y as i128;
+                assert_eq!(
+                    a as i128 * x + b as i128 * y,
+                    c as i128,
+                    "{a}, {b}, {c}: {s:?}"
+                );
+            }
+            assert_eq!(euclid(s.step_x.unsigned_abs(), s.step_y.unsigned_abs()), 1);
+            if b != 0 {
+                assert!((0..s.step_x.abs()).contains(&s.x), "{s:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! Integer partition counts: p(n), partitions into distinct parts q(n), and
+//! partitions whose parts are bounded by `max_part`.
+//!
+//! Small values come from Euler's pentagonal number recurrence. Beyond
+//! `HRR_MIN`, p(n) is summed from the Hardy-Ramanujan-Rademacher series in
+//! fixed-point arithmetic, with each term carried at just enough precision
+//! for the final sum to round to the exact integer.
+
+use std::f64::consts::{LN_2, PI};
+
+use crate::MathStatus;
+use crate::bigint::{self, BigInt};
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+use crate::real::Fixed;
+
+/// From here on p(n) is taken from the Rademacher series instead of the
+/// O(n^1.5) recurrence.
+const HRR_MIN: u64 = 2_000;
+/// Largest n for which a table of all counts up to n is built.
+const TABLE_MAX: u64 = 1 << 24;
+/// Rademacher terms estimated to stay below 2^F64_MAX_BITS are summed in
+/// `f64`, whose rounding errors are then far below 1/2.
+const F64_MAX_BITS: f64 = 10.0;
+
+/// Generalised pentagonal numbers k(3k - 1)/2 for k = 1, -1, 2, -2, ... up
+/// to `limit`, with the sign of their term in Euler's recurrence
+/// p(m) = p(m - 1) + p(m - 2) - p(m - 5) - p(m - 7) + ...
+fn pentagonal(limit: u64) -> impl Iterator<Item = (u64, bool)> {
+    (1u64..)
+        .flat_map(|k| {
+            [
+                (k * (3 * k - 1) / 2, k % 2 == 1),
+                (k * (3 * k + 1) / 2, k % 2 == 1),
+            ]
+        })
+        .take_while(move |&(g, _)| g <= limit)
+}
+
+/// Coefficient of x^m in prod (1 - x^(2i)) = sum (-1)^k x^(k(3k - 1)).
+///
+/// Since prod (1 + x^i) = prod (1 - x^(2i)) / prod (1 - x^i), q(m) follows
+/// Euler's recurrence for p(m) with this coefficient added.
+fn distinct_correction(m: u64) -> i64 {
+    (0u64..)
+        .take_while(|&k| 3 * k * k - k <= m)
+        .find(|&k| m == 3 * k * k - k || m == 3 * k * k + k)
+        .map_or(0, |k| if k % 2 == 0 { 1 } else { -1 })
+}
+
+fn check_table(n: u64) -> MathResult<()> {
+    if n > TABLE_MAX {
+        return Err(MathError::overflow(format!(
+            "counting partitions of {n} needs a table above the supported {TABLE_MAX} entries"
+        )));
+    }
+    Ok(())
+}
+
+/// p(n) (or q(n) when `distinct`) from Euler's recurrence in `u64`. Both
+/// sequences are non-decreasing, so only the result itself can overflow.
+fn checked_pentagonal(n: u64, distinct: bool) -> MathResult<u64> {
+    let what = if distinct { "q" } else { "p" };
+    let mut table: Vec<u64> = vec![1];
+    for m in 1..=n {
+        let mut value = if distinct {
+            distinct_correction(m) as i128
+        } else {
+            0
+        };
+        for (g, positive) in pentagonal(m) {
+            let term = table[(m - g) as usize] as i128;
+            value += if positive { term } else { -term };
+        }
+        let value = u64::try_from(value)
+            .map_err(|_| MathError::overflow(format!("{what}({n}) does not fit in u64")))?;
+        table.push(value);
+    }
+    Ok(table[n as usize])
+}
+
+fn big_pentagonal(n: u64, distinct: bool) -> MathResult<BigInt> {
+    check_table(n)?;
+    let mut table = vec![BigInt::one()];
+    for m in 1..=n {
+        let mut value = BigInt::from(if distinct { distinct_correction(m) } else { 0 });
+        for (g, positive) in pentagonal(m) {
+            let term = &table[(m - g) as usize];
+            value = if positive {
+                &value + term
+            } else {
+                &value - term
+            };
+        }
+        table.push(value);
+    }
+    Ok(table.pop().expect("table has an entry for n"))
+}
+
+/// log2 of the asymptotic p(n) ~ e^(pi sqrt(2n/3)) / (4n sqrt(3)), an upper
+/// bound on its bit length for n >= 1.
+fn partition_bits(n: u64) -> f64 {
+    let n = n as f64;
+    PI * (2.0 * n / 3.0).sqrt() / LN_2 - (4.0 * n * 3f64.sqrt()).log2()
+}
+
+/// Number of Rademacher terms after which the remainder is below 1/4, from
+/// Rademacher's bound
+/// |R(n, N)| < 44 pi^2 / (225 sqrt 3) N^(-1/2)
+///           + pi sqrt(2) / 75 (N / (n - 1))^(1/2) sinh(pi sqrt(2n/3) / N).
+fn rademacher_terms(n: u64) -> u64 {
+    let x = n as f64;
+    let bound = |terms: f64| {
+        44.0 * PI * PI / (225.0 * 3f64.sqrt()) / terms.sqrt()
+            + PI * 2f64.sqrt() / 75.0
+                * (terms / (x - 1.0)).sqrt()
+                * (PI * (2.0 * x / 3.0).sqrt() / terms).sinh()
+    };
+    (1u64..)
+        .find(|&terms| bound(terms as f64) < 0.25)
+        .expect("the remainder bound tends to zero")
+}
+
+/// Values of l in 0..2k with l(3l + 1)/2 = -n (mod k), with the sign (-1)^l.
+fn selberg_indices(n: u64, k: u64) -> Vec<(u64, bool)> {
+    let target = (k - n % k) % k;
+    let mut g = 0u64;
+    let mut indices = Vec::new();
+    for l in 0..2 * k {
+        if g == target {
+            indices.push((l, l % 2 == 0));
+        }
+        // l(3l + 1)/2 grows by 3l + 2 from l to l + 1.
+        g = (g + (3 * l + 2) % k) % k;
+    }
+    indices
+}
+
+/// Reduce cos(pi num / den) to sign * cos(pi a / den) with a / den in
+/// [0, 1/2].
+fn reduce_angle(num: u64, den: u64) -> (u64, bool) {
+    let mut a = num % (2 * den);
+    if a > den {
+        a = 2 * den - a;
+    }
+    if 2 * a > den {
+        (den - a, true)
+    } else {
+        (a, false)
+    }
+}
+
+/// p(n) from the Hardy-Ramanujan-Rademacher formula, written as
+///
+/// p(n) = 4 / (24n - 1) sum_{k=1..N} S_k(n) U(C / k),
+///
+/// with C = pi sqrt(24n - 1) / 6, U(x) = cosh x - sinh x / x, and Selberg's
+/// form of the Kloosterman-type sum A_k(n) = sqrt(k/3) S_k(n) where
+/// S_k(n) = sum (-1)^l cos(pi (6l + 1) / (6k)) over l in 0..2k with
+/// l(3l + 1)/2 = -n (mod k).
+///
+/// Term k is below 2^b_k with b_k = C / (k ln 2) + log2(8k / (24n - 1)), so
+/// it is evaluated with b_k + `guard` bits and the sum stays within 1/4 of
+/// p(n) after truncation.
+fn rademacher(n: u64) -> BigInt {
+    let terms = rademacher_terms(n);
+    let guard = 32 + terms.ilog2() as usize;
+    let m = 24.0 * n as f64 - 1.0;
+    let c = PI * m.sqrt() / 6.0;
+    let term_bits = |k: u64| c / (k as f64 * LN_2) + (8.0 * k as f64 / m).log2();
+
+    let mut m_big = BigInt::from(n);
+    m_big.mul_small_assign(24);
+    let m_big = &m_big - &BigInt::one();
+    let top = term_bits(1).max(0.0).ceil() as usize + guard;
+    let pi = Fixed::pi(top);
+    let root = Fixed::from_int(&m_big, top).sqrt();
+
+    let mut sum = Fixed::from_u64(0, guard);
+    let mut small = 0.0f64;
+    for k in 1..=terms {
+        let indices = selberg_indices(n, k);
+        if indices.is_empty() {
+            continue;
+        }
+        let bits = term_bits(k);
+        if bits < F64_MAX_BITS {
+            let x = c / k as f64;
+            let u = x.cosh() - x.sinh() / x;
+            let s: f64 = indices
+                .iter()
+                .map(|&(l, positive)| {
+                    let cos = (PI * (6 * l + 1) as f64 / (6 * k) as f64).cos();
+                    if positive { cos } else { -cos }
+                })
+                .sum();
+            small += 4.0 * s * u / m;
+            continue;
+        }
+
+        let prec = bits.ceil() as usize + guard;
+        let pi = pi.with_prec(prec);
+        let x = (&pi * &root.with_prec(prec)).div_u64(6 * k);
+        let e = x.exp();
+        let e_inv = &Fixed::one(prec) / &e;
+        let cosh = (&e + &e_inv).div_u64(2);
+        let sinh = (&e - &e_inv).div_u64(2);
+        let u = &cosh - &(&sinh / &x);
+        let mut s = Fixed::from_u64(0, prec);
+        for (l, positive) in indices {
+            let (a, negate) = reduce_angle(6 * l + 1, 6 * k);
+            let cos = pi.mul_u64(a).div_u64(6 * k).cos();
+            s = if positive != negate {
+                &s + &cos
+            } else {
+                &s - &cos
+            };
+        }
+        let term = &(&s * &u).mul_u64(4) / &Fixed::from_int(&m_big, prec);
+        sum = &sum + &term.with_prec(guard);
+    }
+    let sum = &sum + &Fixed::from_f64(small, guard);
+    (&sum + &Fixed::one(guard).div_u64(2)).floor()
+}
+
+fn big_partitions(n: u64) -> MathResult<BigInt> {
+    if n < HRR_MIN {
+        return big_pentagonal(n, false);
+    }
+    bigint::ensure_fits(partition_bits(n))?;
+    Ok(rademacher(n))
+}
+
+fn big_distinct_partitions(n: u64) -> MathResult<BigInt> {
+    // q(n) ~ e^(pi sqrt(n/3)) / (4 3^(1/4) n^(3/4)) < e^(pi sqrt(n/3)).
+    bigint::ensure_fits(PI * (n as f64 / 3.0).sqrt() / LN_2)?;
+    big_pentagonal(n, true)
+}
+
+/// Partitions of n into parts of size at most `max_part` (equivalently, into
+/// at most `max_part` parts), by adding the allowed part sizes one at a time
+/// to a table of counts. No intermediate entry exceeds the result, so only
+/// the result itself can overflow.
+fn bounded_table<T: Clone>(
+    n: u64,
+    max_part: u64,
+    zero: T,
+    one: T,
+    add: impl Fn(&T, &T) -> MathResult<T>,
+) -> MathResult<T> {
+    check_table(n)?;
+    let n = n as usize;
+    let mut table = vec![zero; n + 1];
+    table[0] = one;
+    for part in 1..=max_part as usize {
+        for s in part..=n {
+            table[s] = add(&table[s], &table[s - part])?;
+        }
+    }
+    Ok(table.pop().expect("table has an entry for n"))
+}
+
+fn checked_bounded_partitions(n: u64, max_part: u64) -> MathResult<u64> {
+    match max_part {
+        _ if max_part >= n => checked_pentagonal(n, false),
+        0 => Ok(0),
+        1 => Ok(1),
+        2 => Ok(n / 2 + 1),
+        _ => bounded_table(n, max_part, 0u64, 1u64, |a, b| {
+            a.checked_add(*b).ok_or_else(|| {
+                MathError::overflow(format!(
+                    "partitions of {n} into parts of at most {max_part} do not fit in u64"
+                ))
+            })
+        }),
+    }
+}
+
+fn big_bounded_partitions(n: u64, max_part: u64) -> MathResult<BigInt> {
+    match max_part {
+        _ if max_part >= n => big_partitions(n),
+        0..=2 => Ok(BigInt::from(checked_bounded_partitions(n, max_part)?)),
+        _ => {
+            bigint::ensure_fits(partition_bits(n))?;
+            bounded_table(n, max_part, BigInt::zero(), BigInt::one(), |a, b| Ok(a + b))
+        }
+    }
+}
+
+/// Calculate the number of partitions p(n) of n into positive parts into
+/// `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn partition_count_checked(n: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_pentagonal(n, false)?) })
+}
+
+/// Calculate p(n) exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn partition_count_big(n: u64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_partitions(n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the number of partitions q(n) of n into distinct parts into
+/// `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn distinct_partition_count_checked(n: u64, out: *mut u64) -> MathStatus {
+    ffi::guard_status(|| unsafe { ffi::write_out(out, "out", checked_pentagonal(n, true)?) })
+}
+
+/// Calculate q(n) exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn distinct_partition_count_big(n: u64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_distinct_partitions(n).map(ffi::into_handle)
+    })
+}
+
+/// Calculate the number of partitions of n into parts no larger than
+/// `max_part` into `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bounded_partition_count_checked(
+    n: u64,
+    max_part: u64,
+    out: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| unsafe {
+        ffi::write_out(out, "out", checked_bounded_partitions(n, max_part)?)
+    })
+}
+
+/// Calculate the number of partitions of n into parts no larger than
+/// `max_part` exactly as a big integer, or return null on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn bounded_partition_count_big(n: u64, max_part: u64) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        big_bounded_partitions(n, max_part).map(ffi::into_handle)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partitions() {
+        let small = [1u64, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42];
+        for (n, &p) in small.iter().enumerate() {
+            assert_eq!(checked_pentagonal(n as u64, false).unwrap(), p);
+        }
+        assert_eq!(checked_pentagonal(100, false).unwrap(), 190_569_292);
+        assert_eq!(
+            checked_pentagonal(416, false).unwrap(),
+            17_873_792_969_689_876_004
+        );
+        assert_eq!(
+            checked_pentagonal(417, false).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        assert_eq!(
+            big_partitions(1000).unwrap().to_string(),
+            "24061467864032622473692149727991"
+        );
+    }
+
+    #[test]
+    fn test_rademacher_matches_recurrence() {
+        for n in [HRR_MIN, HRR_MIN + 1, 2_345, 3_001, 4_096] {
+            assert_eq!(rademacher(n), big_pentagonal(n, false).unwrap(), "p({n})");
+        }
+        // Far from the table: p(10^5) has 347 digits.
+        let p = big_partitions(100_000).unwrap().to_string();
+        assert_eq!(p.len(), 347);
+        assert!(
+            p.starts_with("2749351056977569651267751632098635268817"),
+            "{p}"
+        );
+        assert!(p.ends_with("80158600569421098519"), "{p}");
+    }
+
+    #[test]
+    fn test_distinct_partitions() {
+        let small = [1u64, 1, 1, 2, 2, 3, 4, 5, 6, 8, 10, 12, 15];
+        for (n, &q) in small.iter().enumerate() {
+            assert_eq!(checked_pentagonal(n as u64, true).unwrap(), q);
+        }
+        assert_eq!(checked_pentagonal(100, true).unwrap(), 444_793);
+        assert_eq!(checked_pentagonal(200, true).unwrap(), 487_067_746);
+        for n in 0..=60 {
+            let odd_parts = bounded_odd_parts(n);
+            assert_eq!(checked_pentagonal(n, true).unwrap(), odd_parts, "q({n})");
+        }
+        assert_eq!(
+            big_distinct_partitions(200).unwrap(),
+            BigInt::from(487_067_746u64)
+        );
+    }
+
+    /// Partitions into odd parts, which Euler showed equinumerous with
+    /// partitions into distinct parts.
+    fn bounded_odd_parts(n: u64) -> u64 {
+        let mut table = vec![0u64; n as usize + 1];
+        table[0] = 1;
+        for part in (1..=n as usize).step_by(2) {
+            for s in part..=n as usize {
+                table[s] += table[s - part];
+            }
+        }
+        table[n as usize]
+    }
+
+    #[test]
+    fn test_bounded_partitions() {
+        assert_eq!(checked_bounded_partitions(0, 0).unwrap(), 1);
+        assert_eq!(checked_bounded_partitions(5, 0).unwrap(), 0);
+        assert_eq!(checked_bounded_partitions(u64::MAX, 1).unwrap(), 1);
+        assert_eq!(
+            checked_bounded_partitions(u64::MAX, 2).unwrap(),
+            u64::MAX / 2 + 1
+        );
+        assert_eq!(checked_bounded_partitions(10, 3).unwrap(), 14);
+        assert_eq!(checked_bounded_partitions(100, 10).unwrap(), 6_292_069);
+        for n in 0..=30 {
+            let p = checked_pentagonal(n, false).unwrap();
+            assert_eq!(checked_bounded_partitions(n, n).unwrap(), p);
+            assert_eq!(checked_bounded_partitions(n, n + 7).unwrap(), p);
+            // Parts of at most n - 1 exclude only [n] itself.
+            if n > 0 {
+                assert_eq!(checked_bounded_partitions(n, n - 1).unwrap(), p - 1);
+            }
+        }
+        assert_eq!(
+            checked_bounded_partitions(1000, 100).unwrap_err().status(),
+            MathStatus::Overflow
+        );
+        let big = big_bounded_partitions(1000, 999).unwrap();
+        assert_eq!(&big + &BigInt::one(), big_partitions(1000).unwrap());
+        assert_eq!(
+            big_bounded_partitions(TABLE_MAX + 1, 3)
+                .unwrap_err()
+                .status(),
+            MathStatus::Overflow
+        );
+    }
+
+    #[test]
+    fn test_exports() {
+        let mut out = 0u64;
+        assert_eq!(
+            unsafe { partition_count_checked(50, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 204_226);
+        assert_eq!(
+            unsafe { distinct_partition_count_checked(50, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 3_658);
+        assert_eq!(
+            unsafe { bounded_partition_count_checked(50, 5, &mut out) },
+            MathStatus::Ok
+        );
+        assert_eq!(out, 3_765);
+        assert_eq!(
+            unsafe { partition_count_checked(10, std::ptr::null_mut()) },
+            MathStatus::InvalidArgument
+        );
+
+        let handle = partition_count_big(200);
+        assert_eq!(unsafe { &*handle }.to_string(), "3972999029388");
+        unsafe { bigint::bigint_free(handle) };
+        assert!(partition_count_big(u64::MAX).is_null());
+    }
+}
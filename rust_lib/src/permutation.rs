@@ -0,0 +1,550 @@
+//! Lexicographic ranking of permutations and the factoradic number system.
+//!
+//! A permutation of `0..len` maps to its Lehmer code: digit `i` counts the
+//! later elements smaller than `perm[i]`, so it lies in `0..len - i`. Read as
+//! a factoradic number (digit `i` weighted by `(len - 1 - i)!`), the Lehmer
+//! code is exactly the lexicographic rank. Both directions run in
+//! O(len log len) with a Fenwick tree; the big-integer variants split the
+//! mixed-radix conversion recursively so they stay fast for long inputs.
+
+use crate::MathStatus;
+use crate::bigint::{self, BigInt};
+use crate::error::{MathError, MathResult};
+use crate::ffi;
+
+/// Binary indexed tree over `0..len` counting the elements still unused.
+struct Fenwick {
+    tree: Vec<u32>,
+}
+
+impl Fenwick {
+    /// Every element present.
+    fn full(len: usize) -> Fenwick {
+        // Node i (1-based) covers the lowest-set-bit-sized range ending at i.
+        let tree = (0..=len).map(|i| (i & i.wrapping_neg()) as u32).collect();
+        Fenwick { tree }
+    }
+
+    fn remove(&mut self, index: usize) {
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] -= 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Number of present elements below `index`.
+    fn count_below(&self, index: usize) -> u32 {
+        let mut i = index;
+        let mut count = 0;
+        while i > 0 {
+            count += self.tree[i];
+            i &= i - 1;
+        }
+        count
+    }
+
+    /// The present element with exactly `k` present elements below it.
+    fn select(&self, mut k: u32) -> usize {
+        let mut pos = 0;
+        let mut step = (self.tree.len() - 1).checked_ilog2().map_or(0, |b| 1 << b);
+        while step > 0 {
+            if pos + step < self.tree.len() && self.tree[pos + step] <= k {
+                pos += step;
+                k -= self.tree[pos];
+            }
+            step >>= 1;
+        }
+        pos
+    }
+}
+
+fn lehmer_code(perm: &[u32]) -> MathResult<Vec<u32>> {
+    let len = perm.len();
+    let mut seen = vec![false; len];
+    for &x in perm {
+        if x as usize >= len || std::mem::replace(&mut seen[x as usize], true) {
+            return Err(MathError::invalid_argument(format!(
+                "not a permutation of 0..{len}"
+            )));
+        }
+    }
+    let mut unused = Fenwick::full(len);
+    Ok(perm
+        .iter()
+        .map(|&x| {
+            let digit = unused.count_below(x as usize);
+            unused.remove(x as usize);
+            digit
+        })
+        .collect())
+}
+
+fn check_digits(code: &[u32]) -> MathResult<()> {
+    let len = code.len();
+    match code
+        .iter()
+        .enumerate()
+        .find(|&(i, &d)| d as usize >= len - i)
+    {
+        Some((i, &d)) => Err(MathError::invalid_argument(format!(
+            "digit {i} is {d} but must be below {}",
+            len - i
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn from_lehmer_code(code: &[u32]) -> MathResult<Vec<u32>> {
+    check_digits(code)?;
+    let mut unused = Fenwick::full(code.len());
+    Ok(code
+        .iter()
+        .map(|&digit| {
+            let x = unused.select(digit);
+            unused.remove(x);
+            x as u32
+        })
+        .collect())
+}
+
+/// sum_i digits[i] (len - 1 - i)!, evaluated by Horner's rule. Every
+/// intermediate is at most the result, so overflow means it does not fit.
+fn factoradic_value(digits: &[u32]) -> MathResult<u64> {
+    check_digits(digits)?;
+    let len = digits.len() as u64;
+    digits
+        .iter()
+        .enumerate()
+        .try_fold(0u64, |acc, (i, &d)| {
+            acc.checked_mul(len - i as u64)?.checked_add(d as u64)
+        })
+        .ok_or_else(|| MathError::overflow("rank does not fit in u64"))
+}
+
+/// Digits of a `u64` in a mixed radix whose last `FACTORADIC_U64_DIGITS`
+/// radices are 21, 20, ..., 1; 21! exceeds `u64::MAX`, so any digits before
+/// those are zero.
+const FACTORADIC_U64_DIGITS: usize = 21;
+
+/// Write `value` as `digits.len()` factoradic digits, leaving `digits`
+/// untouched if `value` is not below `digits.len()!`.
+fn factoradic_digits(mut value: u64, digits: &mut [u32]) -> MathResult<()> {
+    let len = digits.len();
+    let tail = len.min(FACTORADIC_U64_DIGITS);
+    let mut low = [0u32; FACTORADIC_U64_DIGITS];
+    for (radix, digit) in (1..=tail as u64).zip(low[..tail].iter_mut().rev()) {
+        *digit = (value % radix) as u32;
+        value /= radix;
+    }
+    if value != 0 {
+        return Err(MathError::invalid_argument(format!(
+            "value is not below {len}!"
+        )));
+    }
+    let (high, rest) = digits.split_at_mut(len - tail);
+    high.fill(0);
+    rest.copy_from_slice(&low[..tail]);
+    Ok(())
+}
+
+/// Below this many digits the mixed-radix conversions work digit by digit.
+const SPLIT_MIN: usize = 32;
+
+/// (value, weight) of `digits[lo..hi]`, where digit `i` has radix `len - i`
+/// and `weight` is the product of those radices.
+fn big_factoradic_value(digits: &[u32], lo: usize, hi: usize) -> (BigInt, BigInt) {
+    let len = digits.len();
+    if hi - lo <= SPLIT_MIN {
+        let mut value = BigInt::zero();
+        for (i, &digit) in digits.iter().enumerate().take(hi).skip(lo) {
+            value.mul_small_assign((len - i) as u64);
+            value = &value + &BigInt::from(digit as u64);
+        }
+        let weight = bigint::product_of(hi - lo, &|i| (len - lo - i) as u64, 1);
+        return (value, weight);
+    }
+    let mid = lo + (hi - lo) / 2;
+    let (high, high_weight) = big_factoradic_value(digits, lo, mid);
+    let (low, low_weight) = big_factoradic_value(digits, mid, hi);
+    (&(&high * &low_weight) + &low, &high_weight * &low_weight)
+}
+
+/// Inverse of [`big_factoradic_value`] for `value < weight(lo..hi)`.
+fn big_factoradic_digits(value: BigInt, lo: usize, hi: usize, digits: &mut [u32]) {
+    let len = digits.len();
+    if hi - lo <= SPLIT_MIN {
+        let mut value = value;
+        for i in (lo..hi).rev() {
+            let (q, r) = value.divrem_small((len - i) as u64);
+            digits[i] = r as u32;
+            value = q;
+        }
+        return;
+    }
+    let mid = lo + (hi - lo) / 2;
+    let low_weight = bigint::product_of(hi - mid, &|i| (len - mid - i) as u64, 1);
+    let (high, low) = value
+        .divrem(&low_weight)
+        .expect("radix products are non-zero");
+    big_factoradic_digits(high, lo, mid, digits);
+    big_factoradic_digits(low, mid, hi, digits);
+}
+
+fn big_rank(perm: &[u32]) -> MathResult<BigInt> {
+    let code = lehmer_code(perm)?;
+    Ok(big_factoradic_value(&code, 0, code.len()).0)
+}
+
+fn big_unrank(rank: &BigInt, len: usize) -> MathResult<Vec<u32>> {
+    let bound = bigint::product_of(len, &|i| (len - i) as u64, 1);
+    if rank.is_negative() || *rank >= bound {
+        return Err(MathError::invalid_argument(format!(
+            "rank is not in 0..{len}!"
+        )));
+    }
+    let mut code = vec![0u32; len];
+    big_factoradic_digits(rank.clone(), 0, len, &mut code);
+    from_lehmer_code(&code)
+}
+
+/// Copy `values` into a caller buffer of exactly `len` elements.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing `len` values.
+unsafe fn write_array(out: *mut u32, len: usize, name: &str, values: &[u32]) -> MathResult<()> {
+    unsafe { ffi::slice_mut(out, len, name) }?.copy_from_slice(values);
+    Ok(())
+}
+
+/// Store the lexicographic rank of `perm`, a permutation of `0..len`, in
+/// `out`; the identity has rank 0.
+///
+/// Returns `MathStatus::InvalidArgument` if `perm` is not a permutation and
+/// `MathStatus::Overflow` if the rank exceeds `u64` (possible from
+/// `len = 21` on; see `permutation_rank_big`).
+///
+/// # Safety
+///
+/// `perm` must be null (with `len == 0`) or valid for reading `len` values,
+/// and `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_rank(
+    perm: *const u32,
+    len: usize,
+    out: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let perm = unsafe { ffi::slice_ref(perm, len, "perm") }?;
+        let rank = factoradic_value(&lehmer_code(perm)?)?;
+        unsafe { ffi::write_out(out, "out", rank) }
+    })
+}
+
+/// Write the permutation of `0..len` with lexicographic rank `rank` to
+/// `perm_out`.
+///
+/// Returns `MathStatus::InvalidArgument` unless `rank < len!`.
+///
+/// # Safety
+///
+/// `perm_out` must be null (with `len == 0`) or valid for writing `len`
+/// values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_unrank(
+    rank: u64,
+    perm_out: *mut u32,
+    len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let out = unsafe { ffi::slice_mut(perm_out, len, "perm_out") }?;
+        factoradic_digits(rank, out)?;
+        let perm = from_lehmer_code(out)?;
+        out.copy_from_slice(&perm);
+        Ok(())
+    })
+}
+
+/// Calculate the lexicographic rank of `perm` as a big integer, or return
+/// null on failure.
+///
+/// # Safety
+///
+/// `perm` must be null (with `len == 0`) or valid for reading `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_rank_big(perm: *const u32, len: usize) -> *mut BigInt {
+    ffi::guard(std::ptr::null_mut(), || {
+        let perm = unsafe { ffi::slice_ref(perm, len, "perm") }?;
+        big_rank(perm).map(ffi::into_handle)
+    })
+}
+
+/// Write the permutation of `0..len` whose rank is the big integer `rank` to
+/// `perm_out`.
+///
+/// Returns `MathStatus::InvalidArgument` unless `0 <= rank < len!`.
+///
+/// # Safety
+///
+/// `rank` must be null or a live handle, and `perm_out` must be null (with
+/// `len == 0`) or valid for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_unrank_big(
+    rank: *const BigInt,
+    perm_out: *mut u32,
+    len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let rank = unsafe { ffi::handle_ref(rank, "rank") }?;
+        let out = unsafe { ffi::slice_mut(perm_out, len, "perm_out") }?;
+        out.copy_from_slice(&big_unrank(rank, len)?);
+        Ok(())
+    })
+}
+
+/// Write the Lehmer code of `perm` (for each position, the number of later
+/// elements that are smaller) to `code_out`.
+///
+/// # Safety
+///
+/// `perm` must be null (with `len == 0`) or valid for reading `len` values,
+/// and `code_out` likewise for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_to_lehmer(
+    perm: *const u32,
+    code_out: *mut u32,
+    len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let perm = unsafe { ffi::slice_ref(perm, len, "perm") }?;
+        let code = lehmer_code(perm)?;
+        unsafe { write_array(code_out, len, "code_out", &code) }
+    })
+}
+
+/// Write the permutation with Lehmer code `code` to `perm_out`.
+///
+/// Returns `MathStatus::InvalidArgument` unless `code[i] < len - i` for
+/// every `i`.
+///
+/// # Safety
+///
+/// `code` must be null (with `len == 0`) or valid for reading `len` values,
+/// and `perm_out` likewise for writing `len` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn permutation_from_lehmer(
+    code: *const u32,
+    perm_out: *mut u32,
+    len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let code = unsafe { ffi::slice_ref(code, len, "code") }?;
+        let perm = from_lehmer_code(code)?;
+        unsafe { write_array(perm_out, len, "perm_out", &perm) }
+    })
+}
+
+/// Write `value` as `len` factoradic digits to `digits_out`, most significant
+/// first: `value = sum_i digits[i] * (len - 1 - i)!` with
+/// `digits[i] < len - i`.
+///
+/// Returns `MathStatus::InvalidArgument` unless `value < len!`.
+///
+/// # Safety
+///
+/// `digits_out` must be null (with `len == 0`) or valid for writing `len`
+/// values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factoradic_encode(
+    value: u64,
+    digits_out: *mut u32,
+    len: usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        factoradic_digits(value, unsafe {
+            ffi::slice_mut(digits_out, len, "digits_out")
+        }?)
+    })
+}
+
+/// Store the value of the `len` factoradic digits in `digits` (see
+/// `factoradic_encode`) in `out`.
+///
+/// # Safety
+///
+/// `digits` must be null (with `len == 0`) or valid for reading `len`
+/// values, and `out` must be null or valid for writing a single `u64`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn factoradic_decode(
+    digits: *const u32,
+    len: usize,
+    out: *mut u64,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let digits = unsafe { ffi::slice_ref(digits, len, "digits") }?;
+        unsafe { ffi::write_out(out, "out", factoradic_value(digits)?) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::mag::tests::XorShift;
+
+    /// All permutations of 0..len in lexicographic order.
+    fn all_permutations(len: u32) -> Vec<Vec<u32>> {
+        if len == 0 {
+            return vec![vec![]];
+        }
+        let mut result = Vec::new();
+        for first in 0..len {
+            for rest in all_permutations(len - 1) {
+                let mut perm = vec![first];
+                perm.extend(rest.into_iter().map(|x| x + (x >= first) as u32));
+                result.push(perm);
+            }
+        }
+        result
+    }
+
+    fn shuffled(len: usize, rng: &mut XorShift) -> Vec<u32> {
+        let mut perm: Vec<u32> = (0..len as u32).collect();
+        for i in (1..len).rev() {
+            perm.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+        }
+        perm
+    }
+
+    fn digits(value: u64, len: usize) -> MathResult<Vec<u32>> {
+        let mut digits = vec![0; len];
+        factoradic_digits(value, &mut digits)?;
+        Ok(digits)
+    }
+
+    #[test]
+    fn test_rank_matches_lexicographic_order() {
+        for len in 0..=6 {
+            for (rank, perm) in all_permutations(len).into_iter().enumerate() {
+                let code = lehmer_code(&perm).unwrap();
+                assert_eq!(factoradic_value(&code).unwrap(), rank as u64);
+                assert_eq!(digits(rank as u64, len as usize).unwrap(), code);
+                assert_eq!(from_lehmer_code(&code).unwrap(), perm);
+                assert_eq!(big_rank(&perm).unwrap(), BigInt::from(rank as u64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(lehmer_code(&[0, 2]).is_err());
+        assert!(lehmer_code(&[1, 1]).is_err());
+        assert!(from_lehmer_code(&[0, 1]).is_err());
+        assert!(digits(6, 3).is_err());
+        assert_eq!(digits(5, 3).unwrap(), [2, 1, 0]);
+        assert!(digits(2_432_902_008_176_640_000, 20).is_err());
+        let long = digits(u64::MAX, 1000).unwrap();
+        assert!(long[..979].iter().all(|&d| d == 0));
+        assert_eq!(factoradic_value(&long).unwrap(), u64::MAX);
+        assert!(big_unrank(&BigInt::from(-1i64), 3).is_err());
+        assert!(big_unrank(&BigInt::from(6u64), 3).is_err());
+    }
+
+    #[test]
+    fn test_u64_limits() {
+        // The last permutation of 20 elements has rank 20! - 1.
+        let reversed: Vec<u32> = (0..20).rev().collect();
+        let code = lehmer_code(&reversed).unwrap();
+        assert_eq!(factoradic_value(&code).unwrap(), 2_432_902_008_176_639_999);
+        // Long permutations still rank in u64 when close to the identity.
+        let mut perm: Vec<u32> = (0..100).collect();
+        perm.swap(98, 99);
+        assert_eq!(factoradic_value(&lehmer_code(&perm).unwrap()).unwrap(), 1);
+        perm.swap(0, 99);
+        assert_eq!(
+            factoradic_value(&lehmer_code(&perm).unwrap())
+                .unwrap_err()
+                .status(),
+            MathStatus::Overflow
+        );
+    }
+
+    #[test]
+    fn test_big_round_trip() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        for len in [1, 21, SPLIT_MIN, SPLIT_MIN + 1, 200, 3000] {
+            let perm = shuffled(len, &mut rng);
+            let rank = big_rank(&perm).unwrap();
+            assert_eq!(big_unrank(&rank, len).unwrap(), perm, "len {len}");
+        }
+        // rank = n! - 1 for the reversed permutation.
+        let reversed: Vec<u32> = (0..500).rev().collect();
+        let expected = &bigint::product_of(500, &|i| i as u64 + 1, 1) - &BigInt::one();
+        assert_eq!(big_rank(&reversed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_exports() {
+        let perm = [2u32, 0, 1];
+        let mut rank = 0u64;
+        assert_eq!(
+            unsafe { permutation_rank(perm.as_ptr(), 3, &mut rank) },
+            MathStatus::Ok
+        );
+        assert_eq!(rank, 4);
+        let mut back = [0u32; 3];
+        assert_eq!(
+            unsafe { permutation_unrank(4, back.as_mut_ptr(), 3) },
+            MathStatus::Ok
+        );
+        assert_eq!(back, perm);
+        assert_eq!(
+            unsafe { permutation_unrank(6, back.as_mut_ptr(), 3) },
+            MathStatus::InvalidArgument
+        );
+
+        let mut code = [0u32; 3];
+        assert_eq!(
+            unsafe { permutation_to_lehmer(perm.as_ptr(), code.as_mut_ptr(), 3) },
+            MathStatus::Ok
+        );
+        assert_eq!(code, [2, 0, 0]);
+        assert_eq!(
+            unsafe { permutation_from_lehmer(code.as_ptr(), back.as_mut_ptr(), 3) },
+            MathStatus::Ok
+        );
+        assert_eq!(back, perm);
+
+        let mut digits = [0u32; 4];
+        assert_eq!(
+            unsafe { factoradic_encode(23, digits.as_mut_ptr(), 4) },
+            MathStatus::Ok
+        );
+        assert_eq!(digits, [3, 2, 1, 0]);
+        let mut value = 0u64;
+        assert_eq!(
+            unsafe { factoradic_decode(digits.as_ptr(), 4, &mut value) },
+            MathStatus::Ok
+        );
+        assert_eq!(value, 23);
+
+        // Null outputs are rejected before any work proportional to `len`.
+        assert_eq!(
+            unsafe { factoradic_encode(1, std::ptr::null_mut(), usize::MAX) },
+            MathStatus::InvalidArgument
+        );
+        assert_eq!(
+            unsafe { permutation_unrank(1, std::ptr::null_mut(), usize::MAX) },
+            MathStatus::InvalidArgument
+        );
+
+        let big = unsafe { permutation_rank_big(perm.as_ptr(), 3) };
+        assert_eq!(unsafe { &*big }, &BigInt::from(4u64));
+        assert_eq!(
+            unsafe { permutation_unrank_big(big, back.as_mut_ptr(), 3) },
+            MathStatus::Ok
+        );
+        assert_eq!(back, perm);
+        unsafe { bigint::bigint_free(big) };
+    }
+}
@@ -0,0 +1,522 @@
+//! Primality tests and prime sieves, shared by the number-theoretic
+//! algorithms and exported to C.
+//!
+//! `is_prime_u64` runs a Miller-Rabin test with a base set known to be exact
+//! for the size of its input, and `is_prime_bpsw` the Baillie-PSW test (a
+//! strong base-2 Miller-Rabin test followed by a strong Lucas test), which
+//! has no known counterexample and none below 2^64. Primes in a range are
+//! produced by a segmented sieve of Eratosthenes, either into a caller
+//! buffer (`primes_in_range`) or in batches from a [`PrimeIter`] handle, so
+//! memory stays bounded by the segment size and the base primes up to the
+//! square root of the range.
+
+use crate::MathStatus;
+use crate::error::MathError;
+use crate::ffi;
+use crate::number_theory::{mul_mod, pow_mod};
+
+/// Primes tried by trial division before any probable-prime test.
+pub(crate) const SMALL_PRIMES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+/// Numbers per sieve segment.
+const SEGMENT: u64 = 1 << 18;
+/// Largest number that is sieved; above it the base primes would take too
+/// much memory, and candidates are tested one by one instead.
+const SIEVE_MAX: u64 = 1 << 50;
+
+/// All primes `<= limit` in increasing order, from a bit-packed sieve over
+/// odd numbers (`limit / 16` bytes of scratch space).
+pub(crate) fn primes_up_to(limit: u64) -> Vec<u64> {
+    if limit < 2 {
+        return Vec::new();
+    }
+    // Bit `i` stands for the odd number `2 * i + 1`.
+    let len = (limit.div_ceil(2)) as usize;
+    let mut composite = vec![0u64; len.div_ceil(64)];
+    let mut i = 1usize;
+    while (2 * i + 1) * (2 * i + 1) <= limit as usize {
+        if composite[i / 64] & (1 << (i % 64)) == 0 {
+            let p = 2 * i + 1;
+            let mut j = p * p / 2;
+            while j < len {
+                composite[j / 64] |= 1 << (j % 64);
+                j += p;
+            }
+        }
+        i += 1;
+    }
+
+    let mut primes = vec![2];
+    primes.extend(
+        (1..len)
+            .filter(|&i| composite[i / 64] & (1 << (i % 64)) == 0)
+            .map(|i| 2 * i as u64 + 1),
+    );
+    primes
+}
+
+/// Trial division by [`SMALL_PRIMES`]: `Some(answer)` when that settles
+/// whether `n` is prime.
+fn small_prime_answer(n: u64) -> Option<bool> {
+    if n < 2 {
+        return Some(false);
+    }
+    SMALL_PRIMES
+        .iter()
+        .find(|&&p| n.is_multiple_of(p))
+        .map(|&p| n == p)
+        .or((n < 41 * 41).then_some(true))
+}
+
+/// Strong probable-prime test of an odd `n > 2` to base `a`.
+fn strong_probable_prime(n: u64, a: u64) -> bool {
+    let a = a % n;
+    if a == 0 {
+        return true;
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mut x = pow_mod(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Deterministic Miller-Rabin test, with the smallest known base set that is
+/// exact below each bound.
+pub(crate) fn is_prime(n: u64) -> bool {
+    if let Some(answer) = small_prime_answer(n) {
+        return answer;
+    }
+    let bases: &[u64] = match n {
+        0..2_047 => &[2],
+        2_047..1_373_653 => &[2, 3],
+        1_373_653..4_759_123_141 => &[2, 7, 61],
+        4_759_123_141..3_474_749_660_383 => &[2, 3, 5, 7, 11, 13],
+        // Jim Sinclair's set, exact for every 64-bit input.
+        _ => &[2, 325, 9_375, 28_178, 450_775, 9_780_504, 1_795_265_022],
+    };
+    bases.iter().all(|&a| strong_probable_prime(n, a))
+}
+
+/// Jacobi symbol (a / n) for an odd `n > 0`.
+fn jacobi(mut a: u64, mut n: u64) -> i32 {
+    a %= n;
+    let mut result = 1;
+    while a != 0 {
+        let twos = a.trailing_zeros();
+        a >>= twos;
+        if twos % 2 == 1 && matches!(n % 8, 3 | 5) {
+            result = -result;
+        }
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        (a, n) = (n % a, a);
+    }
+    if n == 1 { result } else { 0 }
+}
+
+/// Strong Lucas probable-prime test of an odd `n > 2` that is not a perfect
+/// square, with Selfridge's parameters: the first `D` in 5, -7, 9, -11, ...
+/// with Jacobi symbol (D / n) = -1, `P = 1` and `Q = (1 - D) / 4`.
+fn strong_lucas_probable_prime(n: u64) -> bool {
+    let to_residue = |x: i64| {
+        if x >= 0 {
+            x as u64 % n
+        } else {
+            n - x.unsigned_abs() % n
+        }
+    };
+    let mut d: i64 = 5;
+    loop {
+        match jacobi(to_residue(d), n) {
+            -1 => break,
+            // A factor of n that is smaller than n, unless n divides |D|.
+            0 if !d.unsigned_abs().is_multiple_of(n) => return false,
+            _ => d = if d > 0 { -d - 2 } else { -d + 2 },
+        }
+    }
+    let (big_d, q) = (to_residue(d), to_residue((1 - d) / 4));
+    let sub = |a: u64, b: u64| if a >= b { a - b } else { n - (b - a) };
+    let add = |a: u64, b: u64| sub(a, n - b);
+    // x / 2 modulo the odd n.
+    let half = |x: u64| {
+        if x.is_multiple_of(2) {
+            x / 2
+        } else {
+            x / 2 + n / 2 + 1
+        }
+    };
+
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+    // U_j, V_j and Q^j for j = 1, then up the bits of k.
+    let (mut u, mut v, mut qk) = (1, 1, q);
+    for bit in (0..k.ilog2()).rev() {
+        (u, v) = (mul_mod(u, v, n), sub(mul_mod(v, v, n), add(qk, qk)));
+        qk = mul_mod(qk, qk, n);
+        if k >> bit & 1 == 1 {
+            (u, v) = (half(add(u, v)), half(add(mul_mod(big_d, u, n), v)));
+            qk = mul_mod(qk, q, n);
+        }
+    }
+    if u == 0 || v == 0 {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub(mul_mod(v, v, n), add(qk, qk));
+        if v == 0 {
+            return true;
+        }
+        qk = mul_mod(qk, qk, n);
+    }
+    false
+}
+
+/// Baillie-PSW test: a strong base-2 test followed by a strong Lucas test.
+pub(crate) fn is_prime_baillie_psw(n: u64) -> bool {
+    if let Some(answer) = small_prime_answer(n) {
+        return answer;
+    }
+    let root = n.isqrt();
+    strong_probable_prime(n, 2) && root * root != n && strong_lucas_probable_prime(n)
+}
+
+/// Opaque iterator over the primes in a range, sieved one segment at a time.
+pub struct PrimeIter {
+    /// Start of the next segment, or `None` once the range is used up.
+    next: Option<u64>,
+    /// Inclusive end of the range.
+    end: u64,
+    /// Primes up to `base_limit`, for sieving.
+    base: Vec<u64>,
+    base_limit: u64,
+    /// Primes of the current segment and how many were handed out.
+    found: Vec<u64>,
+    position: usize,
+}
+
+impl PrimeIter {
+    /// The primes `p` with `lo <= p <= hi`.
+    fn new(lo: u64, hi: u64) -> PrimeIter {
+        let lo = lo.max(2);
+        PrimeIter {
+            next: (lo <= hi).then_some(lo),
+            end: hi,
+            base: Vec::new(),
+            base_limit: 0,
+            found: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Replace `found` by the primes of the segment starting at `low`.
+    fn fill(&mut self, low: u64) {
+        let high = low.saturating_add(SEGMENT - 1).min(self.end);
+        self.next = (high < self.end).then(|| high + 1);
+        self.found.clear();
+        self.position = 0;
+        if high > SIEVE_MAX {
+            self.found.extend((low..=high).filter(|&n| is_prime(n)));
+            return;
+        }
+
+        let root = high.isqrt();
+        if root > self.base_limit {
+            // Grow geometrically so that the base primes are only sieved
+            // O(log) times.
+            self.base_limit = root.max(2 * self.base_limit).min(SIEVE_MAX.isqrt());
+            self.base = primes_up_to(self.base_limit);
+        }
+        if low == 2 {
+            self.found.push(2);
+        }
+        // Entry `i` stands for the odd number `first + 2 * i`.
+        let first = low | 1;
+        if first > high {
+            return;
+        }
+        let mut composite = vec![false; ((high - first) / 2 + 1) as usize];
+        // Only odd numbers are represented, so 2 is skipped.
+        for &p in self.base.iter().skip(1).take_while(|&&p| p * p <= high) {
+            let mut multiple = (p * p).max(first.div_ceil(p) * p);
+            if multiple % 2 == 0 {
+                multiple += p;
+            }
+            for m in (multiple..=high).step_by(2 * p as usize) {
+                composite[((m - first) / 2) as usize] = true;
+            }
+        }
+        self.found.extend(
+            (0..composite.len())
+                .filter(|&i| !composite[i])
+                .map(|i| first + 2 * i as u64)
+                .filter(|&n| n > 1),
+        );
+    }
+}
+
+impl Iterator for PrimeIter {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while self.position == self.found.len() {
+            let low = self.next?;
+            self.fill(low);
+        }
+        self.position += 1;
+        Some(self.found[self.position - 1])
+    }
+}
+
+/// Return whether `n` is prime, with a deterministic Miller-Rabin test.
+#[unsafe(no_mangle)]
+pub extern "C" fn is_prime_u64(n: u64) -> bool {
+    ffi::guard(false, || Ok(is_prime(n)))
+}
+
+/// Return whether `n` is prime, with the Baillie-PSW test.
+#[unsafe(no_mangle)]
+pub extern "C" fn is_prime_bpsw(n: u64) -> bool {
+    ffi::guard(false, || Ok(is_prime_baillie_psw(n)))
+}
+
+/// Write the primes `p` with `lo <= p <= hi` to `out_buf` in increasing
+/// order, and their number to `out_len` (if non-null).
+///
+/// If there are more than `capacity` of them, the first `capacity` are
+/// written and `MathStatus::BufferTooSmall` is returned. The range is not
+/// sieved past that point, so unlike `factorize_u64` and `divisors_u64`,
+/// `out_len` then receives only a lower bound: `capacity + 1`, meaning "more
+/// than `capacity`", not the full count. `prime_iter_new` walks ranges of
+/// any size.
+///
+/// # Safety
+///
+/// `out_buf` must be null (with `capacity == 0`) or valid for writing
+/// `capacity` values, and `out_len` must be null or valid for writing a
+/// single `usize`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn primes_in_range(
+    lo: u64,
+    hi: u64,
+    out_buf: *mut u64,
+    capacity: usize,
+    out_len: *mut usize,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let buf = unsafe { ffi::slice_mut(out_buf, capacity, "out_buf") }?;
+        let mut primes = PrimeIter::new(lo, hi);
+        let written = buf
+            .iter_mut()
+            .zip(&mut primes)
+            .map(|(slot, p)| *slot = p)
+            .count();
+        let more = written == capacity && primes.next().is_some();
+        if !out_len.is_null() {
+            unsafe { out_len.write(written + more as usize) };
+        }
+        if more {
+            return Err(MathError::new(
+                MathStatus::BufferTooSmall,
+                format!("more than {capacity} primes in range"),
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Iterate over the primes `p` with `lo <= p <= hi` in increasing order.
+///
+/// The handle must be released with `prime_iter_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn prime_iter_new(lo: u64, hi: u64) -> *mut PrimeIter {
+    ffi::guard(std::ptr::null_mut(), || {
+        Ok(ffi::into_handle(PrimeIter::new(lo, hi)))
+    })
+}
+
+/// Copy up to `capacity` of the next primes of `iter` to `out_buf` and
+/// return how many were written. Fewer than `capacity` means the range is
+/// exhausted; 0 is also returned on failure, with the reason in the
+/// last-error slot.
+///
+/// # Safety
+///
+/// `iter` must be null or a live handle, and `out_buf` must be null (with
+/// `capacity == 0`) or valid for writing `capacity` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prime_iter_next(
+    iter: *mut PrimeIter,
+    out_buf: *mut u64,
+    capacity: usize,
+) -> usize {
+    ffi::guard(0, || {
+        let iter = unsafe { iter.as_mut() }
+            .ok_or_else(|| MathError::invalid_argument("`iter` is null"))?;
+        let buf = unsafe { ffi::slice_mut(out_buf, capacity, "out_buf") }?;
+        Ok(buf.iter_mut().zip(iter).map(|(slot, p)| *slot = p).count())
+    })
+}
+
+/// Release a prime iterator handle. Passing null is a no-op. The last error
+/// is left untouched.
+///
+/// # Safety
+///
+/// `iter` must be null or a live handle returned by this library, and must
+/// not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn prime_iter_free(iter: *mut PrimeIter) {
+    ffi::catch((), || unsafe { ffi::free_handle(iter) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primes_up_to() {
+        assert!(primes_up_to(0).is_empty());
+        assert!(primes_up_to(1).is_empty());
+        assert_eq!(primes_up_to(2), vec![2]);
+        assert_eq!(primes_up_to(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(primes_up_to(31).last(), Some(&31));
+        assert_eq!(primes_up_to(1_000_000).len(), 78_498);
+    }
+
+    #[test]
+    fn test_is_prime() {
+        let sieved = primes_up_to(10_000);
+        for n in 0..=10_000 {
+            assert_eq!(is_prime(n), sieved.binary_search(&n).is_ok(), "{n}");
+        }
+        assert!(is_prime(18_446_744_073_709_551_557));
+        assert!(!is_prime(u64::MAX));
+        // Strong pseudoprime to bases 2..=23.
+        assert!(!is_prime(3_825_123_056_546_413_051));
+    }
+
+    #[test]
+    fn test_baillie_psw() {
+        let sieved = primes_up_to(100_000);
+        for n in 0..=100_000 {
+            let expected = sieved.binary_search(&n).is_ok();
+            assert_eq!(is_prime(n), expected, "{n}");
+            assert_eq!(is_prime_bpsw(n), expected, "{n}");
+        }
+        // Strong pseudoprimes to base 2 (1_194_649 = 1093^2), strong Lucas
+        // pseudoprimes and Carmichael numbers.
+        for n in [
+            2_047,
+            1_194_649,
+            12_327_121,
+            3_215_031_751,
+            3_825_123_056_546_413_051,
+            5_459,
+            5_777,
+            10_877,
+            16_109,
+            18_971,
+            561,
+            41_041,
+            825_265,
+            4_294_967_297,
+        ] {
+            assert!(!is_prime_bpsw(n), "{n}");
+            assert!(!is_prime_u64(n), "{n}");
+        }
+        assert!(is_prime_bpsw(18_446_744_073_709_551_557));
+        assert!(is_prime_bpsw(4_294_967_291));
+        assert!(!is_prime_bpsw(u64::MAX));
+
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        for _ in 0..20_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let n = (state | 1) >> (state % 40);
+            assert_eq!(is_prime_bpsw(n), is_prime(n), "{n}");
+        }
+    }
+
+    fn collect(lo: u64, hi: u64, batch: usize) -> Vec<u64> {
+        let iter = prime_iter_new(lo, hi);
+        let mut buf = vec![0; batch];
+        let mut primes = Vec::new();
+        loop {
+            let n = unsafe { prime_iter_next(iter, buf.as_mut_ptr(), batch) };
+            primes.extend_from_slice(&buf[..n]);
+            if n < batch {
+                break;
+            }
+        }
+        unsafe { prime_iter_free(iter) };
+        primes
+    }
+
+    #[test]
+    fn test_segmented_sieve() {
+        assert_eq!(collect(0, 1_000_000, 1_000), primes_up_to(1_000_000));
+        assert_eq!(collect(2, 2, 7), vec![2]);
+        assert_eq!(collect(24, 28, 7), Vec::<u64>::new());
+        assert_eq!(collect(10, 5, 7), Vec::<u64>::new());
+
+        let brute = |lo: u64, hi: u64| (lo..=hi).filter(|&n| is_prime(n)).collect::<Vec<_>>();
+        let t = 1_000_000_000_000;
+        assert_eq!(collect(t, t + 600_000, 4_096), brute(t, t + 600_000));
+        assert_eq!(
+            collect(SIEVE_MAX - 3_000, SIEVE_MAX + 3_000, 64),
+            brute(SIEVE_MAX - 3_000, SIEVE_MAX + 3_000)
+        );
+        assert_eq!(
+            collect(u64::MAX - 1_000, u64::MAX, 3),
+            brute(u64::MAX - 1_000, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_primes_in_range() {
+        let mut buf = [0u64; 8];
+        let mut len = 0;
+        let status = unsafe { primes_in_range(10, 30, buf.as_mut_ptr(), 8, &mut len) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(len, 6);
+        assert_eq!(buf[..6], [11, 13, 17, 19, 23, 29]);
+
+        let status = unsafe { primes_in_range(0, 100, buf.as_mut_ptr(), 8, &mut len) };
+        assert_eq!(status, MathStatus::BufferTooSmall);
+        assert_eq!(len, 9);
+        assert_eq!(buf, [2, 3, 5, 7, 11, 13, 17, 19]);
+        let status = unsafe { primes_in_range(0, 19, buf.as_mut_ptr(), 8, &mut len) };
+        assert_eq!((status, len), (MathStatus::Ok, 8));
+
+        let status = unsafe { primes_in_range(0, 100, std::ptr::null_mut(), 0, &mut len) };
+        assert_eq!(status, MathStatus::BufferTooSmall);
+        assert_eq!(len, 1);
+        let status = unsafe { primes_in_range(24, 28, std::ptr::null_mut(), 0, &mut len) };
+        assert_eq!((status, len), (MathStatus::Ok, 0));
+
+        // Returns as soon as the buffer is full, however wide the range.
+        let status = unsafe { primes_in_range(0, u64::MAX, buf.as_mut_ptr(), 4, &mut len) };
+        assert_eq!((status, len), (MathStatus::BufferTooSmall, 5));
+        assert_eq!(buf[..4], [2, 3, 5, 7]);
+        let iter = prime_iter_new(0, 10);
+        assert_eq!(
+            unsafe { prime_iter_next(std::ptr::null_mut(), buf.as_mut_ptr(), 8) },
+            0
+        );
+        unsafe { prime_iter_free(iter) };
+        assert_eq!(
+            crate::error::math_last_error_code(),
+            MathStatus::InvalidArgument
+        );
+    }
+}
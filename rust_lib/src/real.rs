@@ -0,0 +1,367 @@
+//! Binary fixed-point reals backed by `BigInt`, for quantities that need far
+//! more precision than `f64` offers (for example the fractional part of
+//! log10(n!) when n is close to `u64::MAX`).
+//!
+//! A [`Fixed`] stores `raw / 2^prec`. Every operation truncates to the
+//! precision of its operands, so each step is accurate to a few units in the
+//! last place; callers pick `prec` with enough guard bits for their needs.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bigint::BigInt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Fixed {
+    raw: BigInt,
+    prec: usize,
+}
+
+impl Fixed {
+    pub(crate) fn from_int(value: &BigInt, prec: usize) -> Fixed {
+        Fixed {
+            raw: value.shl_bits(prec),
+            prec,
+        }
+    }
+
+    pub(crate) fn from_u64(value: u64, prec: usize) -> Fixed {
+        Fixed::from_int(&BigInt::from(value), prec)
+    }
+
+    pub(crate) fn one(prec: usize) -> Fixed {
+        Fixed::from_u64(1, prec)
+    }
+
+    /// `numerator / denominator` rounded towards zero.
+    pub(crate) fn ratio(numerator: &BigInt, denominator: &BigInt, prec: usize) -> Fixed {
+        let (raw, _) = numerator
+            .shl_bits(prec)
+            .divrem(denominator)
+            .expect("fixed-point ratio with a zero denominator");
+        Fixed { raw, prec }
+    }
+
+    pub(crate) fn mul_u64(&self, factor: u64) -> Fixed {
+        let mut raw = self.raw.clone();
+        raw.mul_small_assign(factor);
+        Fixed {
+            raw,
+            prec: self.prec,
+        }
+    }
+
+    pub(crate) fn div_u64(&self, divisor: u64) -> Fixed {
+        Fixed {
+            raw: self.raw.divrem_small(divisor).0,
+            prec: self.prec,
+        }
+    }
+
+    /// Nearest value representable with `prec` fractional bits, truncating
+    /// towards negative infinity when precision is dropped.
+    pub(crate) fn with_prec(&self, prec: usize) -> Fixed {
+        let raw = if prec >= self.prec {
+            self.raw.shl_bits(prec - self.prec)
+        } else {
+            self.raw.shr((self.prec - prec) as u64)
+        };
+        Fixed { raw, prec }
+    }
+
+    /// The value of a finite `f64`, truncated to `prec` fractional bits.
+    pub(crate) fn from_f64(value: f64, prec: usize) -> Fixed {
+        debug_assert!(value.is_finite());
+        if value == 0.0 {
+            return Fixed::from_u64(0, prec);
+        }
+        // value = mantissa * 2^exponent with a 53-bit integer mantissa.
+        let exponent = value.abs().log2().floor() as i64 - 52;
+        let mantissa = (value.abs() * 2f64.powi(-exponent as i32)) as u64;
+        let raw = BigInt::from(mantissa);
+        let raw = if value < 0.0 { -&raw } else { raw };
+        let shift = exponent + prec as i64;
+        let raw = if shift >= 0 {
+            raw.shl_bits(shift as usize)
+        } else {
+            raw.shr(shift.unsigned_abs())
+        };
+        Fixed { raw, prec }
+    }
+
+    /// Square root of a non-negative value.
+    pub(crate) fn sqrt(&self) -> Fixed {
+        Fixed {
+            raw: self.raw.shl_bits(self.prec).sqrt_floor(),
+            prec: self.prec,
+        }
+    }
+
+    /// e^x, accurate to a few units in the last place however large the
+    /// result.
+    pub(crate) fn exp(&self) -> Fixed {
+        if self.raw.is_negative() {
+            return &Fixed::one(self.prec) / &(-self).exp();
+        }
+        // e^x = (e^(x / 2^r))^(2^r) with x / 2^r below 2^-s, trading Taylor
+        // terms for squarings. Each squaring doubles the relative error and
+        // the result carries about x log2(e) integer bits, so the work is
+        // done with that many extra bits.
+        let s = (self.prec as f64).sqrt() as usize / 2;
+        let r = self.floor().bit_len() as usize + s;
+        let result_bits = (self.to_f64() * std::f64::consts::LOG2_E).ceil() as usize;
+        let work = self.prec + result_bits + r + 16;
+        let z = Fixed {
+            raw: self.with_prec(work).raw.shr(r as u64),
+            prec: work,
+        };
+        let mut sum = Fixed::one(work);
+        let mut term = Fixed::one(work);
+        let mut i = 1u64;
+        while !term.raw.is_zero() {
+            term = (&term * &z).div_u64(i);
+            sum = &sum + &term;
+            i += 1;
+        }
+        for _ in 0..r {
+            sum = &sum * &sum;
+        }
+        sum.with_prec(self.prec)
+    }
+
+    /// Cosine from its Taylor series; meant for arguments of magnitude at
+    /// most about 2, where the series converges quickly.
+    pub(crate) fn cos(&self) -> Fixed {
+        let work = self.prec + 16;
+        let x = self.with_prec(work);
+        let x2 = &x * &x;
+        let mut sum = Fixed::one(work);
+        let mut term = Fixed::one(work);
+        let mut i = 1u64;
+        while !term.raw.is_zero() {
+            term = (&term * &x2).div_u64((2 * i - 1) * (2 * i));
+            sum = if i % 2 == 1 {
+                &sum - &term
+            } else {
+                &sum + &term
+            };
+            i += 1;
+        }
+        sum.with_prec(self.prec)
+    }
+
+    /// Largest integer not above the value.
+    pub(crate) fn floor(&self) -> BigInt {
+        self.raw.shr(self.prec as u64)
+    }
+
+    /// Nearest `f64`, good to about one unit in the last place.
+    pub(crate) fn to_f64(&self) -> f64 {
+        let bits = self.raw.bit_len() as i64;
+        let shift = (bits - 64).max(0);
+        let top = self
+            .raw
+            .abs()
+            .shr(shift as u64)
+            .to_u64()
+            .unwrap_or(u64::MAX);
+        let magnitude = top as f64 * 2f64.powi((shift - self.prec as i64) as i32);
+        if self.raw.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// `sum_{i >= 0} (+-1)^i / ((2i + 1) k^(2i + 1))`: atanh(1/k), or
+    /// atan(1/k) when the signs are `alternating`.
+    fn arc_series_recip(k: u64, alternating: bool, prec: usize) -> Fixed {
+        let k2 = k * k;
+        let mut term = Fixed::one(prec).div_u64(k);
+        let mut sum = Fixed::from_u64(0, prec);
+        let mut i = 0u64;
+        while !term.raw.is_zero() {
+            let part = term.div_u64(2 * i + 1);
+            sum = if alternating && i % 2 == 1 {
+                &sum - &part
+            } else {
+                &sum + &part
+            };
+            term = term.div_u64(k2);
+            i += 1;
+        }
+        sum
+    }
+
+    pub(crate) fn ln2(prec: usize) -> Fixed {
+        // ln 2 = 2 atanh(1/3)
+        Fixed::arc_series_recip(3, false, prec).mul_u64(2)
+    }
+
+    pub(crate) fn ln10(prec: usize) -> Fixed {
+        // ln 10 = 3 ln 2 + ln(5/4) = 3 ln 2 + 2 atanh(1/9)
+        &Fixed::ln2(prec).mul_u64(3) + &Fixed::arc_series_recip(9, false, prec).mul_u64(2)
+    }
+
+    pub(crate) fn pi(prec: usize) -> Fixed {
+        // Machin: pi = 16 atan(1/5) - 4 atan(1/239)
+        &Fixed::arc_series_recip(5, true, prec).mul_u64(16)
+            - &Fixed::arc_series_recip(239, true, prec).mul_u64(4)
+    }
+
+    /// Natural logarithm of a positive value.
+    pub(crate) fn ln(&self) -> Fixed {
+        assert!(self.raw.signum() > 0, "logarithm of a non-positive value");
+        let prec = self.prec;
+        // self = 2^e * y with y in [1, 2), so ln(self) = e ln 2 + ln y and
+        // ln y = 2 atanh(z) with z = (y - 1) / (y + 1) in [0, 1/3).
+        let e = self.raw.bit_len() as i64 - 1 - prec as i64;
+        let y = if e >= 0 {
+            Fixed {
+                raw: self.raw.shr(e as u64),
+                prec,
+            }
+        } else {
+            Fixed {
+                raw: self.raw.shl_bits((-e) as usize),
+                prec,
+            }
+        };
+        let one = Fixed::one(prec);
+        let z = &(&y - &one) / &(&y + &one);
+        let z2 = &z * &z;
+        let mut term = z;
+        let mut sum = Fixed::from_u64(0, prec);
+        let mut i = 0u64;
+        while !term.raw.is_zero() {
+            sum = &sum + &term.div_u64(2 * i + 1);
+            term = &term * &z2;
+            i += 1;
+        }
+        let ln2 = Fixed::ln2(prec).mul_u64(e.unsigned_abs());
+        let ln2 = if e < 0 { -&ln2 } else { ln2 };
+        &ln2 + &sum.mul_u64(2)
+    }
+}
+
+impl Add for &Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, rhs.prec);
+        Fixed {
+            raw: &self.raw + &rhs.raw,
+            prec: self.prec,
+        }
+    }
+}
+
+impl Sub for &Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, rhs.prec);
+        Fixed {
+            raw: &self.raw - &rhs.raw,
+            prec: self.prec,
+        }
+    }
+}
+
+impl Mul for &Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, rhs.prec);
+        Fixed {
+            raw: (&self.raw * &rhs.raw).shr(self.prec as u64),
+            prec: self.prec,
+        }
+    }
+}
+
+impl Div for &Fixed {
+    type Output = Fixed;
+
+    fn div(self, rhs: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, rhs.prec);
+        Fixed::ratio(&self.raw, &rhs.raw, self.prec)
+    }
+}
+
+impl Neg for &Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed {
+            raw: -&self.raw,
+            prec: self.prec,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREC: usize = 200;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 4.0 * f64::EPSILON * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_constants() {
+        assert!(close(Fixed::ln2(PREC).to_f64(), std::f64::consts::LN_2));
+        assert!(close(Fixed::ln10(PREC).to_f64(), std::f64::consts::LN_10));
+        assert!(close(Fixed::pi(PREC).to_f64(), std::f64::consts::PI));
+        // Far beyond f64: the first 50 decimals of pi.
+        let scale = Fixed::from_int(&BigInt::from(10u64).pow(50).unwrap(), PREC);
+        assert_eq!(
+            (&Fixed::pi(PREC) * &scale).floor().to_string(),
+            "314159265358979323846264338327950288419716939937510"
+        );
+    }
+
+    #[test]
+    fn test_ln() {
+        for x in [1u64, 2, 3, 10, 12345, u64::MAX] {
+            assert!(
+                close(Fixed::from_u64(x, PREC).ln().to_f64(), (x as f64).ln()),
+                "{x}"
+            );
+        }
+        let half = Fixed::one(PREC).div_u64(2);
+        assert!(close(half.ln().to_f64(), -std::f64::consts::LN_2));
+        let e = &Fixed::ln10(PREC) - &Fixed::from_u64(10, PREC).ln();
+        assert!(e.raw.abs() < BigInt::from(16u64));
+    }
+
+    #[test]
+    fn test_exp_sqrt_cos() {
+        for x in [0.0, 0.5, 1.0, -2.25, 10.0, 100.0] {
+            let e = Fixed::from_f64(x, PREC).exp();
+            assert!(close(e.to_f64(), f64::exp(x)), "{x}");
+        }
+        for x in [0.0, 0.25, 1.0, 1.5, 2.0] {
+            assert!(
+                close(Fixed::from_f64(x, PREC).cos().to_f64(), x.cos()),
+                "{x}"
+            );
+        }
+        let root = Fixed::from_u64(2, PREC).sqrt();
+        assert!(close(root.to_f64(), std::f64::consts::SQRT_2));
+        assert!((&(&root * &root) - &Fixed::from_u64(2, PREC)).raw.abs() < BigInt::from(4u64));
+        // e^700 exceeds 2^1000 but keeps all of its fractional bits.
+        let big = Fixed::from_u64(700, PREC).exp();
+        assert_eq!(big.floor().bit_len(), 1010);
+        let finer = Fixed::from_u64(700, 2 * PREC).exp().with_prec(PREC);
+        assert!((&big - &finer).raw.abs() < BigInt::from(4u64));
+    }
+
+    #[test]
+    fn test_floor() {
+        let x = -&Fixed::from_u64(7, PREC).div_u64(2);
+        assert_eq!(x.floor(), BigInt::from(-4i64));
+        assert_eq!(x.to_f64(), -3.5);
+    }
+}
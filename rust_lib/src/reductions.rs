@@ -0,0 +1,1336 @@
+//! Reductions and prefix sums over caller-owned arrays.
+//!
+//! `array_sum`, `array_product`, `array_reduce_min`, `array_reduce_max`,
+//! `array_argmin`, `array_argmax` and `array_prefix_sum` exist for `int32_t`,
+//! `int64_t`, `float` and `double` (`array_sum_i64`, `array_argmax_f32`, ...).
+//! Each takes a `threads` count: 1 runs on the calling thread and 0 picks the
+//! number of available cores. Arrays are processed in blocks of 65536
+//! elements, which are spread over the threads and always combined in the
+//! same order, so every result, including the rounding of floating-point
+//! sums, is independent of the thread count.
+//!
+//! Integer sums and products are computed exactly and the [`OverflowPolicy`]
+//! is applied to the exact result, as in `arith`; an intermediate sum that
+//! leaves the range of the type does not count as an overflow. For prefix
+//! sums the policy applies to every prefix, and with `Checked` nothing is
+//! written unless all of them fit. `out` may be the same array as `a`.
+//!
+//! The minimum and maximum ignore NaN elements unless every element is NaN,
+//! and the `arg` variants return the index of the first extreme element.
+
+use std::cmp::Ordering;
+use std::ops::{Add, Mul, Range};
+
+use crate::arith::{self, Int, Outcome};
+use crate::error::{MathError, MathResult};
+use crate::{MathStatus, OverflowPolicy, ffi};
+
+/// Number of elements in a block, the unit of work handed to a thread.
+const BLOCK: usize = 1 << 16;
+
+/// Which prefix `array_prefix_sum` writes for each element.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanKind {
+    /// `out[i] = a[0] + ... + a[i]`.
+    Inclusive = 0,
+    /// `out[i] = a[0] + ... + a[i - 1]`, so `out[0] = 0`.
+    Exclusive = 1,
+}
+
+impl ScanKind {
+    /// The prefix written for an element, given the sums before and after it.
+    fn pick<A>(self, before: A, after: A) -> A {
+        match self {
+            ScanKind::Inclusive => after,
+            ScanKind::Exclusive => before,
+        }
+    }
+}
+
+/// Integer element types, whose sums and products are tracked exactly.
+trait ExactInt: Int + Into<i128> + Send + Sync {
+    const MIN: i128;
+    const MAX: i128;
+
+    /// `exact` reduced modulo 2^bits.
+    fn wrap(exact: i128) -> Self;
+}
+
+macro_rules! impl_exact_int {
+    ($($t:ty),*) => {$(
+        impl ExactInt for $t {
+            const MIN: i128 = <$t>::MIN as i128;
+            const MAX: i128 = <$t>::MAX as i128;
+
+            fn wrap(exact: i128) -> Self {
+                exact as $t
+            }
+        }
+    )*};
+}
+
+impl_exact_int!(i32, i64);
+
+trait Float: Copy + Send + Sync + Add<Output = Self> + Mul<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+impl Float for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+}
+
+impl Float for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+}
+
+/// Element types with a minimum and maximum.
+trait Ordered: Copy + Send + Sync + PartialOrd {
+    fn is_nan(self) -> bool;
+}
+
+macro_rules! impl_ordered {
+    ($($t:ty => $is_nan:expr),*) => {$(
+        impl Ordered for $t {
+            fn is_nan(self) -> bool {
+                $is_nan(self)
+            }
+        }
+    )*};
+}
+
+impl_ordered!(i32 => |_| false, i64 => |_| false, f32 => f32::is_nan, f64 => f64::is_nan);
+
+/// Apply `f` to the index range of every block of a `len`-element array and
+/// collect the results in block order, giving each of up to `threads`
+/// threads a contiguous run of blocks.
+pub(crate) fn map_blocks<P: Send>(
+    len: usize,
+    threads: usize,
+    f: impl Fn(Range<usize>) -> P + Sync,
+) -> Vec<P> {
+    let blocks = len.div_ceil(BLOCK);
+    let block = |i: usize| f(i * BLOCK..len.min((i + 1) * BLOCK));
+    if threads <= 1 || blocks <= 1 {
+        return (0..blocks).map(block).collect();
+    }
+    let per_thread = blocks.div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..blocks)
+            .step_by(per_thread)
+            .map(|first| {
+                let block = &block;
+                scope.spawn(move || {
+                    (first..blocks.min(first + per_thread))
+                        .map(block)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+            })
+            .collect()
+    })
+}
+
+/// Reduce the array `a` with `f` and write the result to `out`, reporting a
+/// deferred error only once the result is written.
+///
+/// # Safety
+///
+/// `a` must be null or valid for reading `len` elements, and `out` null or
+/// valid for writing a single `R`.
+unsafe fn reduce<T, R>(
+    a: *const T,
+    len: usize,
+    out: *mut R,
+    f: impl FnOnce(&[T]) -> Outcome<R>,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let data = unsafe { ffi::slice_ref(a, len, "a") }?;
+        let (value, deferred) = f(data)?;
+        unsafe { ffi::write_out(out, "out", value) }?;
+        deferred.map_or(Ok(()), Err)
+    })
+}
+
+fn int_sum<T: ExactInt>(data: &[T], threads: usize, policy: OverflowPolicy) -> Outcome<T> {
+    let exact: i128 = map_blocks(data.len(), threads, |range| {
+        data[range].iter().map(|&x| x.into()).sum::<i128>()
+    })
+    .into_iter()
+    .sum();
+    let fits = (T::MIN..=T::MAX).contains(&exact);
+    arith::resolve(
+        policy,
+        (T::wrap(exact), !fits),
+        || T::wrap(exact.clamp(T::MIN, T::MAX)),
+        || "the sum of `a`".to_owned(),
+    )
+}
+
+/// An exact integer product, kept as its sign, its magnitude (`None` once
+/// that exceeds `u128`) and its value modulo 2^128.
+#[derive(Debug, Clone, Copy)]
+struct Product {
+    negative: bool,
+    magnitude: Option<u128>,
+    wrapped: i128,
+}
+
+impl Product {
+    const ONE: Product = Product {
+        negative: false,
+        magnitude: Some(1),
+        wrapped: 1,
+    };
+
+    fn of(x: i128) -> Product {
+        Product {
+            negative: x < 0,
+            magnitude: Some(x.unsigned_abs()),
+            wrapped: x,
+        }
+    }
+
+    fn mul(self, other: Product) -> Product {
+        let magnitude = match (self.magnitude, other.magnitude) {
+            (Some(0), _) | (_, Some(0)) => Some(0),
+            (Some(a), Some(b)) => a.checked_mul(b),
+            _ => None,
+        };
+        Product {
+            negative: self.negative != other.negative,
+            magnitude,
+            wrapped: self.wrapped.wrapping_mul(other.wrapped),
+        }
+    }
+}
+
+fn int_product<T: ExactInt>(data: &[T], threads: usize, policy: OverflowPolicy) -> Outcome<T> {
+    let product = map_blocks(data.len(), threads, |range| {
+        data[range]
+            .iter()
+            .fold(Product::ONE, |acc, &x| acc.mul(Product::of(x.into())))
+    })
+    .into_iter()
+    .fold(Product::ONE, Product::mul);
+    let limit = if product.negative {
+        T::MIN.unsigned_abs()
+    } else {
+        T::MAX as u128
+    };
+    let fits = product.magnitude.is_some_and(|m| m <= limit);
+    arith::resolve(
+        policy,
+        (T::wrap(product.wrapped), !fits),
+        || T::wrap(if product.negative { T::MIN } else { T::MAX }),
+        || "the product of `a`".to_owned(),
+    )
+}
+
+/// Fold each block with `op` starting from `init`, then fold the block
+/// results in order.
+fn float_fold<T: Float>(data: &[T], threads: usize, init: T, op: impl Fn(T, T) -> T + Sync) -> T {
+    map_blocks(data.len(), threads, |range| {
+        data[range].iter().fold(init, |acc, &x| op(acc, x))
+    })
+    .into_iter()
+    .fold(init, &op)
+}
+
+/// The first smallest (`want == Less`) or largest (`Greater`) element and its
+/// index, skipping NaN unless every element is NaN.
+fn extremum<T: Ordered>(data: &[T], threads: usize, want: Ordering) -> MathResult<(usize, T)> {
+    let better = |best: (usize, T), next: (usize, T)| {
+        let replace =
+            next.1.partial_cmp(&best.1) == Some(want) || best.1.is_nan() && !next.1.is_nan();
+        if replace { next } else { best }
+    };
+    map_blocks(data.len(), threads, |range| {
+        range
+            .map(|i| (i, data[i]))
+            .reduce(better)
+            .expect("blocks are not empty")
+    })
+    .into_iter()
+    .reduce(better)
+    .ok_or_else(|| MathError::invalid_argument("`a` is empty"))
+}
+
+/// The input and output of a prefix sum, shared with the worker threads.
+#[derive(Clone, Copy)]
+struct Buffers<T> {
+    a: *const T,
+    out: *mut T,
+}
+
+// SAFETY: each thread only touches the elements of its own blocks.
+unsafe impl<T: Send> Send for Buffers<T> {}
+unsafe impl<T: Sync> Sync for Buffers<T> {}
+
+impl<T: Copy> Buffers<T> {
+    /// # Safety
+    ///
+    /// `a` and `out` must be valid for `len` elements, and `out` must be null
+    /// only when `len == 0`.
+    unsafe fn new(a: *const T, out: *mut T, len: usize) -> MathResult<Self> {
+        if len > 0 && out.is_null() {
+            return Err(MathError::invalid_argument("`out` is null"));
+        }
+        Ok(Buffers { a, out })
+    }
+
+    // Methods rather than field accesses, so that closures capture the whole
+    // `Send + Sync` struct instead of its raw pointers.
+    unsafe fn read(&self, i: usize) -> T {
+        unsafe { self.a.add(i).read() }
+    }
+
+    unsafe fn write(&self, i: usize, value: T) {
+        unsafe { self.out.add(i).write(value) }
+    }
+
+    /// Compute `out[i]` for every `i` in a block from `offsets[block]`: `add`
+    /// accumulates an element and `value` turns a prefix into the element to
+    /// write. Each element is read before its index is written.
+    ///
+    /// # Safety
+    ///
+    /// `offsets` must hold one entry per block of a `len`-element array, and
+    /// the buffers must be valid for `len` elements.
+    unsafe fn write_scan<A: Copy + Sync>(
+        self,
+        len: usize,
+        threads: usize,
+        kind: ScanKind,
+        offsets: &[A],
+        add: impl Fn(A, T) -> A + Sync,
+        value: impl Fn(A) -> T + Sync,
+    ) where
+        T: Send + Sync,
+    {
+        map_blocks(len, threads, |range| {
+            let mut before = offsets[range.start / BLOCK];
+            for i in range {
+                let after = add(before, unsafe { self.read(i) });
+                unsafe { self.write(i, value(kind.pick(before, after))) };
+                before = after;
+            }
+        });
+    }
+}
+
+/// The sum of the blocks before each block.
+fn block_offsets<A: Copy>(sums: &[A], zero: A, add: impl Fn(A, A) -> A) -> Vec<A> {
+    sums.iter()
+        .scan(zero, |acc, &sum| {
+            let offset = *acc;
+            *acc = add(*acc, sum);
+            Some(offset)
+        })
+        .collect()
+}
+
+/// # Safety
+///
+/// `a` must be null or valid for reading `len` elements, and `out` null or
+/// valid for writing `len` elements.
+unsafe fn int_prefix_sum<T: ExactInt>(
+    (a, out, len): (*const T, *mut T, usize),
+    kind: ScanKind,
+    policy: OverflowPolicy,
+    threads: usize,
+) -> MathResult<()> {
+    let data = unsafe { ffi::slice_ref(a, len, "a") }?;
+    let buffers = unsafe { Buffers::new(a, out, len) }?;
+    // The sum of each block and the range of the prefixes it writes,
+    // relative to the sum of the blocks before it.
+    let blocks = map_blocks(len, threads, |range| {
+        let (mut sum, mut lo, mut hi) = (0i128, i128::MAX, i128::MIN);
+        for &x in &data[range] {
+            let after = sum + x.into();
+            let prefix = kind.pick(sum, after);
+            (lo, hi) = (lo.min(prefix), hi.max(prefix));
+            sum = after;
+        }
+        (sum, lo, hi)
+    });
+    let sums: Vec<i128> = blocks.iter().map(|&(sum, ..)| sum).collect();
+    let offsets = block_offsets(&sums, 0, |x, y| x + y);
+    let fits = |prefix: i128| (T::MIN..=T::MAX).contains(&prefix);
+    let first = (0..blocks.len())
+        .find(|&b| !fits(offsets[b] + blocks[b].1) || !fits(offsets[b] + blocks[b].2))
+        .map(|b| {
+            let start = b * BLOCK;
+            let mut before = offsets[b];
+            let at = data[start..len.min(start + BLOCK)].iter().position(|&x| {
+                let after = before + x.into();
+                let prefix = kind.pick(before, after);
+                before = after;
+                !fits(prefix)
+            });
+            start + at.expect("a prefix in the block overflows")
+        });
+    let error = first.map(|i| {
+        MathError::overflow(format!(
+            "the prefix sum at element {i} overflows {}",
+            T::NAME
+        ))
+    });
+    let deferred = match (policy, error) {
+        (OverflowPolicy::Checked, Some(error)) => return Err(error),
+        (OverflowPolicy::Overflowing, error) => error,
+        _ => None,
+    };
+    let saturating = policy == OverflowPolicy::Saturating;
+    unsafe {
+        buffers.write_scan(
+            len,
+            threads,
+            kind,
+            &offsets,
+            |acc, x| acc + x.into(),
+            |prefix| {
+                if saturating {
+                    T::wrap(prefix.clamp(T::MIN, T::MAX))
+                } else {
+                    T::wrap(prefix)
+                }
+            },
+        )
+    };
+    deferred.map_or(Ok(()), Err)
+}
+
+/// # Safety
+///
+/// As for [`int_prefix_sum`].
+unsafe fn float_prefix_sum<T: Float>(
+    (a, out, len): (*const T, *mut T, usize),
+    kind: ScanKind,
+    threads: usize,
+) -> MathResult<()> {
+    let data = unsafe { ffi::slice_ref(a, len, "a") }?;
+    let buffers = unsafe { Buffers::new(a, out, len) }?;
+    let sums = map_blocks(len, threads, |range| {
+        data[range].iter().fold(T::ZERO, |acc, &x| acc + x)
+    });
+    let offsets = block_offsets(&sums, T::ZERO, |x, y| x + y);
+    unsafe {
+        buffers.write_scan(
+            len,
+            threads,
+            kind,
+            &offsets,
+            |acc, x| acc + x,
+            |prefix| prefix,
+        )
+    };
+    Ok(())
+}
+
+/// Sum the `int32_t` array `a` under `policy`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sum_i32(
+    a: *const i32,
+    len: usize,
+    policy: OverflowPolicy,
+    threads: u32,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            int_sum(data, ffi::thread_count(threads), policy)
+        })
+    }
+}
+
+/// Multiply the elements of the `int32_t` array `a` under `policy`; an empty
+/// array gives 1.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_product_i32(
+    a: *const i32,
+    len: usize,
+    policy: OverflowPolicy,
+    threads: u32,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            int_product(data, ffi::thread_count(threads), policy)
+        })
+    }
+}
+
+/// Find the smallest element of the non-empty `int32_t` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_min_i32(
+    a: *const i32,
+    len: usize,
+    threads: u32,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the largest element of the non-empty `int32_t` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_max_i32(
+    a: *const i32,
+    len: usize,
+    threads: u32,
+    out: *mut i32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the index of the first smallest element of the non-empty `int32_t`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmin_i32(
+    a: *const i32,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Find the index of the first largest element of the non-empty `int32_t`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmax_i32(
+    a: *const i32,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Write the prefix sums of the `int32_t` array `a` to `out`, applying `policy`
+/// to each of them.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null (with `len == 0`) or valid for writing `len`
+/// elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_prefix_sum_i32(
+    a: *const i32,
+    out: *mut i32,
+    len: usize,
+    kind: ScanKind,
+    policy: OverflowPolicy,
+    threads: u32,
+) -> MathStatus {
+    let threads = ffi::thread_count(threads);
+    ffi::guard_status(|| unsafe { int_prefix_sum((a, out, len), kind, policy, threads) })
+}
+
+/// Sum the `int64_t` array `a` under `policy`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sum_i64(
+    a: *const i64,
+    len: usize,
+    policy: OverflowPolicy,
+    threads: u32,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            int_sum(data, ffi::thread_count(threads), policy)
+        })
+    }
+}
+
+/// Multiply the elements of the `int64_t` array `a` under `policy`; an empty
+/// array gives 1.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_product_i64(
+    a: *const i64,
+    len: usize,
+    policy: OverflowPolicy,
+    threads: u32,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            int_product(data, ffi::thread_count(threads), policy)
+        })
+    }
+}
+
+/// Find the smallest element of the non-empty `int64_t` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_min_i64(
+    a: *const i64,
+    len: usize,
+    threads: u32,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the largest element of the non-empty `int64_t` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `int64_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_max_i64(
+    a: *const i64,
+    len: usize,
+    threads: u32,
+    out: *mut i64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the index of the first smallest element of the non-empty `int64_t`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmin_i64(
+    a: *const i64,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Find the index of the first largest element of the non-empty `int64_t`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmax_i64(
+    a: *const i64,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Write the prefix sums of the `int64_t` array `a` to `out`, applying `policy`
+/// to each of them.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null (with `len == 0`) or valid for writing `len`
+/// elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_prefix_sum_i64(
+    a: *const i64,
+    out: *mut i64,
+    len: usize,
+    kind: ScanKind,
+    policy: OverflowPolicy,
+    threads: u32,
+) -> MathStatus {
+    let threads = ffi::thread_count(threads);
+    ffi::guard_status(|| unsafe { int_prefix_sum((a, out, len), kind, policy, threads) })
+}
+
+/// Sum the `float` array `a`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sum_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            Ok((
+                float_fold(data, ffi::thread_count(threads), f32::ZERO, |x, y| x + y),
+                None,
+            ))
+        })
+    }
+}
+
+/// Multiply the elements of the `float` array `a`; an empty array gives 1.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_product_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            Ok((
+                float_fold(data, ffi::thread_count(threads), f32::ONE, |x, y| x * y),
+                None,
+            ))
+        })
+    }
+}
+
+/// Find the smallest element of the non-empty `float` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_min_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the largest element of the non-empty `float` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_max_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the index of the first smallest element of the non-empty `float`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmin_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Find the index of the first largest element of the non-empty `float`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmax_f32(
+    a: *const f32,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Write the prefix sums of the `float` array `a` to `out`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null (with `len == 0`) or valid for writing `len`
+/// elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_prefix_sum_f32(
+    a: *const f32,
+    out: *mut f32,
+    len: usize,
+    kind: ScanKind,
+    threads: u32,
+) -> MathStatus {
+    let threads = ffi::thread_count(threads);
+    ffi::guard_status(|| unsafe { float_prefix_sum((a, out, len), kind, threads) })
+}
+
+/// Sum the `double` array `a`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_sum_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            Ok((
+                float_fold(data, ffi::thread_count(threads), f64::ZERO, |x, y| x + y),
+                None,
+            ))
+        })
+    }
+}
+
+/// Multiply the elements of the `double` array `a`; an empty array gives 1.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_product_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            Ok((
+                float_fold(data, ffi::thread_count(threads), f64::ONE, |x, y| x * y),
+                None,
+            ))
+        })
+    }
+}
+
+/// Find the smallest element of the non-empty `double` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_min_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the largest element of the non-empty `double` array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_reduce_max_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (_, value) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((value, None))
+        })
+    }
+}
+
+/// Find the index of the first smallest element of the non-empty `double`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmin_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Less)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Find the index of the first largest element of the non-empty `double`
+/// array `a`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_argmax_f64(
+    a: *const f64,
+    len: usize,
+    threads: u32,
+    out: *mut usize,
+) -> MathStatus {
+    unsafe {
+        reduce(a, len, out, |data| {
+            let (index, _) = extremum(data, ffi::thread_count(threads), Ordering::Greater)?;
+            Ok((index, None))
+        })
+    }
+}
+
+/// Write the prefix sums of the `double` array `a` to `out`.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null (with `len == 0`) or valid for writing `len`
+/// elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_prefix_sum_f64(
+    a: *const f64,
+    out: *mut f64,
+    len: usize,
+    kind: ScanKind,
+    threads: u32,
+) -> MathStatus {
+    let threads = ffi::thread_count(threads);
+    ffi::guard_status(|| unsafe { float_prefix_sum((a, out, len), kind, threads) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    use OverflowPolicy::{Checked, Overflowing, Saturating, Wrapping};
+
+    fn sum_i32(a: &[i32], policy: OverflowPolicy, out: &mut i32) -> MathStatus {
+        unsafe { array_sum_i32(a.as_ptr(), a.len(), policy, 1, out) }
+    }
+
+    fn product_i64(a: &[i64], policy: OverflowPolicy, out: &mut i64) -> MathStatus {
+        unsafe { array_product_i64(a.as_ptr(), a.len(), policy, 1, out) }
+    }
+
+    /// `len` pseudo-random values in `[-2^bits, 2^bits)`.
+    fn noise(len: usize, bits: u32) -> Vec<i64> {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> (63 - bits)) as i64 - (1 << bits)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_integer_sum_applies_the_policy_to_the_exact_result() {
+        let mut out = 0;
+        assert_eq!(
+            sum_i32(&[i32::MAX, 1, -1], Checked, &mut out),
+            MathStatus::Ok
+        );
+        assert_eq!(out, i32::MAX);
+
+        out = 7;
+        assert_eq!(
+            sum_i32(&[i32::MAX, 1], Checked, &mut out),
+            MathStatus::Overflow
+        );
+        assert_eq!(out, 7);
+        let text = ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "overflow: the sum of `a` overflows i32");
+        assert_eq!(sum_i32(&[i32::MAX, 1], Wrapping, &mut out), MathStatus::Ok);
+        assert_eq!(out, i32::MIN);
+        assert_eq!(
+            sum_i32(&[i32::MIN, -1, -1], Saturating, &mut out),
+            MathStatus::Ok
+        );
+        assert_eq!(out, i32::MIN);
+        assert_eq!(
+            sum_i32(&[i32::MAX, 1], Overflowing, &mut out),
+            MathStatus::Overflow
+        );
+        assert_eq!(out, i32::MIN);
+
+        let status = unsafe { array_sum_i32(std::ptr::null(), 0, Checked, 1, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, 0);
+        let status = unsafe { array_sum_i32(std::ptr::null(), 1, Checked, 1, &mut out) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_integer_product() {
+        let mut out = 0;
+        assert_eq!(product_i64(&[2, 3, -4], Checked, &mut out), MathStatus::Ok);
+        assert_eq!(out, -24);
+        assert_eq!(
+            product_i64(&[i64::MAX, 2, 0], Checked, &mut out),
+            MathStatus::Ok
+        );
+        assert_eq!(out, 0);
+        assert_eq!(
+            product_i64(&[i64::MIN, 1], Checked, &mut out),
+            MathStatus::Ok
+        );
+        assert_eq!(out, i64::MIN);
+        assert_eq!(product_i64(&[], Checked, &mut out), MathStatus::Ok);
+        assert_eq!(out, 1);
+
+        let twos = vec![2; 70];
+        assert_eq!(product_i64(&twos, Checked, &mut out), MathStatus::Overflow);
+        let text = ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "overflow: the product of `a` overflows i64");
+        assert_eq!(product_i64(&twos, Wrapping, &mut out), MathStatus::Ok);
+        assert_eq!(out, 0);
+        assert_eq!(
+            product_i64(&[i64::MIN, -1], Saturating, &mut out),
+            MathStatus::Ok
+        );
+        assert_eq!(out, i64::MAX);
+        assert_eq!(
+            product_i64(&[i64::MIN, -1], Overflowing, &mut out),
+            MathStatus::Overflow
+        );
+        assert_eq!(out, i64::MIN);
+
+        let mut small = 0;
+        let a = [-3i32, 1 << 20, 1 << 20];
+        let status = unsafe { array_product_i32(a.as_ptr(), 3, Saturating, 1, &mut small) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(small, i32::MIN);
+    }
+
+    #[test]
+    fn test_extrema() {
+        let a = [4i64, -2, 9, -2, 9];
+        let (mut value, mut index) = (0i64, 0usize);
+        unsafe {
+            assert_eq!(
+                array_reduce_min_i64(a.as_ptr(), 5, 1, &mut value),
+                MathStatus::Ok
+            );
+            assert_eq!(value, -2);
+            assert_eq!(
+                array_argmin_i64(a.as_ptr(), 5, 1, &mut index),
+                MathStatus::Ok
+            );
+            assert_eq!(index, 1);
+            assert_eq!(
+                array_reduce_max_i64(a.as_ptr(), 5, 1, &mut value),
+                MathStatus::Ok
+            );
+            assert_eq!(value, 9);
+            assert_eq!(
+                array_argmax_i64(a.as_ptr(), 5, 1, &mut index),
+                MathStatus::Ok
+            );
+            assert_eq!(index, 2);
+        }
+
+        let b = [f64::NAN, 1.5, -0.5, f64::NAN];
+        let mut x = 0.0;
+        unsafe {
+            assert_eq!(
+                array_reduce_min_f64(b.as_ptr(), 4, 1, &mut x),
+                MathStatus::Ok
+            );
+            assert_eq!(x, -0.5);
+            assert_eq!(
+                array_argmax_f64(b.as_ptr(), 4, 1, &mut index),
+                MathStatus::Ok
+            );
+            assert_eq!(index, 1);
+            assert_eq!(
+                array_reduce_max_f64(b.as_ptr(), 1, 1, &mut x),
+                MathStatus::Ok
+            );
+            assert!(x.is_nan());
+        }
+
+        let mut y = 0.0f32;
+        let status = unsafe { array_reduce_max_f32(std::ptr::null(), 0, 1, &mut y) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+        let text = ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "invalid argument: `a` is empty");
+    }
+
+    #[test]
+    fn test_integer_prefix_sums() {
+        let a = [3i32, -1, 4, 1];
+        let mut out = [0i32; 4];
+        let scan = |a: &[i32], out: &mut [i32], kind, policy| unsafe {
+            array_prefix_sum_i32(a.as_ptr(), out.as_mut_ptr(), a.len(), kind, policy, 1)
+        };
+        assert_eq!(
+            scan(&a, &mut out, ScanKind::Inclusive, Checked),
+            MathStatus::Ok
+        );
+        assert_eq!(out, [3, 2, 6, 7]);
+        assert_eq!(
+            scan(&a, &mut out, ScanKind::Exclusive, Checked),
+            MathStatus::Ok
+        );
+        assert_eq!(out, [0, 3, 2, 6]);
+
+        // Only the written prefixes have to fit.
+        let a = [i32::MAX, 1];
+        assert_eq!(
+            scan(&a, &mut out[..2], ScanKind::Exclusive, Checked),
+            MathStatus::Ok
+        );
+        assert_eq!(out[..2], [0, i32::MAX]);
+
+        let a = [i32::MAX, 1, -5];
+        out = [9; 4];
+        let status = scan(&a, &mut out[..3], ScanKind::Inclusive, Checked);
+        assert_eq!(status, MathStatus::Overflow);
+        assert_eq!(out, [9; 4]);
+        let text = ffi::take_string(error::math_last_error_message());
+        assert_eq!(text, "overflow: the prefix sum at element 1 overflows i32");
+        let status = scan(&a, &mut out[..3], ScanKind::Inclusive, Saturating);
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out[..3], [i32::MAX, i32::MAX, i32::MAX - 4]);
+        let status = scan(&a, &mut out[..3], ScanKind::Inclusive, Overflowing);
+        assert_eq!(status, MathStatus::Overflow);
+        assert_eq!(out[..3], [i32::MAX, i32::MIN, i32::MAX - 4]);
+
+        let mut in_place = [1i64, 2, 3, 4];
+        let status = unsafe {
+            array_prefix_sum_i64(
+                in_place.as_ptr(),
+                in_place.as_mut_ptr(),
+                4,
+                ScanKind::Inclusive,
+                Wrapping,
+                1,
+            )
+        };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(in_place, [1, 3, 6, 10]);
+
+        let status = unsafe {
+            array_prefix_sum_i64(
+                in_place.as_ptr(),
+                std::ptr::null_mut(),
+                4,
+                ScanKind::Inclusive,
+                Checked,
+                1,
+            )
+        };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_results_do_not_depend_on_the_thread_count() {
+        let len = 5 * BLOCK + 123;
+        let ints = noise(len, 40);
+        let floats: Vec<f64> = ints.iter().map(|&x| x as f64 / 3.0).collect();
+
+        let mut expected = vec![0i64; len];
+        let mut acc = 0;
+        for (e, &x) in expected.iter_mut().zip(&ints) {
+            acc += x;
+            *e = acc;
+        }
+
+        for threads in [1, 2, 3, 0] {
+            let mut sum = 0;
+            let status = unsafe { array_sum_i64(ints.as_ptr(), len, Checked, threads, &mut sum) };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(sum, ints.iter().sum::<i64>());
+
+            let mut index = 0;
+            let status = unsafe { array_argmax_i64(ints.as_ptr(), len, threads, &mut index) };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(
+                index,
+                (0..len)
+                    .max_by_key(|&i| (ints[i], std::cmp::Reverse(i)))
+                    .unwrap()
+            );
+
+            let mut prefix = ints.clone();
+            let status = unsafe {
+                array_prefix_sum_i64(
+                    prefix.as_ptr(),
+                    prefix.as_mut_ptr(),
+                    len,
+                    ScanKind::Inclusive,
+                    Checked,
+                    threads,
+                )
+            };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(prefix, expected);
+
+            let mut float_sum = 0.0;
+            let status = unsafe { array_sum_f64(floats.as_ptr(), len, threads, &mut float_sum) };
+            assert_eq!(status, MathStatus::Ok);
+            let mut single = 0.0;
+            unsafe { array_sum_f64(floats.as_ptr(), len, 1, &mut single) };
+            assert_eq!(float_sum.to_bits(), single.to_bits());
+
+            let mut scan = vec![0.0; len];
+            let status = unsafe {
+                array_prefix_sum_f64(
+                    floats.as_ptr(),
+                    scan.as_mut_ptr(),
+                    len,
+                    ScanKind::Exclusive,
+                    threads,
+                )
+            };
+            assert_eq!(status, MathStatus::Ok);
+            assert_eq!(scan[0], 0.0);
+            assert!((scan[len - 1] + floats[len - 1] - float_sum).abs() <= 1e-3 * float_sum.abs());
+        }
+    }
+}
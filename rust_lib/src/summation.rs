@@ -0,0 +1,631 @@
+//! Accurate floating-point sums and dot products.
+//!
+//! `array_accurate_sum_f32`/`_f64` and `array_dot_f32`/`_f64` take a
+//! [`SumAlgorithm`]: Kahan's or Neumaier's compensated summation, pairwise
+//! summation, or the exact result rounded once to nearest (ties to even),
+//! computed with a superaccumulator. The compensated and pairwise algorithms
+//! work in the precision of the element type, and for dot products they sum
+//! the rounded products `a[i] * b[i]`.
+//!
+//! Like the reductions in `reductions`, the arrays are cut into fixed blocks
+//! that are spread over `threads` threads (0 picks the number of available
+//! cores) and combined in a fixed order, so the result is bit-for-bit the same
+//! for every thread count.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::reductions::map_blocks;
+use crate::{MathStatus, ffi};
+
+/// How `array_accurate_sum` and `array_dot` add up their terms.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SumAlgorithm {
+    /// Kahan's compensated summation.
+    Kahan = 0,
+    /// Neumaier's variant of Kahan summation, which stays accurate when a
+    /// term is larger than the running sum.
+    Neumaier = 1,
+    /// Pairwise summation, whose error grows with the logarithm of the length.
+    Pairwise = 2,
+    /// The exact result, rounded once.
+    Exact = 3,
+}
+
+trait Float:
+    Copy + Send + Sync + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const NAN: Self;
+    /// Significand bits, including the implicit one.
+    const PRECISION: u32;
+    /// Exponent of the smallest subnormal.
+    const MIN_EXPONENT: i32;
+    /// Bit pattern of positive infinity.
+    const INFINITY_BITS: u64;
+
+    fn abs(self) -> Self;
+    fn to_f64(self) -> f64;
+    fn from_bits(negative: bool, magnitude: u64) -> Self;
+}
+
+impl Float for f32 {
+    const ZERO: Self = 0.0;
+    const NAN: Self = f32::NAN;
+    const PRECISION: u32 = f32::MANTISSA_DIGITS;
+    const MIN_EXPONENT: i32 = -149;
+    const INFINITY_BITS: u64 = f32::INFINITY.to_bits() as u64;
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_bits(negative: bool, magnitude: u64) -> Self {
+        f32::from_bits((negative as u32) << 31 | magnitude as u32)
+    }
+}
+
+impl Float for f64 {
+    const ZERO: Self = 0.0;
+    const NAN: Self = f64::NAN;
+    const PRECISION: u32 = f64::MANTISSA_DIGITS;
+    const MIN_EXPONENT: i32 = -1074;
+    const INFINITY_BITS: u64 = f64::INFINITY.to_bits();
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_bits(negative: bool, magnitude: u64) -> Self {
+        f64::from_bits((negative as u64) << 63 | magnitude)
+    }
+}
+
+/// Kahan's running sum and the negated error of the last addition.
+#[derive(Debug, Clone, Copy)]
+struct Kahan<T> {
+    sum: T,
+    error: T,
+}
+
+impl<T: Float> Kahan<T> {
+    const ZERO: Self = Kahan {
+        sum: T::ZERO,
+        error: T::ZERO,
+    };
+
+    fn add(self, term: T) -> Self {
+        let y = term - self.error;
+        let sum = self.sum + y;
+        Kahan {
+            sum,
+            error: (sum - self.sum) - y,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        self.add(other.sum).add(T::ZERO - other.error)
+    }
+}
+
+/// Neumaier's running sum and the accumulated rounding errors.
+#[derive(Debug, Clone, Copy)]
+struct Neumaier<T> {
+    sum: T,
+    compensation: T,
+}
+
+impl<T: Float> Neumaier<T> {
+    const ZERO: Self = Neumaier {
+        sum: T::ZERO,
+        compensation: T::ZERO,
+    };
+
+    fn add(self, term: T) -> Self {
+        let sum = self.sum + term;
+        let error = if self.sum.abs() >= term.abs() {
+            (self.sum - sum) + term
+        } else {
+            (term - sum) + self.sum
+        };
+        Neumaier {
+            sum,
+            compensation: self.compensation + error,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        let mut merged = self.add(other.sum);
+        merged.compensation = merged.compensation + other.compensation;
+        merged
+    }
+
+    fn value(self) -> T {
+        self.sum + self.compensation
+    }
+}
+
+/// Sum `term(i)` over `range` by halving it down to runs of `PAIRWISE_RUN`.
+fn pairwise<T: Float>(range: std::ops::Range<usize>, term: &impl Fn(usize) -> T) -> T {
+    const PAIRWISE_RUN: usize = 32;
+    if range.len() <= PAIRWISE_RUN {
+        return range.fold(T::ZERO, |acc, i| acc + term(i));
+    }
+    let mid = range.start + range.len() / 2;
+    pairwise(range.start..mid, term) + pairwise(mid..range.end, term)
+}
+
+/// Number of 32-bit digits in a [`Superaccumulator`].
+const DIGITS: usize = 136;
+/// The accumulator counts in units of 2^-2148, the smallest product of two
+/// subnormal doubles.
+const UNIT_EXPONENT: i32 = 2 * -1074;
+/// Additions between carry propagations, far below the 2^31 that a digit
+/// could absorb.
+const NORMALIZE_EVERY: u32 = 1 << 20;
+
+/// `x` as `(negative, m, e)` with `|x| = m * 2^e` and `e >= -1074`.
+fn decompose(x: f64) -> (bool, u64, i32) {
+    let bits = x.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    match biased {
+        0 => (x.is_sign_negative(), fraction, -1074),
+        _ => (x.is_sign_negative(), fraction | 1 << 52, biased - 1075),
+    }
+}
+
+/// An exact fixed-point sum of doubles and of products of two doubles.
+///
+/// Digit `k` holds bits `32k..32k + 32` of the sum in units of
+/// 2^[`UNIT_EXPONENT`]. Digits may temporarily leave `0..2^32` between
+/// carry propagations; the top digit carries the sign, with enough headroom
+/// for 2^64 terms of any size.
+#[derive(Debug, Clone)]
+struct Superaccumulator {
+    digits: [i64; DIGITS],
+    pending: u32,
+    nan: bool,
+    positive_infinity: bool,
+    negative_infinity: bool,
+}
+
+impl Superaccumulator {
+    fn new() -> Self {
+        Superaccumulator {
+            digits: [0; DIGITS],
+            pending: 0,
+            nan: false,
+            positive_infinity: false,
+            negative_infinity: false,
+        }
+    }
+
+    fn add(&mut self, x: f64) {
+        if !x.is_finite() {
+            return self.add_special(x);
+        }
+        let (negative, mantissa, exponent) = decompose(x);
+        self.add_shifted(negative, mantissa, (exponent - UNIT_EXPONENT) as usize);
+    }
+
+    fn add_product(&mut self, a: f64, b: f64) {
+        if !a.is_finite() || !b.is_finite() {
+            return self.add_special(a * b);
+        }
+        let (a_negative, a_mantissa, a_exponent) = decompose(a);
+        let (b_negative, b_mantissa, b_exponent) = decompose(b);
+        let product = a_mantissa as u128 * b_mantissa as u128;
+        let position = (a_exponent + b_exponent - UNIT_EXPONENT) as usize;
+        let negative = a_negative != b_negative;
+        self.add_shifted(negative, product as u64, position);
+        self.add_shifted(negative, (product >> 64) as u64, position + 64);
+    }
+
+    fn add_special(&mut self, x: f64) {
+        if x.is_nan() {
+            self.nan = true;
+        } else if x > 0.0 {
+            self.positive_infinity = true;
+        } else {
+            self.negative_infinity = true;
+        }
+    }
+
+    /// Add `±value * 2^position` units.
+    fn add_shifted(&mut self, negative: bool, value: u64, position: usize) {
+        if value == 0 {
+            return;
+        }
+        let (index, shift) = (position / 32, position % 32);
+        let wide = (value as u128) << shift;
+        for (k, digit) in self.digits[index..index + 3].iter_mut().enumerate() {
+            let part = (wide >> (32 * k)) as i64 & 0xffff_ffff;
+            *digit += if negative { -part } else { part };
+        }
+        self.pending += 1;
+        if self.pending == NORMALIZE_EVERY {
+            self.normalize();
+        }
+    }
+
+    /// Propagate carries so that every digit but the top one is in `0..2^32`.
+    fn normalize(&mut self) {
+        let mut carry = 0;
+        for digit in &mut self.digits[..DIGITS - 1] {
+            let value = *digit + carry;
+            *digit = value & 0xffff_ffff;
+            carry = value >> 32;
+        }
+        self.digits[DIGITS - 1] += carry;
+        self.pending = 0;
+    }
+
+    fn merge(mut self, other: &Superaccumulator) -> Self {
+        self.normalize();
+        for (digit, &theirs) in self.digits.iter_mut().zip(&other.digits) {
+            *digit += theirs;
+        }
+        self.normalize();
+        self.nan |= other.nan;
+        self.positive_infinity |= other.positive_infinity;
+        self.negative_infinity |= other.negative_infinity;
+        self
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.digits[i / 32] >> (i % 32) & 1 == 1
+    }
+
+    /// The 64 bits starting at bit `i`; only valid after [`Self::normalize`].
+    fn bits_from(&self, i: usize) -> u64 {
+        let digit = |k: usize| self.digits.get(k).map_or(0, |&d| d as u128);
+        let k = i / 32;
+        let wide = digit(k) | digit(k + 1) << 32 | digit(k + 2) << 64;
+        (wide >> (i % 32)) as u64
+    }
+
+    fn any_below(&self, i: usize) -> bool {
+        let k = i / 32;
+        self.digits[..k].iter().any(|&d| d != 0) || self.digits[k] & ((1 << (i % 32)) - 1) != 0
+    }
+
+    /// The sum rounded to nearest, ties to even.
+    fn round<T: Float>(mut self) -> T {
+        if self.nan || self.positive_infinity && self.negative_infinity {
+            return T::NAN;
+        }
+        if self.positive_infinity || self.negative_infinity {
+            return T::from_bits(self.negative_infinity, T::INFINITY_BITS);
+        }
+        self.normalize();
+        let negative = self.digits[DIGITS - 1] < 0;
+        if negative {
+            self.digits.iter_mut().for_each(|d| *d = -*d);
+            self.normalize();
+        }
+        let Some(top) = (0..DIGITS).rev().find(|&k| self.digits[k] != 0) else {
+            return T::ZERO;
+        };
+        let highest = 32 * top + 63 - self.digits[top].leading_zeros() as usize;
+        // Bits below the smallest subnormal of `T`, or below its precision,
+        // are rounded away. `drop - subnormal` is then the biased exponent
+        // minus one, so that adding the mantissa (with its leading one)
+        // assembles the bit pattern, and a carry out of the mantissa moves
+        // to the next binade.
+        let subnormal = (T::MIN_EXPONENT - UNIT_EXPONENT) as usize;
+        let drop = subnormal.max((highest + 1).saturating_sub(T::PRECISION as usize));
+        let kept = self.bits_from(drop) & ((1 << T::PRECISION) - 1);
+        let half = drop > 0 && self.bit(drop - 1);
+        let sticky = drop > 1 && self.any_below(drop - 1);
+        let mantissa = kept + (half && (sticky || kept & 1 == 1)) as u64;
+        let magnitude = (((drop - subnormal) as u64) << (T::PRECISION - 1)) + mantissa;
+        T::from_bits(negative, magnitude.min(T::INFINITY_BITS))
+    }
+}
+
+/// Add up `term(i)` for `i < len` with `algorithm`; `exact_term` adds the
+/// exact value of term `i` to a superaccumulator.
+fn accumulate<T: Float>(
+    len: usize,
+    algorithm: SumAlgorithm,
+    threads: usize,
+    term: impl Fn(usize) -> T + Sync,
+    exact_term: impl Fn(&mut Superaccumulator, usize) + Sync,
+) -> T {
+    match algorithm {
+        SumAlgorithm::Kahan => {
+            map_blocks(len, threads, |range| {
+                range.fold(Kahan::ZERO, |acc, i| acc.add(term(i)))
+            })
+            .into_iter()
+            .fold(Kahan::ZERO, Kahan::merge)
+            .sum
+        }
+        SumAlgorithm::Neumaier => map_blocks(len, threads, |range| {
+            range.fold(Neumaier::ZERO, |acc, i| acc.add(term(i)))
+        })
+        .into_iter()
+        .fold(Neumaier::ZERO, Neumaier::merge)
+        .value(),
+        SumAlgorithm::Pairwise => {
+            let sums = map_blocks(len, threads, |range| pairwise(range, &term));
+            pairwise(0..sums.len(), &|i| sums[i])
+        }
+        SumAlgorithm::Exact => map_blocks(len, threads, |range| {
+            let mut acc = Superaccumulator::new();
+            range.for_each(|i| exact_term(&mut acc, i));
+            acc
+        })
+        .iter()
+        .fold(Superaccumulator::new(), Superaccumulator::merge)
+        .round(),
+    }
+}
+
+/// # Safety
+///
+/// `a` must be null or valid for reading `len` elements, and `out` null or
+/// valid for writing a single `T`.
+unsafe fn sum<T: Float>(
+    (a, len): (*const T, usize),
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { ffi::slice_ref(a, len, "a") }?;
+        let total = accumulate(
+            len,
+            algorithm,
+            ffi::thread_count(threads),
+            |i| a[i],
+            |acc, i| acc.add(a[i].to_f64()),
+        );
+        unsafe { ffi::write_out(out, "out", total) }
+    })
+}
+
+/// # Safety
+///
+/// `a` and `b` must be null or valid for reading `len` elements, and `out`
+/// null or valid for writing a single `T`.
+unsafe fn dot<T: Float>(
+    (a, b, len): (*const T, *const T, usize),
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut T,
+) -> MathStatus {
+    ffi::guard_status(|| {
+        let a = unsafe { ffi::slice_ref(a, len, "a") }?;
+        let b = unsafe { ffi::slice_ref(b, len, "b") }?;
+        let total = accumulate(
+            len,
+            algorithm,
+            ffi::thread_count(threads),
+            |i| a[i] * b[i],
+            |acc, i| acc.add_product(a[i].to_f64(), b[i].to_f64()),
+        );
+        unsafe { ffi::write_out(out, "out", total) }
+    })
+}
+
+/// Sum the `float` array `a` with `algorithm`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_accurate_sum_f32(
+    a: *const f32,
+    len: usize,
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe { sum((a, len), algorithm, threads, out) }
+}
+
+/// Sum the `double` array `a` with `algorithm`; an empty array sums to 0.
+///
+/// # Safety
+///
+/// `a` must be null (with `len == 0`) or valid for reading `len` elements,
+/// and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_accurate_sum_f64(
+    a: *const f64,
+    len: usize,
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe { sum((a, len), algorithm, threads, out) }
+}
+
+/// Compute the dot product of the `float` arrays `a` and `b` with
+/// `algorithm`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null or valid for writing a single `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_dot_f32(
+    a: *const f32,
+    b: *const f32,
+    len: usize,
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut f32,
+) -> MathStatus {
+    unsafe { dot((a, b, len), algorithm, threads, out) }
+}
+
+/// Compute the dot product of the `double` arrays `a` and `b` with
+/// `algorithm`.
+///
+/// # Safety
+///
+/// `a` and `b` must be null (with `len == 0`) or valid for reading `len`
+/// elements, and `out` must be null or valid for writing a single `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn array_dot_f64(
+    a: *const f64,
+    b: *const f64,
+    len: usize,
+    algorithm: SumAlgorithm,
+    threads: u32,
+    out: *mut f64,
+) -> MathStatus {
+    unsafe { dot((a, b, len), algorithm, threads, out) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use SumAlgorithm::{Exact, Kahan, Neumaier, Pairwise};
+
+    fn sum_f64(a: &[f64], algorithm: SumAlgorithm) -> f64 {
+        let mut out = f64::NAN;
+        let status = unsafe { array_accurate_sum_f64(a.as_ptr(), a.len(), algorithm, 1, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        out
+    }
+
+    #[test]
+    fn test_algorithms_differ_on_cancellation() {
+        let a = [1.0, 1e100, 1.0, -1e100];
+        assert_eq!(sum_f64(&a, Kahan), 0.0);
+        assert_eq!(sum_f64(&a, Pairwise), 0.0);
+        assert_eq!(sum_f64(&a, Neumaier), 2.0);
+        assert_eq!(sum_f64(&a, Exact), 2.0);
+
+        let tenths = [0.1; 10];
+        assert_eq!(tenths.iter().sum::<f64>(), 0.9999999999999999);
+        for algorithm in [Kahan, Neumaier, Exact] {
+            assert_eq!(sum_f64(&tenths, algorithm), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_exact_sum_rounds_once() {
+        let tiny = 2f64.powi(-53);
+        assert_eq!(sum_f64(&[1.0, tiny], Exact), 1.0);
+        assert_eq!(
+            sum_f64(&[1.0, tiny, 2f64.powi(-100)], Exact),
+            1.0 + 2f64.powi(-52)
+        );
+        assert_eq!(sum_f64(&[1.0, 3.0 * tiny], Exact), 1.0 + 2f64.powi(-51));
+        assert_eq!(sum_f64(&[1e308, 1e308, -1e308], Exact), 1e308);
+        assert_eq!(sum_f64(&[f64::MAX, f64::MAX], Exact), f64::INFINITY);
+        assert_eq!(sum_f64(&[-f64::MAX, -f64::MAX], Exact), f64::NEG_INFINITY);
+        assert_eq!(sum_f64(&[5e-324, 5e-324], Exact), 1e-323);
+        assert_eq!(
+            sum_f64(&[f64::MIN_POSITIVE, -5e-324], Exact),
+            f64::from_bits(f64::MIN_POSITIVE.to_bits() - 1)
+        );
+        assert_eq!(sum_f64(&[0.5, -0.5], Exact).to_bits(), 0);
+
+        // Rounding the exact sum to double first would make this a tie.
+        let a = [1.0f32, 2f32.powi(-24), 2f32.powi(-60)];
+        let mut out = 0.0;
+        let status = unsafe { array_accurate_sum_f32(a.as_ptr(), 3, Exact, 1, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, 1.0 + f32::EPSILON);
+    }
+
+    #[test]
+    fn test_exact_sum_of_special_values() {
+        assert_eq!(sum_f64(&[f64::INFINITY, 1.0, 1e308], Exact), f64::INFINITY);
+        assert!(sum_f64(&[f64::INFINITY, f64::NEG_INFINITY], Exact).is_nan());
+        assert!(sum_f64(&[1.0, f64::NAN], Exact).is_nan());
+    }
+
+    #[test]
+    fn test_dot_products() {
+        // The exact products are 1 - 2^-60 and -1.
+        let a = [1.0 + 2f64.powi(-30), -1.0];
+        let b = [1.0 - 2f64.powi(-30), 1.0];
+        let mut out = f64::NAN;
+        let status = unsafe { array_dot_f64(a.as_ptr(), b.as_ptr(), 2, Exact, 1, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, -(2f64.powi(-60)));
+        let status = unsafe { array_dot_f64(a.as_ptr(), b.as_ptr(), 2, Neumaier, 1, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(out, 0.0);
+
+        let (x, y) = ([3.0f32, 1e30, -1e30], [0.5f32, 1e8, 1e8]);
+        let mut single = f32::NAN;
+        let status = unsafe { array_dot_f32(x.as_ptr(), y.as_ptr(), 3, Exact, 1, &mut single) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(single, 1.5);
+
+        let status =
+            unsafe { array_dot_f32(std::ptr::null(), std::ptr::null(), 0, Kahan, 1, &mut single) };
+        assert_eq!(status, MathStatus::Ok);
+        assert_eq!(single, 0.0);
+        let status =
+            unsafe { array_dot_f32(x.as_ptr(), std::ptr::null(), 3, Kahan, 1, &mut single) };
+        assert_eq!(status, MathStatus::InvalidArgument);
+    }
+
+    #[test]
+    fn test_results_are_reproducible_across_thread_counts() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let a: Vec<f64> = (0..5 * (1 << 16) + 7)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let mantissa = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+                mantissa * 2f64.powi((state % 64) as i32 - 32)
+            })
+            .collect();
+        let reversed: Vec<f64> = a.iter().rev().copied().collect();
+
+        for algorithm in [Kahan, Neumaier, Pairwise, Exact] {
+            let expected = sum_f64(&a, algorithm);
+            for threads in [2, 3, 0] {
+                let mut out = f64::NAN;
+                let status = unsafe {
+                    array_accurate_sum_f64(a.as_ptr(), a.len(), algorithm, threads, &mut out)
+                };
+                assert_eq!(status, MathStatus::Ok);
+                assert_eq!(
+                    out.to_bits(),
+                    expected.to_bits(),
+                    "{algorithm:?}, {threads} threads"
+                );
+            }
+        }
+        assert_eq!(sum_f64(&a, Exact), sum_f64(&reversed, Exact));
+
+        let mut out = f64::NAN;
+        let status =
+            unsafe { array_dot_f64(a.as_ptr(), reversed.as_ptr(), a.len(), Exact, 4, &mut out) };
+        assert_eq!(status, MathStatus::Ok);
+        let mut single = f64::NAN;
+        unsafe {
+            array_dot_f64(
+                reversed.as_ptr(),
+                a.as_ptr(),
+                a.len(),
+                Exact,
+                1,
+                &mut single,
+            )
+        };
+        assert_eq!(out, single);
+    }
+}
@@ -1,3 +1,5 @@
+#include <cstdio>
+
 #include "utils.h"
 #include "rust_math_lib.h"
 
@@ -7,6 +9,18 @@ int main() {
     
     Utils::print_result("15 + 27", sum);
     Utils::print_result("5!", fact);
+
+    uint64_t checked = 0;
+    if (factorial_checked(25, &checked) == MATH_STATUS_OVERFLOW) {
+        Utils::print_result("25! overflows u64, status", MATH_STATUS_OVERFLOW);
+    }
+
+    FactorialApprox approx = factorial_approx(1000);
+    std::printf("1000! ~ %.4fe%llu\n", approx.MANTISSA, (unsigned long long)approx.EXPONENT);
+
+    char *wide = uint128_to_string(factorial_u128(34));
+    std::printf("34! = %s\n", wide);
+    math_free_string(wide);
     
     return 0;
 }